@@ -0,0 +1,496 @@
+// Cellular automata: a rectangular grid of cells that all update simultaneously, each according to
+// a Rule applied to the cell and its neighbors.
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::str::FromStr;
+use anyhow::{bail, ensure, Context, Error, Result};
+
+use crate::euclid::{point, vector, Point, Vector};
+use crate::terminal::{Color, TerminalImage, TerminalRender};
+
+// The cells, relative to a cell, that its next state depends on
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Neighborhood {
+    // The eight surrounding cells
+    Moore,
+    // The four orthogonally adjacent cells
+    VonNeumann,
+    Custom(Vec<Vector>),
+}
+
+impl Neighborhood {
+    // The neighbors' offsets, in reading order (top to bottom, left to right) for Moore and
+    // VonNeumann and as given for Custom.
+    pub fn offsets(&self) -> Vec<Vector> {
+        match self {
+            Neighborhood::Moore => (-1..=1).flat_map(|y| (-1..=1).map(move |x| vector(x, y)))
+                .filter(|v| *v != vector(0, 0)).collect(),
+            Neighborhood::VonNeumann => vec![vector(0, -1), vector(-1, 0), vector(1, 0), vector(0, 1)],
+            Neighborhood::Custom(offsets) => offsets.clone(),
+        }
+    }
+
+    // This neighborhood plus the cell itself, in reading order; e.g. a 3x3 block for Moore.
+    pub fn with_center(&self) -> Neighborhood {
+        let mut offsets = self.offsets();
+        if !offsets.contains(&vector(0, 0)) {
+            offsets.push(vector(0, 0));
+        }
+        offsets.sort_by_key(|v| (v.y, v.x));
+        Neighborhood::Custom(offsets)
+    }
+
+    // How far the neighborhood extends from the cell in any direction
+    fn reach(offsets: &[Vector]) -> i32 {
+        offsets.iter().map(|v| v.x.abs().max(v.y.abs())).max().unwrap_or(0)
+    }
+}
+
+pub trait Rule<S> {
+    fn neighborhood(&self) -> Neighborhood;
+
+    // The next state of a cell, given its current state and its neighbors' states (in the order of
+    // the neighborhood's offsets).
+    fn next(&self, cell: &S, neighbors: &[S]) -> S;
+}
+
+// A Rule defined by a closure
+pub struct FnRule<F> {
+    neighborhood: Neighborhood,
+    next: F,
+}
+
+pub fn rule<S, F: Fn(&S, &[S]) -> S>(neighborhood: Neighborhood, next: F) -> FnRule<F> {
+    FnRule { neighborhood, next }
+}
+
+impl<S, F: Fn(&S, &[S]) -> S> Rule<S> for FnRule<F> {
+    fn neighborhood(&self) -> Neighborhood { self.neighborhood.clone() }
+
+    fn next(&self, cell: &S, neighbors: &[S]) -> S { (self.next)(cell, neighbors) }
+}
+
+// A two-state rule where a cell's next state depends only on whether it's alive and how many of
+// its neighbors are, such as Conway's Game of Life (B3/S23).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Totalistic {
+    neighborhood: Neighborhood,
+    // Indexed by the number of live neighbors
+    birth: Vec<bool>,
+    survival: Vec<bool>,
+}
+
+impl Totalistic {
+    pub fn new(neighborhood: Neighborhood, birth: &[usize], survival: &[usize]) -> Result<Totalistic> {
+        let size = neighborhood.offsets().len() + 1;
+        let counts = |counts: &[usize]| -> Result<Vec<bool>> {
+            let mut ret = vec![false; size];
+            for &count in counts {
+                ensure!(count < size, "{} neighbors is more than the neighborhood holds", count);
+                ret[count] = true;
+            }
+            Ok(ret)
+        };
+        let (birth, survival) = (counts(birth)?, counts(survival)?);
+        Ok(Totalistic { neighborhood, birth, survival })
+    }
+
+    pub fn conway() -> Totalistic {
+        Totalistic::new(Neighborhood::Moore, &[3], &[2, 3]).expect("Valid")
+    }
+}
+
+impl Rule<bool> for Totalistic {
+    fn neighborhood(&self) -> Neighborhood { self.neighborhood.clone() }
+
+    fn next(&self, cell: &bool, neighbors: &[bool]) -> bool {
+        let alive = neighbors.iter().filter(|n| **n).count();
+        if *cell { self.survival[alive] } else { self.birth[alive] }
+    }
+}
+
+// Parses Moore-neighborhood rules in B/S notation, e.g. B36/S23
+impl FromStr for Totalistic {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parse = |part: &str, prefix: char| -> Result<Vec<usize>> {
+            let digits = part.strip_prefix(prefix).or_else(|| part.strip_prefix(prefix.to_ascii_lowercase()))
+                .with_context(|| format!("Expected {} in {:?}", prefix, s))?;
+            digits.chars().map(|c| c.to_digit(10).map(|d| d as usize).with_context(|| format!("Invalid count: {}", c))).collect()
+        };
+        match s.trim().split('/').collect::<Vec<_>>()[..] {
+            [birth, survival] => Totalistic::new(Neighborhood::Moore, &parse(birth, 'B')?, &parse(survival, 'S')?),
+            _ => bail!("Expected B.../S..., found {:?}", s),
+        }
+    }
+}
+
+impl fmt::Display for Totalistic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = |set: &[bool]| set.iter().enumerate().filter(|(_, b)| **b).map(|(i, _)| i.to_string()).collect::<String>();
+        write!(f, "B{}/S{}", counts(&self.birth), counts(&self.survival))
+    }
+}
+
+// A two-state rule with an entry for every combination of neighbor states. The first neighbor is
+// the most significant bit of the index, so with Moore.with_center() this is the format of the
+// Day 20 image enhancement algorithm.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LookupTable {
+    neighborhood: Neighborhood,
+    table: Vec<bool>,
+}
+
+impl LookupTable {
+    pub fn new(neighborhood: Neighborhood, table: Vec<bool>) -> Result<LookupTable> {
+        let size = neighborhood.offsets().len();
+        ensure!(size < usize::BITS as usize && table.len() == 1 << size,
+            "A neighborhood of {} cells needs {} entries, found {}", size, 1usize << size.min(63), table.len());
+        Ok(LookupTable { neighborhood, table })
+    }
+
+    pub fn table(&self) -> &[bool] { &self.table }
+
+    // The equivalent Totalistic rule, if the neighborhood includes the cell itself and the table
+    // only depends on the cell's state and the number of other live cells (such as Day 20's
+    // conway.txt).
+    pub fn as_totalistic(&self) -> Option<Totalistic> {
+        let offsets = self.neighborhood.offsets();
+        let center = offsets.iter().position(|v| *v == vector(0, 0))?;
+        let center_bit = 1 << (offsets.len() - 1 - center);
+        let mut outcomes: [Vec<Option<bool>>; 2] = [vec![None; offsets.len()], vec![None; offsets.len()]];
+        for (index, &next) in self.table.iter().enumerate() {
+            let alive = index & center_bit != 0;
+            let neighbors = (index & !center_bit).count_ones() as usize;
+            match outcomes[alive as usize][neighbors].replace(next) {
+                Some(previous) if previous != next => return None,
+                _ => {},
+            }
+        }
+        let counts = |outcomes: &[Option<bool>]| -> Vec<usize> {
+            outcomes.iter().enumerate().filter(|(_, o)| **o == Some(true)).map(|(i, _)| i).collect()
+        };
+        let mut others = offsets;
+        others.remove(center);
+        let neighborhood = [Neighborhood::Moore, Neighborhood::VonNeumann].into_iter()
+            .find(|n| n.offsets() == others)
+            .unwrap_or(Neighborhood::Custom(others));
+        Some(Totalistic::new(neighborhood, &counts(&outcomes[0]), &counts(&outcomes[1])).expect("Counts are in range"))
+    }
+}
+
+impl Rule<bool> for LookupTable {
+    fn neighborhood(&self) -> Neighborhood { self.neighborhood.clone() }
+
+    fn next(&self, _cell: &bool, neighbors: &[bool]) -> bool {
+        self.table[neighbors.iter().fold(0, |index, &n| index << 1 | n as usize)]
+    }
+}
+
+// What lies beyond the edges of the grid
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Edges {
+    // Cells outside the grid are in the background state, and never change
+    Fixed,
+    // The grid wraps around on itself, like a torus
+    Wrapping,
+    // The grid is infinite; cells outside the tracked area are all in the background state, which
+    // is updated by the rule like any other cell (so it may e.g. flash on and off).
+    Infinite,
+}
+
+// A cell state with a color, so that the automaton can be rendered to the terminal
+pub trait Colored {
+    fn color(&self) -> Color;
+}
+
+impl Colored for bool {
+    fn color(&self) -> Color { if *self { Color::WHITE } else { Color::BLACK } }
+}
+
+#[derive(Clone, Debug)]
+pub struct Automaton<S> {
+    min: Point,
+    width: usize,
+    height: usize,
+    cells: Vec<S>,
+    background: S,
+    edges: Edges,
+    generation: usize,
+}
+
+impl<S: Clone + PartialEq> Automaton<S> {
+    // Creates an automaton covering the bounding box of cells, with any missing cells in the
+    // background state.
+    pub fn new(cells: HashMap<Point, S>, background: S, edges: Edges) -> Result<Automaton<S>> {
+        let (min, max) = Point::bounding_box(cells.keys()).context("No cells")?;
+        let (width, height) = ((max.x - min.x + 1) as usize, (max.y - min.y + 1) as usize);
+        let cells = (min.y..=max.y).flat_map(|y| (min.x..=max.x).map(move |x| point(x, y)))
+            .map(|p| cells.get(&p).unwrap_or(&background).clone())
+            .collect();
+        Ok(Automaton { min, width, height, cells, background, edges, generation: 0 })
+    }
+
+    // The number of steps taken
+    pub fn generation(&self) -> usize { self.generation }
+
+    pub fn background(&self) -> &S { &self.background }
+
+    // The minimum and maximum corners of the tracked area, or None if it's empty (which is only
+    // possible with Infinite edges).
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        if self.cells.is_empty() { return None; }
+        Some((self.min, self.min + vector(self.width as i32 - 1, self.height as i32 - 1)))
+    }
+
+    // The number of cells in the tracked area
+    pub fn len(&self) -> usize { self.cells.len() }
+
+    pub fn is_empty(&self) -> bool { self.cells.is_empty() }
+
+    fn index(&self, p: Point) -> Option<usize> {
+        let (mut x, mut y) = (p.x - self.min.x, p.y - self.min.y);
+        if self.edges == Edges::Wrapping {
+            x = x.rem_euclid(self.width as i32);
+            y = y.rem_euclid(self.height as i32);
+        }
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        Some(y as usize * self.width + x as usize)
+    }
+
+    pub fn get(&self, p: Point) -> &S {
+        self.index(p).map(|i| &self.cells[i]).unwrap_or(&self.background)
+    }
+
+    // The cells in the tracked area, in reading order
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &S)> + '_ {
+        self.cells.iter().enumerate()
+            .map(move |(i, s)| (self.min + vector((i % self.width) as i32, (i / self.width) as i32), s))
+    }
+
+    pub fn count(&self, predicate: impl Fn(&S) -> bool) -> usize {
+        self.cells.iter().filter(|s| predicate(s)).count()
+    }
+
+    // The area an Infinite grid needs to track after the next step: everything that isn't in the
+    // background state, plus anything within reach of it.
+    fn next_area(&self, reach: i32) -> (Point, usize, usize) {
+        let active = self.iter().filter(|(_, s)| **s != self.background).map(|(p, _)| p).collect::<Vec<_>>();
+        match Point::bounding_box(&active) {
+            Some((min, max)) => (
+                min + vector(-reach, -reach),
+                (max.x - min.x + 1 + 2 * reach) as usize,
+                (max.y - min.y + 1 + 2 * reach) as usize),
+            None => (self.min, 0, 0),
+        }
+    }
+
+    // Applies the rule to every cell, returning whether any cell (or the background) changed.
+    pub fn step(&mut self, rule: &impl Rule<S>) -> bool {
+        let offsets = rule.neighborhood().offsets();
+        let (min, width, height) = match self.edges {
+            Edges::Infinite => self.next_area(Neighborhood::reach(&offsets)),
+            _ => (self.min, self.width, self.height),
+        };
+
+        let mut changed = false;
+        let mut cells = Vec::with_capacity(width * height);
+        let mut neighbors = Vec::with_capacity(offsets.len());
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                let p = min + vector(x, y);
+                neighbors.clear();
+                neighbors.extend(offsets.iter().map(|v| self.get(p + v).clone()));
+                let cell = self.get(p);
+                let next = rule.next(cell, &neighbors);
+                changed |= next != *cell;
+                cells.push(next);
+            }
+        }
+        if self.edges == Edges::Infinite {
+            let background = rule.next(&self.background, &vec![self.background.clone(); offsets.len()]);
+            changed |= background != self.background;
+            self.background = background;
+        }
+
+        self.min = min;
+        self.width = width;
+        self.height = height;
+        self.cells = cells;
+        self.generation += 1;
+        changed
+    }
+
+    // Steps until nothing changes, returning the number of steps that changed something, or None
+    // if the automaton is still changing after limit steps.
+    pub fn run_until_fixed(&mut self, rule: &impl Rule<S>, limit: usize) -> Option<usize> {
+        (0..limit).find(|_| !self.step(rule))
+    }
+
+    // Renders the tracked area using the given colors
+    pub fn image(&self, color: impl Fn(&S) -> Color) -> TerminalImage {
+        TerminalImage { pixels: self.cells.iter().map(color).collect(), width: self.width }
+    }
+
+    // Renders the tracked area, one line per row
+    pub fn display(&self, render: impl Fn(&S) -> String) -> String {
+        let mut out = String::new();
+        for row in self.cells.chunks(self.width.max(1)) {
+            row.iter().for_each(|s| out.push_str(&render(s)));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl<S: Clone + Eq + Hash> Automaton<S> {
+    // Steps until the automaton returns to a state it was in before, returning the generation that
+    // state was first seen and the cycle's period (1 for a fixed point), or None if no state
+    // repeats within limit steps. Only the cells, background, and position are compared.
+    pub fn find_cycle(&mut self, rule: &impl Rule<S>, limit: usize) -> Option<(usize, usize)> {
+        let mut seen = HashMap::new();
+        for _ in 0..=limit {
+            let state = (self.min, self.width, self.cells.clone(), self.background.clone());
+            if let Some(start) = seen.insert(state, self.generation) {
+                return Some((start, self.generation - start));
+            }
+            self.step(rule);
+        }
+        None
+    }
+}
+
+// Automata are equal if their cells are, regardless of how many steps they've taken
+impl<S: PartialEq> PartialEq for Automaton<S> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.min, self.width, self.height, self.edges) == (other.min, other.width, other.height, other.edges)
+            && self.cells == other.cells && self.background == other.background
+    }
+}
+
+impl<S: Eq> Eq for Automaton<S> {}
+
+impl<S: Clone + PartialEq + Colored> TerminalRender for Automaton<S> {
+    fn render(&self, _w: usize, _h: usize) -> TerminalImage {
+        self.image(S::color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::parse_grid;
+
+    fn life(rows: &str, edges: Edges) -> Automaton<bool> {
+        Automaton::new(parse_grid(rows, |c| Ok(c == '#')).unwrap(), false, edges).unwrap()
+    }
+
+    fn show(automaton: &Automaton<bool>) -> String {
+        automaton.display(|&s| if s { "#" } else { "." }.into())
+    }
+
+    #[test]
+    fn neighborhoods() {
+        assert_eq!(Neighborhood::Moore.offsets().len(), 8);
+        assert_eq!(Neighborhood::Moore.with_center().offsets(),
+            (-1..=1).flat_map(|y| (-1..=1).map(move |x| vector(x, y))).collect::<Vec<_>>());
+        assert_eq!(Neighborhood::VonNeumann.with_center().offsets()[2], vector(0, 0));
+        assert_eq!(Neighborhood::reach(&Neighborhood::Custom(vec![vector(-2, 1)]).offsets()), 2);
+    }
+
+    #[test]
+    fn blinker() {
+        let mut automaton = life(".....\n..#..\n..#..\n..#..\n.....", Edges::Fixed);
+        assert!(automaton.step(&Totalistic::conway()));
+        assert_eq!(show(&automaton), ".....\n.....\n.###.\n.....\n.....\n");
+        assert_eq!(automaton.find_cycle(&Totalistic::conway(), 10), Some((1, 2)));
+        assert_eq!(automaton.generation(), 3);
+    }
+
+    #[test]
+    fn infinite_glider() {
+        let mut automaton = life(".#.\n..#\n###", Edges::Infinite);
+        for _ in 0..4 {
+            automaton.step(&Totalistic::conway());
+        }
+        // The same glider, one cell down and to the right, with a border of empty cells
+        assert_eq!(automaton.count(|&s| s), 5);
+        assert_eq!(automaton.bounds(), Some((point(0, 0), point(4, 4))));
+        assert_eq!(show(&automaton), ".....\n..#..\n...#.\n.###.\n.....\n");
+    }
+
+    #[test]
+    fn wrapping_glider() {
+        let mut automaton = life(".#....\n..#...\n###...\n......\n......\n......", Edges::Wrapping);
+        // Crosses the 6x6 torus diagonally, moving one cell every four generations
+        assert_eq!(automaton.find_cycle(&Totalistic::conway(), 100), Some((0, 24)));
+    }
+
+    #[test]
+    fn fixed_point() {
+        let mut automaton = life("....\n.##.\n.#..\n....", Edges::Fixed);
+        assert_eq!(automaton.run_until_fixed(&Totalistic::conway(), 10), Some(1));
+        assert_eq!(show(&automaton), "....\n.##.\n.##.\n....\n");
+        assert_eq!(automaton.find_cycle(&Totalistic::conway(), 10), Some((2, 1)));
+    }
+
+    #[test]
+    fn flashing_background() {
+        // Every cell flips, including the infinite background
+        let invert = LookupTable::new(Neighborhood::Custom(vec![vector(0, 0)]), vec![true, false]).unwrap();
+        let mut automaton = life("#.", Edges::Infinite);
+        assert!(automaton.step(&invert));
+        assert!(*automaton.background());
+        assert_eq!(show(&automaton), ".\n");
+        assert_eq!(automaton.bounds(), Some((point(0, 0), point(0, 0))));
+    }
+
+    #[test]
+    fn closure_rule() {
+        // Each cell becomes the sum of its von Neumann neighbors
+        let sum = rule(Neighborhood::VonNeumann, |_: &u32, n: &[u32]| n.iter().sum());
+        let cells = parse_grid("010\n203\n040", |c| Ok(c.to_digit(10).unwrap())).unwrap();
+        let mut automaton = Automaton::new(cells, 0, Edges::Wrapping).unwrap();
+        automaton.step(&sum);
+        assert_eq!(automaton.display(|n| format!("{:3}", n)), "  3  4  4\n  3 10  2\n  6  1  7\n");
+    }
+
+    #[test]
+    fn rules() {
+        let rule: Totalistic = "B36/S23".parse().unwrap();
+        assert_eq!(rule.to_string(), "B36/S23");
+        assert!(rule.next(&false, &[true, true, true, true, true, true, false, false]));
+        assert_eq!("b3/s23".parse::<Totalistic>().unwrap(), Totalistic::conway());
+        assert!("B9/S23".parse::<Totalistic>().is_err());
+        assert!("S23".parse::<Totalistic>().is_err());
+        assert!(LookupTable::new(Neighborhood::Moore, vec![false; 512]).is_err());
+
+        let table = LookupTable::new(Neighborhood::VonNeumann, (0..16).map(|i| i == 0b1001).collect()).unwrap();
+        assert!(table.next(&false, &[true, false, false, true]));
+        assert!(!table.next(&false, &[false, true, true, false]));
+        assert_eq!(table.as_totalistic(), None);
+    }
+
+    #[test]
+    fn totalistic_tables() {
+        let moore = Neighborhood::Moore.with_center();
+        let conway = Totalistic::conway();
+        let table: Vec<_> = (0..512usize).map(|i| {
+            let neighbors: Vec<_> = (0..9).filter(|&b| b != 4).map(|b| i & (1 << (8 - b)) != 0).collect();
+            conway.next(&(i & 1 << 4 != 0), &neighbors)
+        }).collect();
+        let table = LookupTable::new(moore.clone(), table).unwrap();
+        assert_eq!(table.as_totalistic(), Some(conway));
+
+        // Depends on which neighbors are alive, not just how many
+        let table = LookupTable::new(moore, (0..512).map(|i| i & 1 != 0).collect()).unwrap();
+        assert_eq!(table.as_totalistic(), None);
+
+        let parity = LookupTable::new(Neighborhood::VonNeumann.with_center(), (0..32u32).map(|i| i.count_ones() % 2 == 1).collect()).unwrap();
+        assert_eq!(parity.as_totalistic().unwrap().to_string(), "B13/S024");
+    }
+}
@@ -1,12 +1,24 @@
-fn main() {
-    let input = parse_input();
+use anyhow::Result;
+use advent_2021::input;
+use advent_2021::parsing::parse_lines;
+
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    println!("Increasing Measurements: {}", part1(&input)?);
+    println!("Increasing Windows:      {}", part2(&input)?);
+    Ok(())
+}
+
+pub fn part1(input: &str) -> Result<String> {
+    Ok(count_increases(&parse_input(input)?).to_string())
+}
 
-    println!("Increasing Measurements: {}", count_increases(&input));
-    println!("Increasing Windows:      {}", count_increases(&sum_windows(&input, 3)));
+pub fn part2(input: &str) -> Result<String> {
+    Ok(count_increases(&sum_windows(&parse_input(input)?, 3)).to_string())
 }
 
-fn parse_input() -> Vec<i32> {
-    include_str!("input.txt").lines().map(|l| l.parse().unwrap()).collect()
+fn parse_input(input: &str) -> Result<Vec<i32>> {
+    parse_lines(input)
 }
 
 fn count_increases(report: &[i32]) -> usize {
@@ -2,17 +2,23 @@ use anyhow::Result;
 
 use advent_2021::euclid::{vector, Vector};
 use advent_2021::parsing::{capture_group, regex_captures, static_regex};
+use advent_2021::input;
 
-fn main() -> Result<()> {
-    let input = parse_input(include_str!("input.txt"))?;
-
-    let distance = sum_directions(&input);
-    println!("Naive Dist: {} = {}", distance, distance.x*distance.y);
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    println!("Naive Dist: {}", part1(&input)?);
+    println!("Aimed Dist: {}", part2(&input)?);
+    Ok(())
+}
 
-    let distance = aim_directions(&input);
-    println!("Aimed Dist: {} = {}", distance, distance.x*distance.y);
+pub fn part1(input: &str) -> Result<String> {
+    let distance = sum_directions(&parse_input(input)?);
+    Ok((distance.x*distance.y).to_string())
+}
 
-    Ok(())
+pub fn part2(input: &str) -> Result<String> {
+    let distance = aim_directions(&parse_input(input)?);
+    Ok((distance.x*distance.y).to_string())
 }
 
 fn sum_directions(directions: &[Vector]) -> Vector {
@@ -49,18 +55,13 @@ fn parse_input(input: &str) -> Result<Vec<Vector>> {
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    fn example() -> Vec<Vector> {
-        parse_input(include_str!("example.txt")).unwrap()
-    }
-
-    #[test]
-    fn travel() {
-        assert_eq!(sum_directions(&example()), vector(15, 10));
-    }
+    use advent_2021::puzzle::this_day;
 
     #[test]
-    fn aim() {
-        assert_eq!(aim_directions(&example()), vector(15, 60));
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            assert!(example.check(1, part1(example.input()).unwrap()));
+            assert!(example.check(2, part2(example.input()).unwrap()));
+        }
     }
 }
@@ -1,22 +1,23 @@
 use anyhow::Result;
+use advent_2021::input;
 
-fn main() -> Result<()> {
-    let input: Vec<_> = include_str!("input.txt").lines().collect();
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    println!("Power Consumption: {}", part1(&input)?);
+    println!("Life Support:      {}", part2(&input)?);
+    Ok(())
+}
+
+pub fn part1(input: &str) -> Result<String> {
+    let input: Vec<_> = input.lines().collect();
     let gamma = gamma(&input);
     let epsilon = epsilon(&gamma);
-    let gamma_n = u32::from_str_radix(&gamma, 2)?;
-    let epsilon_n = u32::from_str_radix(&epsilon, 2)?;
-
-    println!("Gamma: {} - Epsilon: {} - Power Consumption: {}", gamma_n, epsilon_n, gamma_n * epsilon_n);
-
-    let o2 = o2_gen(&input);
-    let co2 = co2_scrub(&input);
-
-    let o2_n = u32::from_str_radix(&o2, 2)?;
-    let co2_n = u32::from_str_radix(&co2, 2)?;
-    println!("O2: {} - CO2: {} - Life Support: {}", o2_n, co2_n, o2_n * co2_n);
+    Ok((u32::from_str_radix(&gamma, 2)? * u32::from_str_radix(&epsilon, 2)?).to_string())
+}
 
-    Ok(())
+pub fn part2(input: &str) -> Result<String> {
+    let input: Vec<_> = input.lines().collect();
+    Ok((u32::from_str_radix(&o2_gen(&input), 2)? * u32::from_str_radix(&co2_scrub(&input), 2)?).to_string())
 }
 
 fn gamma(report: &[&str]) -> String {
@@ -81,10 +82,20 @@ fn co2_scrub(report: &[&str]) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_2021::puzzle::this_day;
+
+    #[test]
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            assert!(example.check(1, part1(example.input()).unwrap()));
+            assert!(example.check(2, part2(example.input()).unwrap()));
+        }
+    }
 
     #[test]
     fn gamma_and_epsilon() {
-        let input: Vec<_> = include_str!("example.txt").lines().collect();
+        let example = this_day!().example("example").unwrap();
+        let input: Vec<_> = example.input().lines().collect();
         let gamma = gamma(&input);
         assert_eq!(gamma, "10110");
         assert_eq!(epsilon(&gamma), "01001");
@@ -92,7 +103,8 @@ mod tests {
 
     #[test]
     fn o2_and_co2() {
-        let input: Vec<_> = include_str!("example.txt").lines().collect();
+        let example = this_day!().example("example").unwrap();
+        let input: Vec<_> = example.input().lines().collect();
         assert_eq!(o2_gen(&input), "10111");
         assert_eq!(co2_scrub(&input), "01010");
     }
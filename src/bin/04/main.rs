@@ -1,13 +1,23 @@
 use std::cell::Cell;
-use anyhow::Result;
-
-fn main() -> Result<()> {
-    let (moves, mut boards) = parse_input(include_str!("input.txt"));
+use anyhow::{ensure, Result};
+use advent_2021::parsing::sections;
+use advent_2021::input;
+
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    println!("Best Board:  {}", part1(&input)?);
+    println!("Worst Board: {}", part2(&input)?);
+    Ok(())
+}
 
-    let (best, worst) = play(&moves, &mut boards);
-    println!("Best Board:  {}\nWorst Board: {}", best, worst);
+pub fn part1(input: &str) -> Result<String> {
+    let (moves, mut boards) = parse_input(input)?;
+    Ok(play(&moves, &mut boards).0.to_string())
+}
 
-    Ok(())
+pub fn part2(input: &str) -> Result<String> {
+    let (moves, mut boards) = parse_input(input)?;
+    Ok(play(&moves, &mut boards).1.to_string())
 }
 
 #[derive(Debug)]
@@ -70,31 +80,33 @@ fn play(moves: &[u32], boards: &mut [Board]) -> (u32, u32) {
     (boards[completed[0]].score(), boards[completed[completed.len()-1]].score())
 }
 
-fn parse_input(input: &str) -> (Vec<u32>, Vec<Board>) {
-    let mut lines = input.lines();
-    let moves = lines.next().unwrap().split(',').map(|n| n.parse().unwrap()).collect();
-    lines.next().unwrap();
-
-    let lines: Vec<_> = lines.collect();
+fn parse_input(input: &str) -> Result<(Vec<u32>, Vec<Board>)> {
+    let sections = sections(input);
+    ensure!(!sections.is_empty(), "No moves");
+    let moves = sections[0].parse_csv()?;
 
     let mut boards = vec!();
-    for i in 0..(lines.len()+1)/6 {
-        let board: Board = Board::create(lines[i*6..(i*6)+5].join(" ").split_whitespace().map(|n| n.parse().unwrap()).collect());
-        boards.push(board);
+    for section in &sections[1..] {
+        let grid = section.parse_whitespace()?;
+        ensure!(grid.len() == 25, "Board on line {} has {} squares", section.lines().next().expect("non-empty").0, grid.len());
+        boards.push(Board::create(grid));
     }
 
-    (moves, boards)
+    Ok((moves, boards))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_2021::puzzle::this_day;
 
     #[test]
-    fn play_example() {
-        let (moves, mut boards) = parse_input(include_str!("example.txt"));
-        let (best, worst) = play(&moves, &mut boards);
-        assert_eq!(best, 4512);
-        assert_eq!(worst, 1924);
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            let (moves, mut boards) = parse_input(example.input()).unwrap();
+            let (best, worst) = play(&moves, &mut boards);
+            assert!(example.check(1, best));
+            assert!(example.check(2, worst));
+        }
     }
 }
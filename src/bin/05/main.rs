@@ -1,16 +1,25 @@
 use std::collections::HashMap;
 use anyhow::Result;
 
-use advent_2021::parsing::{static_regex,capture_group,regex_captures};
+use advent_2021::parsing::pattern_struct;
 use advent_2021::euclid::{Point, vector};
+use advent_2021::input;
 
-fn main() -> Result<()> {
-    let input = parse_input(include_str!("input.txt"))?;
-    println!("Oriented Overlaps: {}", count_overlaps(&filter_diagonals(&input)));
-    println!("All Overlaps:      {}", count_overlaps(&input));
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    println!("Oriented Overlaps: {}", part1(&input)?);
+    println!("All Overlaps:      {}", part2(&input)?);
     Ok(())
 }
 
+pub fn part1(input: &str) -> Result<String> {
+    Ok(count_overlaps(&filter_diagonals(&parse_input(input)?)).to_string())
+}
+
+pub fn part2(input: &str) -> Result<String> {
+    Ok(count_overlaps(&parse_input(input)?).to_string())
+}
+
 fn count_overlaps(paths: &[(Point, Point)]) -> usize {
     let points: Vec<Point> = paths.iter().flat_map(|(a, b)| points_between(*a, *b).into_iter()).collect();
     let mut grid: HashMap<Point, u32> = HashMap::new();
@@ -35,20 +44,18 @@ fn points_between(a: Point, b: Point) -> Vec<Point> {
 }
 
 fn parse_input(input: &str) -> Result<Vec<(Point, Point)>> {
-    fn to_pair(line: &str) -> Result<(Point, Point)> {
-        let regex = static_regex!(r"(.*) -> (.*)");
-        let caps = regex_captures(regex, line)?;
-        let a = capture_group(&caps, 1);
-        let b = capture_group(&caps, 2);
-        Ok((a.parse()?, b.parse()?))
+    pattern_struct! {
+        #[pattern(r"(?P<a>.*) -> (?P<b>.*)")]
+        struct Line { a: Point, b: Point }
     }
 
-    input.lines().map(to_pair).collect()
+    input.lines().map(|l| l.parse::<Line>().map(|l| (l.a, l.b))).collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_2021::puzzle::this_day;
     use advent_2021::euclid::point;
 
     parameterized_test::create!{ between, (a, b, expected), { assert_eq!(&points_between(a, b), expected); } }
@@ -62,7 +69,7 @@ mod tests {
 
     #[test]
     fn exclude_diagonals() {
-        let example = parse_input(include_str!("example.txt")).unwrap();
+        let example = parse_input(this_day!().example("example").unwrap().input()).unwrap();
         assert_eq!(&filter_diagonals(&example), &[
             (point(0, 9), point(5, 9)),
             (point(9, 4), point(3, 4)),
@@ -74,10 +81,11 @@ mod tests {
     }
 
     #[test]
-    fn overlaps() {
-        let example = parse_input(include_str!("example.txt")).unwrap();
-
-        assert_eq!(count_overlaps(&filter_diagonals(&example)), 5);
-        assert_eq!(count_overlaps(&example), 12);
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            let paths = parse_input(example.input()).unwrap();
+            assert!(example.check(1, count_overlaps(&filter_diagonals(&paths))));
+            assert!(example.check(2, count_overlaps(&paths)));
+        }
     }
 }
@@ -1,14 +1,24 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
+use advent_2021::parsing::parse_csv;
+use advent_2021::input;
 
-fn main() -> Result<()> {
-    let input = parse_input(include_str!("input.txt"))?;
-    println!("Population after 80 days:  {}", simulate(&input, 80));
-    println!("Population after 256 days: {}", emulate(&input, 256));
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    println!("Population after 80 days:  {}", part1(&input)?);
+    println!("Population after 256 days: {}", part2(&input)?);
     Ok(())
 }
 
+pub fn part1(input: &str) -> Result<String> {
+    Ok(simulate(&parse_input(input)?, 80).to_string())
+}
+
+pub fn part2(input: &str) -> Result<String> {
+    Ok(emulate(&parse_input(input)?, 256).to_string())
+}
+
 fn parse_input(input: &str) -> Result<Vec<u64>> {
-    input.trim().split(',').map(|n| n.parse().context(n.to_string())).collect()
+    parse_csv(input)
 }
 
 fn simulate(fish: &[u64], days: usize) -> usize {
@@ -29,7 +39,7 @@ fn simulate(fish: &[u64], days: usize) -> usize {
 
 fn emulate(fish: &[u64], days: usize) -> u64 {
     let mut fish_days = fish.iter().fold(vec![0; 7], |mut d, &f| { d[f as usize] += 1; d });
-    let mut new_fish_days = vec![0; 7];
+    let mut new_fish_days = [0; 7];
 
     for i in 0..days {
         new_fish_days[(i+2)%7] = fish_days[i%7];  // create new fish
@@ -1,17 +1,28 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
+use advent_2021::parsing::parse_csv;
+use advent_2021::input;
 
-fn main() -> Result<()> {
-    let input = parse_input(include_str!("input.txt"))?;
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    println!("Simple Fuel Cost:  {}", part1(&input)?);
+    println!("Complex Fuel Cost: {}", part2(&input)?);
+    Ok(())
+}
+
+pub fn part1(input: &str) -> Result<String> {
+    let input = parse_input(input)?;
     // Observed the example answer looked like the median, not very principled
-    println!("Simple Fuel Cost: {}", simple_fuel_cost(&input, median(&input)));
+    Ok(simple_fuel_cost(&input, median(&input)).to_string())
+}
+
+pub fn part2(input: &str) -> Result<String> {
+    let input = parse_input(input)?;
     // Guessed the mean might be helpful given the median worked before, also not right but it
     // turns out to find _almost_ the right value. Would be nice to do a proper hill-climb solution.
     let mean = mean(&input);
     let floor = complex_fuel_cost(&input, mean.floor() as i32);
     let ceil = complex_fuel_cost(&input, mean.ceil() as i32);
-    println!("Complex Fuel Cost: {}", std::cmp::min(floor, ceil));
-
-    Ok(())
+    Ok(std::cmp::min(floor, ceil).to_string())
 }
 
 fn median<T: Copy+std::cmp::Ord>(sequence: &[T]) -> T {
@@ -34,7 +45,7 @@ fn complex_fuel_cost(positions: &[i32], target: i32) -> i32 {
 }
 
 fn parse_input(input: &str) -> Result<Vec<i32>> {
-    input.trim().split(',').map(|n| n.parse().context(n.to_string())).collect()
+    parse_csv(input)
 }
 
 #[cfg(test)]
@@ -2,18 +2,23 @@ use anyhow::{bail,ensure,Result};
 use bitmaps::Bitmap;
 
 use advent_2021::parsing::*;
+use advent_2021::input;
 
 // TODO interactive! with https://en.wikipedia.org/wiki/Symbols_for_Legacy_Computing
-fn main() -> Result<()> {
-    let input = parse_input(include_str!("input.txt"))?;
-
-    let easy_digits = input.iter().map(|d| d.count_easy_digits()).sum::<u32>();
-    println!("Easy Digits: {}", easy_digits);
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    println!("Easy Digits:    {}", part1(&input)?);
+    println!("Summed Outputs: {}", part2(&input)?);
+    Ok(())
+}
 
-    let outputs: Vec<_> = input.iter().map(|d| d.read_display()).collect::<Result<_>>()?;
-    println!("Summed Outputs: {}", outputs.iter().sum::<u32>());
+pub fn part1(input: &str) -> Result<String> {
+    Ok(parse_input(input)?.iter().map(|d| d.count_easy_digits()).sum::<u32>().to_string())
+}
 
-    Ok(())
+pub fn part2(input: &str) -> Result<String> {
+    let outputs: Vec<_> = parse_input(input)?.iter().map(|d| d.read_display()).collect::<Result<_>>()?;
+    Ok(outputs.iter().sum::<u32>().to_string())
 }
 
 // See also CharSet in https://github.com/dimo414/advent-2019/blob/e0b2414e90/src/aoc18.rs#L260
@@ -104,7 +109,7 @@ impl SegmentDisplay {
         let found = find_candidate(&candidates, |&c| !c.is_empty())?;
         swap(&mut digits[2], &mut candidates[found]);
 
-        ensure!(digits.iter().find(|&d| d.is_empty()) == None, "All digits should be set");
+        ensure!(!digits.iter().any(|d| d.is_empty()), "All digits should be set");
 
         Ok(SegmentDisplay { digits, output: output.iter().map(|o| to_segments(o)).collect::<Result<_>>()? })
     }
@@ -144,25 +149,32 @@ fn parse_input(input: &str) -> Result<Vec<SegmentDisplay>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_2021::puzzle::this_day;
 
-    const EXAMPLE1: &str = include_str!("example1.txt");
-    const EXAMPLE2: &str = include_str!("example2.txt");
+    #[test]
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            assert!(example.check(1, part1(example.input()).unwrap()));
+            assert!(example.check(2, part2(example.input()).unwrap()));
+        }
+    }
 
-    parameterized_test::create!{ easy_digits, (input, expected), {
-        let example = parse_input(input).unwrap();
+    // The answers are sums over each display; these check the individual displays
+    parameterized_test::create!{ easy_digits, (name, expected), {
+        let example = parse_input(this_day!().example(name).unwrap().input()).unwrap();
         assert_eq!(&example.iter().map(SegmentDisplay::count_easy_digits).collect::<Vec<_>>(), &expected);
     } }
     easy_digits! {
-        one: (EXAMPLE1, [0]),
-        two: (EXAMPLE2, [2, 3, 3, 1, 3, 4, 3, 1, 4, 2]),
+        one: ("example1", [0]),
+        two: ("example2", [2, 3, 3, 1, 3, 4, 3, 1, 4, 2]),
     }
 
-    parameterized_test::create!{ display, (input, expected), {
-        let example = parse_input(input).unwrap();
+    parameterized_test::create!{ display, (name, expected), {
+        let example = parse_input(this_day!().example(name).unwrap().input()).unwrap();
         assert_eq!(&example.iter().map(SegmentDisplay::read_display).collect::<Result<Vec<_>>>().unwrap(), &expected);
     } }
     display! {
-        one: (EXAMPLE1, [5353]),
-        two: (EXAMPLE2, [8394, 9781, 1197, 9361, 4873, 8418, 4548, 1625, 8717, 4315]),
+        one: ("example1", [5353]),
+        two: ("example2", [8394, 9781, 1197, 9361, 4873, 8418, 4548, 1625, 8717, 4315]),
     }
 }
@@ -1,19 +1,26 @@
 use std::collections::{HashMap, HashSet, VecDeque};
-use anyhow::{anyhow, Result};
-use advent_2021::euclid::{Point, point, Vector};
+use anyhow::{Context, Result};
+use advent_2021::parsing::parse_grid;
+use advent_2021::euclid::{Point, Vector};
+use advent_2021::input;
 
-fn main() -> Result<()> {
-    let input = parse_input(include_str!("input.txt"))?;
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    println!("Depth Score:    {}", part1(&input)?);
+    println!("Largest Basins: {}", part2(&input)?);
+    Ok(())
+}
 
-    let low_points = find_low_points(&input);
-    println!("Depth Score: {}", low_points.iter().map(|p| input[p]+1).sum::<u32>());
+pub fn part1(input: &str) -> Result<String> {
+    let input = parse_input(input)?;
+    Ok(find_low_points(&input).iter().map(|p| input[p]+1).sum::<u32>().to_string())
+}
 
-    let mut basin_sizes: Vec<_> = low_points.iter().map(|&p| basin_size(p, &input)).collect();
+pub fn part2(input: &str) -> Result<String> {
+    let input = parse_input(input)?;
+    let mut basin_sizes: Vec<_> = find_low_points(&input).iter().map(|&p| basin_size(p, &input)).collect();
     basin_sizes.sort_unstable();
-    let product = basin_sizes.iter().rev().take(3).product::<u32>();
-    println!("Largest Basins: {}", product);
-
-    Ok(())
+    Ok(basin_sizes.iter().rev().take(3).product::<u32>().to_string())
 }
 
 fn find_low_points(depths: &HashMap<Point, u32>) -> Vec<Point> {
@@ -55,29 +62,37 @@ fn basin_size(starting_point: Point, depths: &HashMap<Point, u32>) -> u32 {
 }
 
 fn parse_input(input: &str) -> Result<HashMap<Point, u32>> {
-    let mut ret = HashMap::new();
-    for (y, line) in input.lines().enumerate() {
-        for (x, d) in line.chars().enumerate() {
-            ret.insert(point(x as i32, y as i32), d.to_digit(10).ok_or(anyhow!("Invalid digit"))?);
-        }
-    }
-    Ok(ret)
+    parse_grid(input, |d| d.to_digit(10).context("Invalid digit"))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_2021::euclid::point;
+    use advent_2021::puzzle::this_day;
+
+    #[test]
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            assert!(example.check(1, part1(example.input()).unwrap()));
+            assert!(example.check(2, part2(example.input()).unwrap()));
+        }
+    }
+
+    fn example() -> HashMap<Point, u32> {
+        parse_input(this_day!().example("example").unwrap().input()).unwrap()
+    }
 
     #[test]
     fn low_points() {
-        let depths = parse_input(include_str!("example.txt")).unwrap();
+        let depths = example();
 
         assert_eq!(&find_low_points(&depths),
                    &[point(1, 0), point(2, 2), point(6, 4), point(9, 0)]);
     }
 
     parameterized_test::create!{ basins, (point, expected_size), {
-        let depths = parse_input(include_str!("example.txt")).unwrap();
+        let depths = example();
         assert_eq!(basin_size(point, &depths), expected_size);
     } }
     basins! {
@@ -1,14 +1,21 @@
 use anyhow::{bail, Result};
+use advent_2021::input;
 
-fn main() -> Result<()> {
-    // https://stackoverflow.com/q/70340147/113632
-    let (valid, invalid, incomplete) = partition(parse_input(include_str!("input.txt"))?);
-      assert_eq!(valid.len(), 0, "Shouldn't be any valid expressions");
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    println!("Invalid Score:    {}", part1(&input)?);
+    println!("Incomplete Score: {}", part2(&input)?);
+    Ok(())
+}
 
-    println!("Invalid Score: {}", score_invalid(&invalid));
-    println!("Incomplete Score: {}", score_incomplete(&incomplete));
+pub fn part1(input: &str) -> Result<String> {
+    let (_, invalid, _) = partition(parse_input(input)?);
+    Ok(score_invalid(&invalid).to_string())
+}
 
-    Ok(())
+pub fn part2(input: &str) -> Result<String> {
+    let (_, _, incomplete) = partition(parse_input(input)?);
+    Ok(score_incomplete(&incomplete).to_string())
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -18,6 +25,7 @@ enum Status {
     Invalid(char),
 }
 
+// https://stackoverflow.com/q/70340147/113632
 fn partition(statuses: Vec<Status>) -> (Vec<Status>, Vec<Status>, Vec<Status>) {
     statuses.into_iter()
         .fold((Vec::new(), Vec::new(), Vec::new()),
@@ -98,16 +106,17 @@ fn parse_input(input: &str) -> Result<Vec<Status>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_2021::puzzle::this_day;
 
     #[test]
-    fn example() {
-        let example = parse_input(include_str!("example.txt")).unwrap();
-
-        let (valid, invalid, incomplete) = partition(example);
-        assert_eq!(valid.len(), 0, "Shouldn't be any valid expressions");
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            let (valid, invalid, incomplete) = partition(parse_input(example.input()).unwrap());
+            assert_eq!(valid.len(), 0, "Shouldn't be any valid expressions");
 
-        assert_eq!(score_invalid(&invalid), 26397);
-        assert_eq!(score_incomplete(&incomplete), 288957);
+            assert!(example.check(1, score_invalid(&invalid)));
+            assert!(example.check(2, score_incomplete(&incomplete)));
+        }
     }
 
     parameterized_test::create!{ inline_examples, (example, expected), {
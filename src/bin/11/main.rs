@@ -1,57 +1,117 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::fmt::{Debug, Display};
 use std::str::FromStr;
 use std::time::Duration;
 use anyhow::{anyhow,Error,Result};
-use advent_2021::euclid::{point, Point, Vector};
-use advent_2021::terminal::{Color, Terminal, TerminalImage, TerminalRender};
+use advent_2021::automaton::{rule, Automaton, Edges, Neighborhood, Rule};
+use advent_2021::euclid::{point, Point};
+use advent_2021::terminal::{Color, Frame, Palette, Terminal, TerminalImage, TerminalRender};
+use advent_2021::input;
 
-fn main() -> Result<()> {
+pub fn main() -> Result<()> {
     let _drop = Terminal::init();
-    let mut octopi: Octopi = include_str!("input.txt").parse()?;
+    let input = input::read(include_str!("input.txt"))?;
+    if Terminal::active() {
+        animate(input.parse()?);
+    }
+    println!("Blinks after 100 generations: {}", part1(&input)?);
+    println!("All blinked at generation:    {}", part2(&input)?);
 
+    Ok(())
+}
+
+pub fn part1(input: &str) -> Result<String> {
+    let mut octopi: Octopi = input.parse()?;
+    Ok((0..100).map(|_| octopi.step()).sum::<u32>().to_string())
+}
+
+pub fn part2(input: &str) -> Result<String> {
+    let mut octopi: Octopi = input.parse()?;
+    let size = octopi.automaton.len() as u32;
+    while octopi.step() != size {}
+    Ok(octopi.generation.to_string())
+}
+
+fn animate(mut octopi: Octopi) {
     let mut blinks = 0;
     for _ in 0..100 {
         octopi.half_step();
-        Terminal::interactive_render(&octopi, Duration::from_millis(75));
+        Terminal::interactive_frame(|| octopi.frame(format!("Blinks: {}", blinks)), Duration::from_millis(75));
         blinks += octopi.step();
-        Terminal::interactive_render(&octopi, Duration::from_millis(150));
+        Terminal::interactive_frame(|| octopi.frame(format!("Blinks: {}", blinks)), Duration::from_millis(150));
     }
-    Terminal::end_interactive();
-    println!("Blinks after 100 generations: {}", blinks);
 
     let mut first_all_blink = None;
     for _ in 101..500 {
         let blinks = octopi.step();
-        Terminal::interactive_render(&octopi, Duration::from_millis(75));
         if blinks == 100 && first_all_blink.is_none() {
             first_all_blink = Some(octopi.generation);
         }
+        Terminal::interactive_frame(|| octopi.frame(match first_all_blink {
+            Some(generation) => format!("All blinked at generation {}", generation),
+            None => format!("Blinks: {}", blinks),
+        }), Duration::from_millis(75));
     }
     Terminal::clear_interactive();
-    println!("All blinked at generation:    {}", first_all_blink.expect("Insufficient generations"));
+}
 
-    Ok(())
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Octopus {
+    energy: u32,
+    flashed: bool,
+}
+
+impl Octopus {
+    fn flashing(&self) -> bool { !self.flashed && self.energy > 9 }
+}
+
+// Each step every octopus first gains one energy...
+fn charge() -> impl Rule<Octopus> {
+    rule(Neighborhood::Custom(vec![]), |o: &Octopus, _: &[Octopus]| Octopus { energy: o.energy + 1, ..*o })
+}
+
+// ...then any with more than 9 energy flash, at most once, giving one energy to each neighbor (which
+// may cause them to flash in turn)...
+fn flash() -> impl Rule<Octopus> {
+    rule(Neighborhood::Moore, |o: &Octopus, neighbors: &[Octopus]| {
+        let flashes = neighbors.iter().filter(|n| n.flashing()).count() as u32;
+        Octopus { energy: o.energy + flashes, flashed: o.flashed || o.flashing() }
+    })
+}
+
+// ...and finally those that flashed are reset to 0.
+fn reset() -> impl Rule<Octopus> {
+    rule(Neighborhood::Custom(vec![]), |o: &Octopus, _: &[Octopus]|
+        if o.flashed { Octopus { energy: 0, flashed: false } } else { *o })
 }
 
 #[derive(Clone)]
 struct Octopi {
-    grid: HashMap<Point, u32>,
+    automaton: Automaton<Octopus>,
     generation: u32,
     incomplete: bool,
 }
 
 impl Octopi {
-    fn create(grid: HashMap<Point, u32>) -> Octopi {
-        assert!(!grid.is_empty(), "Grid cannot be empty.");
-        Octopi { grid, generation: 0, incomplete: false }
+    fn create(grid: HashMap<Point, u32>) -> Result<Octopi> {
+        let grid = grid.into_iter().map(|(p, energy)| (p, Octopus { energy, flashed: false })).collect();
+        let automaton = Automaton::new(grid, Octopus { energy: 0, flashed: false }, Edges::Fixed)?;
+        Ok(Octopi { automaton, generation: 0, incomplete: false })
     }
 
     fn half_step(&mut self) {
         assert!(!self.incomplete, "Cannot take a half-step until the last step is completed.");
         self.generation += 1;
         self.incomplete = true;
-        self.grid = self.grid.iter().map(|(&p, &v)| (p, v+1)).collect();
+        self.automaton.step(&charge());
+    }
+
+    fn frame(&self, status: String) -> Frame {
+        Frame::stack([
+            Frame::text(format!("Generation: {}", self.generation)),
+            Frame::image(self.render(0, 0)),
+            Frame::text(status),
+        ])
     }
 
     fn step(&mut self) -> u32 {
@@ -60,37 +120,17 @@ impl Octopi {
         }
         self.incomplete = false;
 
-        // TODO keeping a set of 'blinked' cells and updating a cells neighbors when it's inserted
-        //   would be more efficient than the repeated passes being done here.
-        let mut charging: HashSet<_> = self.grid.keys().cloned().collect();
-        let mut finished = false;
-        while !finished {
-            finished = true;
-            for point in charging.iter().cloned().collect::<Vec<_>>() {
-                if self.grid[&point] > 9 {
-                    finished = false;
-                    charging.remove(&point);
-                    for neighbor in Vector::ORDINAL.iter().map(|v| point + v) {
-                        self.grid.entry(neighbor).and_modify(|v| *v+=1);
-                    }
-                }
-            }
-        }
-
-        let mut blinks = 0;
-        for point in self.grid.keys().cloned().collect::<Vec<_>>() {
-            if self.grid[&point] > 9 {
-                self.grid.insert(point, 0);
-                blinks += 1;
-            }
-        }
+        // Every octopus can flash at most once, so this must stop
+        self.automaton.run_until_fixed(&flash(), usize::MAX).expect("Flashes stop");
+        let blinks = self.automaton.count(|o| o.flashed) as u32;
+        self.automaton.step(&reset());
         blinks
     }
 }
 
 impl PartialEq for Octopi {
     fn eq(&self, other: &Self) -> bool {
-        self.grid == other.grid
+        self.automaton == other.automaton
     }
 }
 impl Eq for Octopi {}
@@ -107,27 +147,24 @@ impl FromStr for Octopi {
                             .ok_or(anyhow!("invalid digit"))
                             .map(|e| (point(x as i32, y as i32), e))))
             .collect::<Result<HashMap<_, _>>>()?;
-        Ok(Octopi::create(grid))
+        Octopi::create(grid)
     }
 }
 
 impl TerminalRender for Octopi {
     fn render(&self, _w: usize, _h: usize) -> TerminalImage {
-        fn to_color(digit: u32) -> Color {
+        // scale to 12 instead of 10 to make the white "blink" more distinct
+        let palette = Palette::greyscale(0.0, 12.0);
+        let to_color = |digit: u32| {
             match digit {
                 // during a half-step a cell can be over-energized
                 0|10..=18 => Color::WHITE,
-                // divide by 12 instead of 10 to make the white "blink" more distinct
-                1..=9 => Color::GREYSCALE(digit as f32 / 12.0),
+                1..=9 => palette.color(digit as f64),
                 _ => panic!("Unexpected 'digit': {}", digit),
             }
-        }
+        };
 
-        let (min, max) = Point::bounding_box(self.grid.keys()).expect("not empty");
-        let pixels =
-            (min.y..=max.y).flat_map(move |y|
-                (min.x..=max.x).map(move |x| to_color(self.grid[&point(x, y)]))).collect();
-        TerminalImage{ pixels, width: (max.x-min.x+1) as usize, }
+        self.automaton.image(|o| to_color(o.energy))
     }
 }
 
@@ -138,36 +175,44 @@ impl Display for Octopi {
         // well with the colorizing behavior of Console at the time (it replaced any char that gets
         // colored with a solid block symbol). So for posterity here's digits as braille, 1-8:
         // ⡀ ⣀ ⣄ ⣤ ⣦ ⣶ ⣷ ⣿
-        fn render_digit(d: Option<&u32>) -> String {
+        fn render_digit(d: u32) -> String {
             match d {
-                Some(d@1..=9) => char::from_digit(*d, 10).expect("valid digit"),
-                Some(0|10..=18) => '0',
-                Some(d) => panic!("Unexpected number {}", d),
-                None => ' ',
+                1..=9 => char::from_digit(d, 10).expect("valid digit"),
+                0|10..=18 => '0',
+                d => panic!("Unexpected number {}", d),
             }.to_string()
         }
 
-        write!(f, "{}", Point::display_point_map(&self.grid, render_digit))
+        write!(f, "{}", self.automaton.display(|o| render_digit(o.energy)))
     }
 }
 
 impl Debug for Octopi {
     // This trait requires `fmt` with this exact signature.
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Generation: {}\n{}", self.generation,
-               Point::display_point_map(&self.grid, |v|
-                   v.map(|v| format!("{:2}", v)).unwrap_or_else(|| "  ".into())))
+        write!(f, "Generation: {}\n{}", self.generation, self.automaton.display(|o| format!("{:2}", o.energy)))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_2021::puzzle::this_day;
+
+    #[test]
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            assert!(example.check(1, part1(example.input()).unwrap()));
+            assert!(example.check(2, part2(example.input()).unwrap()));
+        }
+    }
 
-    parameterized_test::create!{ steps, (generation, expected), {
-        let mut octopi: Octopi = include_str!("example1.txt").parse().unwrap();
-        let expected: Octopi = expected.parse().unwrap();
-        let expected_blinks = expected.grid.values().filter(|&&e| e == 0).count() as u32;
+    // example1-N.txt is the state after N steps
+    parameterized_test::create!{ steps, generation, {
+        let example = this_day!().example("example1").unwrap();
+        let mut octopi: Octopi = example.input().parse().unwrap();
+        let expected: Octopi = example.fixture(&generation.to_string()).unwrap().parse().unwrap();
+        let expected_blinks = expected.automaton.count(|o| o.energy == 0) as u32;
 
         let mut last_blinks = None;
         for _ in 0..generation {
@@ -178,51 +223,28 @@ mod tests {
         assert_eq!(last_blinks.expect("should be set"), expected_blinks);
     } }
     steps! {
-        g1:   (1,   include_str!("example1-1.txt")),
-        g2:   (2,   include_str!("example1-2.txt")),
-        g3:   (3,   include_str!("example1-3.txt")),
-        g4:   (4,   include_str!("example1-4.txt")),
-        g5:   (5,   include_str!("example1-5.txt")),
-        g6:   (6,   include_str!("example1-6.txt")),
-        g7:   (7,   include_str!("example1-7.txt")),
-        g8:   (8,   include_str!("example1-8.txt")),
-        g9:   (9,   include_str!("example1-9.txt")),
-        g10:  (10,  include_str!("example1-10.txt")),
-        g20:  (20,  include_str!("example1-20.txt")),
-        g30:  (30,  include_str!("example1-30.txt")),
-        g40:  (40,  include_str!("example1-40.txt")),
-        g50:  (50,  include_str!("example1-50.txt")),
-        g60:  (60,  include_str!("example1-60.txt")),
-        g70:  (70,  include_str!("example1-70.txt")),
-        g80:  (80,  include_str!("example1-80.txt")),
-        g90:  (90,  include_str!("example1-90.txt")),
-        g100: (100, include_str!("example1-100.txt")),
-    }
-
-    parameterized_test::create!{ total_blinks, (generation, expected), {
-        let mut octopi: Octopi = include_str!("example1.txt").parse().unwrap();
-
-        let mut total_blinks = 0;
-        for _ in 0..generation {
-            total_blinks += octopi.step();
-        }
-        assert_eq!(total_blinks, expected);
-    } }
-    total_blinks! {
-        g10: (10, 204),
-        g100: (100, 1656),
+        g1: 1, g2: 2, g3: 3, g4: 4, g5: 5, g6: 6, g7: 7, g8: 8, g9: 9, g10: 10,
+        g20: 20, g30: 30, g40: 40, g50: 50, g60: 60, g70: 70, g80: 80, g90: 90, g100: 100,
+    }
+
+    #[test]
+    fn total_blinks() {
+        let mut octopi: Octopi = this_day!().example("example1").unwrap().input().parse().unwrap();
+        let total_blinks: u32 = (0..10).map(|_| octopi.step()).sum();
+        assert_eq!(total_blinks, 204);
     }
 
     #[test]
     fn energized() {
-        let mut octopi: Octopi = include_str!("example2.txt").parse().unwrap();
+        let example = this_day!().example("example2").unwrap();
+        let mut octopi: Octopi = example.input().parse().unwrap();
 
         let blinks1 = octopi.step();
-        assert_eq!(octopi, include_str!("example2-1.txt").parse::<Octopi>().unwrap());
+        assert_eq!(octopi, example.fixture("1").unwrap().parse::<Octopi>().unwrap());
         assert_eq!(blinks1, 9);
 
         let blinks2 = octopi.step();
-        assert_eq!(octopi, include_str!("example2-2.txt").parse::<Octopi>().unwrap());
+        assert_eq!(octopi, example.fixture("2").unwrap().parse::<Octopi>().unwrap());
         assert_eq!(blinks2, 0);
     }
 }
@@ -2,9 +2,10 @@ use std::collections::{HashMap, VecDeque};
 use std::rc::Rc;
 use std::str::FromStr;
 use anyhow::{ensure, Error, Result};
+use advent_2021::input;
 
-fn main() -> Result<()> {
-    let input: Caves = include_str!("input.txt").parse()?;
+pub fn main() -> Result<()> {
+    let input: Caves = input::read(include_str!("input.txt"))?.parse()?;
 
     println!("Paths:              {}", input.all_paths().len());
     println!("Paths (Revisiting): {}", input.all_paths_allow_revisit().len());
@@ -12,6 +13,14 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+pub fn part1(input: &str) -> Result<String> {
+    Ok(input.parse::<Caves>()?.all_paths().len().to_string())
+}
+
+pub fn part2(input: &str) -> Result<String> {
+    Ok(input.parse::<Caves>()?.all_paths_allow_revisit().len().to_string())
+}
+
 trait VisitLog: Clone {
     fn visit(&mut self, node: &Rc<String>);
     fn visited(&self, node: &Rc<String>) -> bool;
@@ -120,15 +129,14 @@ impl FromStr for Caves {
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    parameterized_test::create!{ paths, (input, num_paths, num_repeats_paths), {
-        let input: Caves = input.parse().unwrap();
-        assert_eq!(input.all_paths().len(), num_paths);
-        assert_eq!(input.all_paths_allow_revisit().len(), num_repeats_paths);
-    } }
-    paths! {
-        example1: (include_str!("example1.txt"), 10, 36),
-        example2: (include_str!("example2.txt"), 19, 103),
-        example3: (include_str!("example3.txt"), 226, 3509),
+    use advent_2021::puzzle::this_day;
+
+    #[test]
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            let input: Caves = example.input().parse().unwrap();
+            assert!(example.check(1, input.all_paths().len()));
+            assert!(example.check(2, input.all_paths_allow_revisit().len()));
+        }
     }
 }
@@ -1,21 +1,32 @@
 use std::collections::HashSet;
-use anyhow::{bail, Result};
+use anyhow::{bail, ensure, Error, Result};
+use std::str::FromStr;
 use advent_2021::euclid::{Point, point};
 
 use advent_2021::parsing::*;
+use advent_2021::input;
+use advent_2021::terminal::Glyphs;
 
-fn main() -> Result<()> {
-    let (mut points, folds) = parse_input(include_str!("input.txt"))?;
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    println!("Points after first fold: {}", part1(&input)?);
+    println!("Folded image:\n{}", part2(&input)?);
 
-    points = folds[0].fold_all(&points);
-    println!("Points after first fold: {}", points.len());
+    Ok(())
+}
 
-    for fold in &folds[1..] {
+pub fn part1(input: &str) -> Result<String> {
+    let (points, folds) = parse_input(input)?;
+    Ok(folds[0].fold_all(&points).len().to_string())
+}
+
+// The answer is whatever letters the folded points spell out
+pub fn part2(input: &str) -> Result<String> {
+    let (mut points, folds) = parse_input(input)?;
+    for fold in &folds {
         points = fold.fold_all(&points);
     }
-    println!("Folded image:\n{}", Point::display_points(&points, '█', ' '));
-
-    Ok(())
+    Ok(Point::display_point_set_glyphs(&points, Glyphs::Braille).trim_end().to_string())
 }
 
 enum Fold {
@@ -23,6 +34,21 @@ enum Fold {
     Y(i32),
 }
 
+impl FromStr for Fold {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let regex = static_regex!("fold along (.)=(.*)");
+        let caps = regex_captures(regex, s)?;
+        let value: i32 = capture_group(&caps, 2).parse()?;
+        Ok(match capture_group(&caps, 1) {
+            "x" => Fold::X(value),
+            "y" => Fold::Y(value),
+            _ => bail!("invalid axis"),
+        })
+    }
+}
+
 impl Fold {
     fn fold(&self, p: Point) -> Point {
         match self {
@@ -37,37 +63,23 @@ impl Fold {
 }
 
 fn parse_input(input: &str) -> Result<(HashSet<Point>, Vec<Fold>)> {
-    let parts: Vec<_> = input.split("\n\n").collect();
-    let points = parts[0].lines().map(|l| l.parse()).collect::<Result<HashSet<_>>>()?;
-
-    let regex = static_regex!("fold along (.)=(.*)");
-    let folds = parts[1].lines().map(|l| {
-        let caps = regex_captures(regex, l)?;
-        let value: i32 = capture_group(&caps, 2).parse().unwrap();
-        let fold = match capture_group(&caps, 1) {
-            "x" => Fold::X(value),
-            "y" => Fold::Y(value),
-            _ => bail!("invalid"),
-        };
-        Ok(fold)
-    }).collect::<Result<Vec<_>>>()?;
+    let parts = sections(input);
+    ensure!(parts.len() == 2, "Expected points and folds, found {} sections", parts.len());
+    let points = parts[0].parse_lines()?.into_iter().collect();
+    let folds = parts[1].parse_lines()?;
     Ok((points, folds))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_2021::puzzle::this_day;
 
     #[test]
-    fn example() {
-        let (mut points, folds) = parse_input(include_str!("example.txt")).unwrap();
-
-        points = folds[0].fold_all(&points);
-        assert_eq!(points.len(), 17);
-
-        for fold in &folds[1..] {
-            points = fold.fold_all(&points);
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            assert!(example.check(1, part1(example.input()).unwrap()));
+            assert!(example.check(2, part2(example.input()).unwrap()));
         }
-        assert_eq!(Point::display_points(&points, '█', ' '), "█████\n█   █\n█   █\n█   █\n█████\n");
     }
 }
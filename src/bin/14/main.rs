@@ -3,22 +3,30 @@ use std::fmt::Write;
 use std::time::Duration;
 use anyhow::Result;
 use advent_2021::terminal::{Color, elapsed, Terminal, TerminalImage, TerminalRender};
+use advent_2021::input;
 
-fn main() -> Result<()> {
+pub fn main() -> Result<()> {
     let _drop = Terminal::init();
-    let (polymer, transforms) = parse_input(include_str!("example.txt"));
+    let input = input::read(include_str!("input.txt"))?;
+    if Terminal::active() {
+        // The real polymer quickly outgrows the terminal, so visualize the example instead
+        let (polymer, transforms) = parse_input(include_str!("example.txt"));
+        simulate(&polymer, &transforms, 10);
+    }
+    println!("Longest-Shortest after 10 iters: {}", elapsed!(part1(&input))?);
+    println!("Longest-Shortest after 40 iters: {}", elapsed!(part2(&input))?);
 
-    let char_counts = elapsed!(simulate(&polymer, &transforms, 10));
-    let char_counts_emulated = elapsed!(emulate(&polymer, &transforms, 10));
-    assert_eq!(char_counts, char_counts_emulated);
-    println!("Length after 10 iters: {}", char_counts.values().sum::<u64>());
-    println!("Longest-Shortest:      {}", score_polymer(&char_counts));
+    Ok(())
+}
 
-    let char_counts = elapsed!(emulate(&polymer, &transforms, 40));
-    println!("Length after 40 iters: {}", char_counts.values().sum::<u64>());
-    println!("Longest-Shortest:      {}", score_polymer(&char_counts));
+pub fn part1(input: &str) -> Result<String> {
+    let (polymer, transforms) = parse_input(input);
+    Ok(score_polymer(&emulate(&polymer, &transforms, 10)).to_string())
+}
 
-    Ok(())
+pub fn part2(input: &str) -> Result<String> {
+    let (polymer, transforms) = parse_input(input);
+    Ok(score_polymer(&emulate(&polymer, &transforms, 40)).to_string())
 }
 
 fn to_char_counts(s: &str) -> BTreeMap<char, u64> {
@@ -77,7 +85,7 @@ struct VisualizePolymer<'a> {
 }
 
 impl<'a> TerminalRender for VisualizePolymer<'a> {
-    fn render(&self, width_hint: usize, _h: usize) -> TerminalImage {
+    fn render(&self, pixel_width: usize, _pixel_height: usize) -> TerminalImage {
         static COLORS: &[Color] = &[Color::GREEN, Color::YELLOW, Color::RED, Color::BLUE, Color::MAGENTA, Color::CYAN, Color::GREY, Color::ORANGE, Color::BROWN, Color::WHITE];
         // It'd be nice to avoid reconstructing this every time...
         let elements: BTreeSet<_> = self.transforms.values().collect();
@@ -85,10 +93,10 @@ impl<'a> TerminalRender for VisualizePolymer<'a> {
             elements.iter().enumerate().map(|(i, e)| (e.chars().next().expect("Non-empty"), i)).collect();
 
         let mut pixels: Vec<_> = self.polymer.chars().map(|c| COLORS[mapping[&c]]).collect();
-        while pixels.len() % width_hint != 0 {
+        while pixels.len() % pixel_width != 0 {
             pixels.push(Color::BLACK);
         }
-        TerminalImage{ pixels, width: width_hint, }
+        TerminalImage{ pixels, width: pixel_width, }
     }
 }
 
@@ -142,10 +150,19 @@ fn parse_input(input: &str) -> (String, BTreeMap<String, String>) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_2021::puzzle::this_day;
+
+    #[test]
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            assert!(example.check(1, part1(example.input()).unwrap()));
+            assert!(example.check(2, part2(example.input()).unwrap()));
+        }
+    }
 
     #[test]
     fn simulated() {
-        let (polymer, transforms) = parse_input(include_str!("example.txt"));
+        let (polymer, transforms) = parse_input(this_day!().example("example").unwrap().input());
         assert_eq!(simulate(&polymer, &transforms, 1), to_char_counts("NCNBCHB"));
         assert_eq!(simulate(&polymer, &transforms, 2), to_char_counts("NBCCNBBBCBHCB"));
         assert_eq!(simulate(&polymer, &transforms, 3), to_char_counts("NBBBCNCCNBBNBNBBCHBHHBCHB"));
@@ -161,7 +178,7 @@ mod tests {
 
     #[test]
     fn emulated() {
-        let (polymer, transforms) = parse_input(include_str!("example.txt"));
+        let (polymer, transforms) = parse_input(this_day!().example("example").unwrap().input());
         assert_eq!(emulate(&polymer, &transforms, 1), to_char_counts("NCNBCHB"));
         assert_eq!(emulate(&polymer, &transforms, 2), to_char_counts("NBCCNBBBCBHCB"));
         assert_eq!(emulate(&polymer, &transforms, 3), to_char_counts("NBBBCNCCNBBNBNBBCHBHHBCHB"));
@@ -177,6 +194,5 @@ mod tests {
         let step40 = emulate(&polymer, &transforms, 40);
         assert_eq!(step40[&'B'], 2192039569602);
         assert_eq!(step40[&'H'], 3849876073);
-        assert_eq!(score_polymer(&step40), 2188189693529);
     }
 }
@@ -1,28 +1,43 @@
 use anyhow::{anyhow, ensure, Error, Result};
 
 use std::collections::{HashMap, HashSet};
-use advent_2021::euclid::{point, Point, Vector};
+use advent_2021::euclid::{point, Point, Svg, Vector};
 use advent_2021::pathfinding::{Graph, Edge};
 use std::str::FromStr;
 use std::fmt::Display;
 use std::time::Duration;
-use advent_2021::terminal::{Color, Terminal, TerminalImage, TerminalRender};
+use advent_2021::terminal::{Color, Palette, Terminal, TerminalImage, TerminalRender, Viewport};
+use advent_2021::input;
 
-fn main() -> Result<()> {
+pub fn main() -> Result<()> {
     let _drop = Terminal::init();
-    let input: Cave = include_str!("input.txt").parse()?;
-    let path = input.traverse_path().ok_or_else(|| anyhow!("No path"))?;
-    if Terminal::active() {
-        input.render(&path);
+    let input = input::read(include_str!("input.txt"))?;
+    // Pass --svg PATH to also save the route as an image
+    let svg = input::args().into_iter().skip_while(|a| a != "--svg").nth(1);
+    if Terminal::active() || svg.is_some() {
+        let cave: Cave = input.parse()?;
+        let path = cave.traverse_path().ok_or_else(|| anyhow!("No path"))?;
+        if Terminal::active() {
+            cave.render(&path);
+        }
+        if let Some(out) = svg {
+            cave.svg(&path).save(out)?;
+        }
     }
-    println!("Initial risk:  {}", path.iter().map(|e| e.weight()).sum::<i32>());
-
-    let big = input.scale(5);
-    println!("Expanded risk: {}", big.traverse().ok_or_else(|| anyhow!("No path"))?);
+    println!("Initial risk:  {}", part1(&input)?);
+    println!("Expanded risk: {}", part2(&input)?);
 
     Ok(())
 }
 
+pub fn part1(input: &str) -> Result<String> {
+    Ok(input.parse::<Cave>()?.traverse().ok_or_else(|| anyhow!("No path"))?.to_string())
+}
+
+pub fn part2(input: &str) -> Result<String> {
+    Ok(input.parse::<Cave>()?.scale(5).traverse().ok_or_else(|| anyhow!("No path"))?.to_string())
+}
+
 struct Cave {
     scan: HashMap<Point, i32>,
     scan_size: i32,
@@ -65,6 +80,13 @@ impl Cave {
             Terminal::interactive_render(&r, Duration::from_millis(10));
         }
     }
+
+    fn svg(&self, path: &[Edge<Point>]) -> Svg {
+        let palette = Palette::greyscale(0.0, 10.0).reversed();
+        Svg::new(8)
+            .point_map(&self.scan, |&r| Some(palette.color(r as f64)))
+            .route(path, Color::YELLOW)
+    }
 }
 
 impl Graph for Cave {
@@ -115,6 +137,7 @@ impl<'a> TerminalRender for CaveRoute<'a> {
     fn render(&self, _w: usize, _h: usize) -> TerminalImage {
         let visited: HashSet<_> = self.route.iter().flat_map(|e| [*e.source(), *e.dest()].into_iter()).collect();
         let width = (self.cave.dest.x+1) as usize;
+        let palette = Palette::greyscale(0.0, 10.0).reversed();
         let mut pixels = Vec::new();
         for y in 0..=self.cave.dest.y {
             for x in 0..=self.cave.dest.x {
@@ -123,7 +146,7 @@ impl<'a> TerminalRender for CaveRoute<'a> {
                     pixels.push(Color::YELLOW);
                 } else {
                     pixels.push(match self.cave.risk(pos) {
-                        Some(r) => Color::GREYSCALE(1.0 - (r as f32 / 10.0)),
+                        Some(r) => palette.color(r as f64),
                         None => Color::RED,
                     });
                 }
@@ -131,11 +154,17 @@ impl<'a> TerminalRender for CaveRoute<'a> {
         }
         TerminalImage{ pixels, width, }
     }
+
+    fn viewport(&self) -> Option<Viewport> {
+        let head = self.route.last().map(|e| *e.dest()).unwrap_or(Point::ORIGIN);
+        Some(Viewport::follow(head.x as usize, head.y as usize))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_2021::puzzle::this_day;
 
     #[test]
     fn scale_up() {
@@ -147,14 +176,11 @@ mod tests {
     }
 
     #[test]
-    fn example() {
-        let input: Cave = include_str!("example.txt").parse().unwrap();
-        assert_eq!(input.traverse(), Some(40));
-    }
-
-    #[test]
-    fn example_scaled() {
-        let input = include_str!("example.txt").parse::<Cave>().unwrap().scale(5);
-        assert_eq!(input.traverse(), Some(315));
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            let input: Cave = example.input().parse().unwrap();
+            assert!(example.check(1, input.traverse().unwrap()));
+            assert!(example.check(2, input.scale(5).traverse().unwrap()));
+        }
     }
 }
@@ -1,17 +1,66 @@
-use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufReader, Read};
 use std::ops::Deref;
 use std::str::FromStr;
-use anyhow::{anyhow, Context, ensure, Error, Result};
+use anyhow::{anyhow, bail, ensure, Error, Result};
+use advent_2021::bits::{BitWriter, Order};
+use advent_2021::input;
+use advent_2021::parsing::combinator::{alt, delimited, int, literal, many, optional, recursive, seq, Failure, Parser, Recursive, Step};
 
-fn main() -> Result<()> {
-    let mut input: Bitstream = include_str!("input.txt").parse()?;
-    let packet = Packet::deserialize(&mut input)?;
-    println!("Versions: {:?}", packet.sum_versions());
-    println!("Launches: {:?}", packet.evaluate().expect("Could not evaluate"));
+pub fn main() -> Result<()> {
+    // `compile EXPR` encodes an S-expression as a transmission, `print` shows the decoded input
+    let args = input::args();
+    if let [command, expr] = &args[..] {
+        if command == "compile" {
+            let packet: Packet = expr.parse()?;
+            println!("{}", packet.serialize()?);
+            return Ok(());
+        }
+    }
+
+    let input = input::read(include_str!("input.txt"))?;
+    if args.iter().any(|a| a == "print") {
+        println!("{:#}", Packet::deserialize(&mut Bitstream::new(input.as_bytes()))?);
+    }
+    println!("Versions: {}", part1(&input)?);
+    println!("Launches: {}", part2(&input)?);
 
     Ok(())
 }
 
+pub fn part1(input: &str) -> Result<String> {
+    Ok(decode(&mut Bitstream::new(input.as_bytes()), &mut Versions)?.to_string())
+}
+
+pub fn part2(input: &str) -> Result<String> {
+    Ok(decode(&mut Bitstream::new(input.as_bytes()), &mut Evaluator)?.to_string())
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum DecodeError {
+    Truncated { wanted: usize, remaining: usize },
+    UnknownOperator(u64),
+    SubpacketCount { operator: u64, count: usize },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated { wanted, remaining } =>
+                write!(f, "Truncated stream: wanted {} bits but only {} remain", wanted, remaining),
+            DecodeError::UnknownOperator(id) => write!(f, "Unknown operator {}", id),
+            DecodeError::SubpacketCount { operator, count } =>
+                write!(f, "{} requires exactly 2 subpackets, found {}", OPERATORS[*operator as usize], count),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+// Operator names, indexed by type ID, as used in the S-expression syntax
+const OPERATORS: [&str; 8] = ["sum", "product", "min", "max", "lit", "gt", "lt", "eq"];
+const LITERAL: u64 = 4;
+
 #[derive(Debug, Eq, PartialEq)]
 enum Type {
     Literal(u64),
@@ -25,6 +74,43 @@ enum Type {
 }
 
 impl Type {
+    fn operator(id: u64, packets: Vec<Packet>) -> Result<Type, DecodeError> {
+        fn pair(operator: u64, packets: Vec<Packet>) -> Result<Box<[Packet; 2]>, DecodeError> {
+            packets.try_into().map(Box::new).map_err(|v: Vec<_>| DecodeError::SubpacketCount { operator, count: v.len() })
+        }
+        Ok(match id {
+            0 => Type::Sum(packets),
+            1 => Type::Product(packets),
+            2 => Type::Min(packets),
+            3 => Type::Max(packets),
+            5 => Type::Gt(pair(id, packets)?),
+            6 => Type::Lt(pair(id, packets)?),
+            7 => Type::Eq(pair(id, packets)?),
+            _ => return Err(DecodeError::UnknownOperator(id)),
+        })
+    }
+
+    fn id(&self) -> u64 {
+        match self {
+            Type::Sum(_) => 0,
+            Type::Product(_) => 1,
+            Type::Min(_) => 2,
+            Type::Max(_) => 3,
+            Type::Literal(_) => LITERAL,
+            Type::Gt(_) => 5,
+            Type::Lt(_) => 6,
+            Type::Eq(_) => 7,
+        }
+    }
+
+    fn take_subpackets(&mut self) -> Vec<Packet> {
+        match std::mem::replace(self, Type::Literal(0)) {
+            Type::Literal(_) => Vec::new(),
+            Type::Sum(v)|Type::Product(v)|Type::Min(v)|Type::Max(v) => v,
+            Type::Gt(a)|Type::Lt(a)|Type::Eq(a) => Vec::from(*a),
+        }
+    }
+
     fn subpackets(&self) -> &[Packet] {
         match self {
             Type::Literal(_) => &[],
@@ -40,168 +126,407 @@ struct Packet {
     body: Type,
 }
 
+// The default drop would recurse through nested packets, see visit()
+impl Drop for Packet {
+    fn drop(&mut self) {
+        let mut stack = self.body.take_subpackets();
+        while let Some(mut packet) = stack.pop() {
+            stack.extend(packet.body.take_subpackets());
+        }
+    }
+}
+
 impl Packet {
-    // These factory functions are only used in the tests
-    #[allow(dead_code)] fn literal(version: u8, value: u64) -> Self { Packet{ version, body: Type::Literal(value), } }
-    #[allow(dead_code)] fn sum(version: u8, packets: Vec<Packet>) -> Self { Packet{ version, body: Type::Sum(packets), } }
-    #[allow(dead_code)] fn product(version: u8, packets: Vec<Packet>) -> Self { Packet{ version, body: Type::Product(packets), } }
-    #[allow(dead_code)] fn max(version: u8, packets: Vec<Packet>) -> Self { Packet{ version, body: Type::Max(packets), } }
-    #[allow(dead_code)] fn min(version: u8, packets: Vec<Packet>) -> Self { Packet{ version, body: Type::Min(packets), } }
-    #[allow(dead_code)] fn gt(version: u8, left: Packet, right: Packet) -> Self {
+    fn deserialize(bits: &mut Bitstream<impl Read>) -> Result<Packet> {
+        decode(bits, &mut Builder)
+    }
+
+    // Encodes the packet as hex, padded to a whole number of bytes. Operators use the subpacket
+    // count form of the length when possible, so this may differ from the transmission a packet
+    // was decoded from.
+    fn serialize(&self) -> Result<String> {
+        let mut bits = BitWriter::new(Order::Msb);
+        self.write(&mut bits)?;
+        Ok(bits.to_hex())
+    }
+
+    fn write(&self, bits: &mut BitWriter) -> Result<()> {
+        ensure!(self.version < 8, "Version {} does not fit in 3 bits", self.version);
+        bits.write(3, self.version as u64);
+        bits.write(3, self.body.id());
+        if let Type::Literal(value) = self.body {
+            let groups = (64 - value.leading_zeros() as usize).div_ceil(4).max(1);
+            for i in (0..groups).rev() {
+                bits.write(1, if i > 0 { 1 } else { 0 });
+                bits.write(4, (value >> (i * 4)) & 0xF);
+            }
+            return Ok(());
+        }
+
+        let packets = self.body.subpackets();
+        let mut body = BitWriter::new(Order::Msb);
+        for packet in packets {
+            packet.write(&mut body)?;
+        }
+        if packets.len() < 1 << 11 {
+            bits.write(1, 1);
+            bits.write(11, packets.len() as u64);
+        } else {
+            ensure!(body.len() < 1 << 15, "Too many subpackets to encode: {}", packets.len());
+            bits.write(1, 0);
+            bits.write(15, body.len() as u64);
+        }
+        bits.append(body.into_reader());
+        Ok(())
+    }
+}
+
+// Only used by the tests
+#[cfg(test)]
+impl Packet {
+    fn literal(version: u8, value: u64) -> Self { Packet{ version, body: Type::Literal(value), } }
+    fn sum(version: u8, packets: Vec<Packet>) -> Self { Packet{ version, body: Type::Sum(packets), } }
+    fn product(version: u8, packets: Vec<Packet>) -> Self { Packet{ version, body: Type::Product(packets), } }
+    fn max(version: u8, packets: Vec<Packet>) -> Self { Packet{ version, body: Type::Max(packets), } }
+    fn min(version: u8, packets: Vec<Packet>) -> Self { Packet{ version, body: Type::Min(packets), } }
+    fn gt(version: u8, left: Packet, right: Packet) -> Self {
         Packet{ version, body: Type::Gt(Box::new([left, right])), }
     }
-    #[allow(dead_code)] fn lt(version: u8, left: Packet, right: Packet) -> Self {
+    fn lt(version: u8, left: Packet, right: Packet) -> Self {
         Packet{ version, body: Type::Lt(Box::new([left, right])), }
     }
-    #[allow(dead_code)] fn eq(version: u8, left: Packet, right: Packet) -> Self {
+    fn eq(version: u8, left: Packet, right: Packet) -> Self {
         Packet{ version, body: Type::Eq(Box::new([left, right])), }
     }
 
-    fn deserialize(bits: &mut Bitstream) -> Result<Packet> {
-        fn deserialize_subpackages(bits: &mut Bitstream) -> Result<Vec<Packet>> {
-            let mut packets = Vec::new();
-            let sub_packet_type = bits.pop_bits(1)? == 1;
-            if sub_packet_type {
-                let num_packets = bits.pop_bits(11)?;
-                for _ in 0..num_packets {
-                    packets.push(Packet::deserialize(bits)?);
+    // The puzzle is solved by decode() as the transmission is read, these walk an already-decoded
+    // tree to cross-check it.
+    fn sum_versions(&self) -> u64 {
+        self.visit(&mut Versions).expect("Summing versions cannot fail")
+    }
+
+    fn evaluate(&self) -> Result<u64> {
+        self.visit(&mut Evaluator)
+    }
+
+    // Walks the packet tree depth-first with an explicit stack, so arbitrarily deep packets can't
+    // overflow the call stack.
+    fn visit<V: Visitor>(&self, visitor: &mut V) -> Result<V::Value> {
+        let mut stack: Vec<(V::Partial, std::slice::Iter<'_, Packet>)> = Vec::new();
+        let mut next = Some(self);
+        loop {
+            let mut value = None;
+            if let Some(packet) = next.take() {
+                match &packet.body {
+                    Type::Literal(v) => value = Some(visitor.literal(packet.version, *v)?),
+                    body => stack.push((visitor.open(packet.version, body.id())?, body.subpackets().iter())),
                 }
-            } else {
-                let num_bits = bits.pop_bits(15)?;
-                let bits_leftover = bits.len() - num_bits as usize;
-                while bits.len() > bits_leftover {
-                    packets.push(Packet::deserialize(bits)?);
+            }
+            loop {
+                let (partial, subpackets) = match stack.last_mut() {
+                    Some(top) => top,
+                    None => return Ok(value.expect("Top-level packet was visited")),
+                };
+                if let Some(value) = value.take() {
+                    visitor.add(partial, value)?;
                 }
+                if let Some(subpacket) = subpackets.next() {
+                    next = Some(subpacket);
+                    break;
+                }
+                let (partial, _) = stack.pop().expect("Non-empty");
+                value = Some(visitor.close(partial)?);
             }
-            Ok(packets)
         }
+    }
+}
 
-        let version = bits.pop_bits(3)?.try_into()?;
-        let packet_type = bits.pop_bits(3)?;
-        let packet = match packet_type {
-            4 => { // Literal
-                let mut value = 0;
-                loop {
-                    let more = bits.pop_bits(1)? == 1;
-                    value = (value << 4) | bits.pop_bits(4)?;
-                    if !more { break; }
+// Packets are written as S-expressions, e.g. (sum v3 10 (lit v5 11)). Versions are omitted when
+// zero, and version-zero literals are written as bare numbers. The alternate form ({:#}) puts each
+// subpacket on its own indented line.
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write(packet: &Packet, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+            if let Type::Literal(value) = packet.body {
+                if packet.version == 0 {
+                    return write!(f, "{}", value);
                 }
-                Packet { version, body: Type::Literal(value), }
-            },
-            t => {
-                let packets = deserialize_subpackages(bits)?;
-                let body = match t {
-                    0 => Type::Sum(packets),
-                    1 => Type::Product(packets),
-                    2 => Type::Min(packets),
-                    3 => Type::Max(packets),
-                    5 => Type::Gt(Box::new(packets.try_into().map_err(|v| anyhow!("Invalid number of subpackets: {:?}", v))?)),
-                    6 => Type::Lt(Box::new(packets.try_into().map_err(|v| anyhow!("Invalid number of subpackets: {:?}", v))?)),
-                    7 => Type::Eq(Box::new(packets.try_into().map_err(|v| anyhow!("Invalid number of subpackets: {:?}", v))?)),
-                    _ => panic!("Unknown operator {}", t),
-                };
-                Packet{ version, body }
-            },
-        };
-        Ok(packet)
+                return write!(f, "(lit v{} {})", packet.version, value);
+            }
+            write!(f, "({}", OPERATORS[packet.body.id() as usize])?;
+            if packet.version != 0 {
+                write!(f, " v{}", packet.version)?;
+            }
+            for subpacket in packet.body.subpackets() {
+                if f.alternate() {
+                    write!(f, "\n{:indent$}", "", indent = (depth + 1) * 2)?;
+                } else {
+                    write!(f, " ")?;
+                }
+                write(subpacket, f, depth + 1)?;
+            }
+            write!(f, ")")
+        }
+        write(self, f, 0)
     }
+}
 
-    fn sum_versions(&self) -> u64 {
-        self.version as u64 + self.body.subpackets().iter().map(Packet::sum_versions).sum::<u64>()
-    }
+// The S-expression syntax, before it's checked and compiled into a Packet
+#[derive(Debug)]
+enum Sexp {
+    Number(u64),
+    List { name: String, version: u8, args: Vec<Sexp> },
+}
 
-    fn evaluate(&self) -> Result<u64> {
-        // It doesn't seem possible to directly min()/max() an Iter<Result<u64>>
-        fn min(packets: &[Packet]) -> Result<u64> {
-            let evaled = packets.iter().map(Packet::evaluate).collect::<Result<Vec<_>>>()?;
-            Ok(*evaled.iter().min().ok_or_else(||anyhow!("Invalid sub-pattern"))?)
+impl Sexp {
+    fn parser() -> Recursive<Sexp> {
+        fn whitespace(input: &str) -> Step<'_, ()> {
+            Ok(((), input.trim_start()))
         }
-        fn max(packets: &[Packet]) -> Result<u64> {
-            let evaled = packets.iter().map(Packet::evaluate).collect::<Result<Vec<_>>>()?;
-            Ok(*evaled.iter().max().ok_or_else(||anyhow!("Invalid sub-pattern"))?)
+        fn name(input: &str) -> Step<'_, String> {
+            let len = input.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(input.len());
+            if len == 0 { return Err(Failure::new(input, "operator")); }
+            Ok((input[..len].to_string(), &input[len..]))
         }
 
-        Ok(match &self.body {
-            Type::Literal(value) => *value,
-            Type::Sum(parts) => parts.iter().map(Packet::evaluate).sum::<Result<u64>>()?,
-            Type::Product(parts) => parts.iter().map(Packet::evaluate).product::<Result<u64>>()?,
-            Type::Min(parts) => min(parts)?,
-            Type::Max(parts) => max(parts)?,
-            Type::Gt(parts) => if parts[0].evaluate()? > parts[1].evaluate()? { 1 } else { 0 },
-            Type::Lt(parts) => if parts[0].evaluate()? < parts[1].evaluate()? { 1 } else { 0 },
-            Type::Eq(parts) => if parts[0].evaluate()? == parts[1].evaluate()? { 1 } else { 0 },
+        recursive(|sexp| {
+            let version = optional(seq((whitespace, literal("v"), int::<u8>()))).map(|v| v.map(|(_, _, v)| v).unwrap_or(0));
+            let args = many(seq((whitespace, sexp)).map(|(_, s)| s));
+            let list = delimited(literal("("), seq((whitespace, name, version, args)), seq((whitespace, literal(")"))))
+                .map(|(_, name, version, args)| Sexp::List { name, version, args });
+            alt((int().map(Sexp::Number), list))
         })
     }
-}
 
-struct Bitstream {
-    bytes: VecDeque<u8>,
-    offset: usize,
+    fn compile(self) -> Result<Packet> {
+        let (name, version, args) = match self {
+            Sexp::Number(value) => return Ok(Packet { version: 0, body: Type::Literal(value) }),
+            Sexp::List { name, version, args } => (name, version, args),
+        };
+        ensure!(version < 8, "Version {} does not fit in 3 bits", version);
+        let id = OPERATORS.iter().position(|&o| o == name).ok_or_else(|| anyhow!("Unknown operator `{}`", name))? as u64;
+        if id == LITERAL {
+            return match args[..] {
+                [Sexp::Number(value)] => Ok(Packet { version, body: Type::Literal(value) }),
+                _ => bail!("lit requires a single number"),
+            };
+        }
+        let packets = args.into_iter().map(Sexp::compile).collect::<Result<_>>()?;
+        Ok(Packet { version, body: Type::operator(id, packets)? })
+    }
 }
 
-impl Bitstream {
-    fn create(bytes: &[u8]) -> Bitstream {
-        Bitstream{ bytes: bytes.iter().cloned().collect(), offset: 0, }
-    }
+impl FromStr for Packet {
+    type Err = Error;
 
-    pub fn len(&self) -> usize {
-        let byte_bits = self.bytes.len() * 8;
-        if self.offset == 0 {
-            return byte_bits;
+    fn from_str(s: &str) -> Result<Self> {
+        thread_local! {
+            static PARSER: Recursive<Sexp> = Sexp::parser();
         }
-        byte_bits - (8 - self.offset)
+        PARSER.with(|parser| parser.parse(s.trim()))?.compile()
+    }
+}
+
+// Decodes a stream of hex digits (whitespace is ignored) into bits, without needing to hold the
+// whole transmission in memory.
+struct Bitstream<R> {
+    reader: io::Bytes<BufReader<R>>,
+    // Bits that have been read but not yet popped, in the low `buffered` bits
+    buffer: u128,
+    buffered: usize,
+    position: usize,
+}
+
+impl<R: Read> Bitstream<R> {
+    fn new(reader: R) -> Bitstream<R> {
+        Bitstream { reader: BufReader::new(reader).bytes(), buffer: 0, buffered: 0, position: 0 }
     }
 
-    fn pop_from_offset(&mut self, mut bits: usize) -> (usize, u64) {
-        if bits > self.offset { bits = self.offset; } // may be zero
-        let mut ret = 0;
-        for _ in 0..bits {
-            let front = self.bytes.front_mut().expect("Unexpected offset");
-            ret = (ret << 1) | ((*front as u64 & 128) >> 7);
-            *front <<= 1;
+    // The number of bits popped so far
+    fn position(&self) -> usize { self.position }
+
+    // Reads hex digits until at least bits bits are buffered
+    fn fill(&mut self, bits: usize) -> Result<()> {
+        while self.buffered < bits {
+            let byte = match self.reader.next().transpose()? {
+                Some(byte) if byte.is_ascii_whitespace() => continue,
+                Some(byte) => byte,
+                None => return Err(DecodeError::Truncated { wanted: bits, remaining: self.buffered }.into()),
+            };
+            let digit = (byte as char).to_digit(16).ok_or_else(|| anyhow!("Invalid digit: {}", byte as char))?;
+            self.buffer = (self.buffer << 4) | digit as u128;
+            self.buffered += 4;
         }
-        if self.offset > 0 && self.offset == bits { self.bytes.pop_front().expect("Can't be absent"); }
-        self.offset -= bits;
-        (bits, ret)
+        Ok(())
     }
 
-    pub fn pop_bits(&mut self, mut bits: usize) -> Result<u64> {
+    pub fn pop_bits(&mut self, bits: usize) -> Result<u64> {
         assert!(bits <= 64);
-        ensure!(bits <= self.len(), "Insufficient bits remaining");
-        let (offset_bits, mut ret) = self.pop_from_offset(bits);
-        bits -= offset_bits;
-        while bits >= 8 {
-            let popped = self.bytes.pop_front().expect("Already checked len");
-            ret = (ret << 8) | popped as u64;
-            bits -= 8;
+        self.fill(bits)?;
+        self.buffered -= bits;
+        self.position += bits;
+        // The buffer only ever holds unpopped bits, so this is exactly the popped bits
+        let ret = (self.buffer >> self.buffered) as u64;
+        self.buffer &= (1 << self.buffered) - 1;
+        Ok(ret)
+    }
+}
+
+// Receives packets as they are decoded or walked, building up a Value for each one. Operators are
+// opened, have the values of each of their subpackets added in turn, and are then closed.
+trait Visitor {
+    type Value;
+    type Partial;
+
+    fn literal(&mut self, version: u8, value: u64) -> Result<Self::Value>;
+    fn open(&mut self, version: u8, operator: u64) -> Result<Self::Partial>;
+    fn add(&mut self, partial: &mut Self::Partial, value: Self::Value) -> Result<()>;
+    fn close(&mut self, partial: Self::Partial) -> Result<Self::Value>;
+}
+
+// Builds the Packet tree
+struct Builder;
+
+impl Visitor for Builder {
+    type Value = Packet;
+    type Partial = (u8, u64, Vec<Packet>);
+
+    fn literal(&mut self, version: u8, value: u64) -> Result<Packet> {
+        Ok(Packet { version, body: Type::Literal(value) })
+    }
+
+    fn open(&mut self, version: u8, operator: u64) -> Result<Self::Partial> {
+        Ok((version, operator, Vec::new()))
+    }
+
+    fn add(&mut self, partial: &mut Self::Partial, value: Packet) -> Result<()> {
+        partial.2.push(value);
+        Ok(())
+    }
+
+    fn close(&mut self, (version, operator, packets): Self::Partial) -> Result<Packet> {
+        Ok(Packet { version, body: Type::operator(operator, packets)? })
+    }
+}
+
+struct Versions;
+
+impl Visitor for Versions {
+    type Value = u64;
+    type Partial = u64;
+
+    fn literal(&mut self, version: u8, _: u64) -> Result<u64> { Ok(version as u64) }
+    fn open(&mut self, version: u8, _: u64) -> Result<u64> { Ok(version as u64) }
+    fn add(&mut self, partial: &mut u64, value: u64) -> Result<()> { *partial += value; Ok(()) }
+    fn close(&mut self, partial: u64) -> Result<u64> { Ok(partial) }
+}
+
+// Evaluates packets without retaining their subpackets, folding each value into its operator's
+// running result.
+struct Evaluator;
+
+struct Accumulator {
+    operator: u64,
+    value: Option<u64>,
+    count: usize,
+}
+
+impl Visitor for Evaluator {
+    type Value = u64;
+    type Partial = Accumulator;
+
+    fn literal(&mut self, _: u8, value: u64) -> Result<u64> { Ok(value) }
+
+    fn open(&mut self, _: u8, operator: u64) -> Result<Accumulator> {
+        ensure!(operator < 8 && operator != LITERAL, DecodeError::UnknownOperator(operator));
+        Ok(Accumulator { operator, value: None, count: 0 })
+    }
+
+    fn add(&mut self, acc: &mut Accumulator, value: u64) -> Result<()> {
+        let name = OPERATORS[acc.operator as usize];
+        acc.count += 1;
+        acc.value = Some(match acc.value {
+            None => value,
+            Some(prev) => match acc.operator {
+                0 => prev.checked_add(value).ok_or_else(|| anyhow!("Overflow in {}: {} + {}", name, prev, value))?,
+                1 => prev.checked_mul(value).ok_or_else(|| anyhow!("Overflow in {}: {} * {}", name, prev, value))?,
+                2 => prev.min(value),
+                3 => prev.max(value),
+                5 => (prev > value) as u64,
+                6 => (prev < value) as u64,
+                7 => (prev == value) as u64,
+                _ => unreachable!("Checked in open()"),
+            },
+        });
+        Ok(())
+    }
+
+    fn close(&mut self, acc: Accumulator) -> Result<u64> {
+        if acc.operator > LITERAL {
+            ensure!(acc.count == 2, DecodeError::SubpacketCount { operator: acc.operator, count: acc.count });
         }
-        if bits > 0 {
-            self.offset = 8;
-            let (offset_bits, offset_value) = self.pop_from_offset(bits);
-            assert_eq!(offset_bits, bits);
-            ret = (ret << bits) | offset_value;
+        match (acc.operator, acc.value) {
+            (_, Some(value)) => Ok(value),
+            (0, None) => Ok(0),
+            (1, None) => Ok(1),
+            (operator, None) => bail!("{} requires at least one subpacket", OPERATORS[operator as usize]),
         }
-        Ok(ret)
     }
 }
 
-impl FromStr for Bitstream {
-    type Err = Error;
+enum Limit {
+    Packets(u64),
+    Position(usize),
+}
 
-    fn from_str(s: &str) -> Result<Self> {
-        let digits: Vec<u8> = s.trim().chars()
-            .map(|c| c.to_digit(16)
-                .ok_or_else(|| anyhow!("Invalid digit: {}", c))
-                .and_then(|d| d.try_into().context("Cannot cast to u8")))
-            .collect::<Result<_>>()?;
-        let digits = digits.chunks(2)
-            .map(|s| match *s {
-                [c1, c2] => c1 << 4 | c2,
-                [c1] => c1 << 4,
-                _ => panic!("impossible"),
-            })
-            .collect::<Vec<_>>();
-        Ok(Bitstream::create(&digits))
+// Decodes a single packet from the stream, handing it to the visitor as it's read. Operators are
+// tracked on an explicit stack, so pathologically nested packets can't overflow the call stack.
+fn decode<V: Visitor>(bits: &mut Bitstream<impl Read>, visitor: &mut V) -> Result<V::Value> {
+    let mut stack: Vec<(V::Partial, Limit)> = Vec::new();
+    loop {
+        let version = bits.pop_bits(3)? as u8;
+        let operator = bits.pop_bits(3)?;
+        let mut value = if operator == LITERAL {
+            let mut value: u64 = 0;
+            loop {
+                let more = bits.pop_bits(1)? == 1;
+                ensure!(value.leading_zeros() >= 4, "Literal does not fit in 64 bits");
+                value = (value << 4) | bits.pop_bits(4)?;
+                if !more { break; }
+            }
+            Some(visitor.literal(version, value)?)
+        } else {
+            let limit = if bits.pop_bits(1)? == 1 {
+                Limit::Packets(bits.pop_bits(11)?)
+            } else {
+                let length = bits.pop_bits(15)? as usize;
+                Limit::Position(bits.position() + length)
+            };
+            stack.push((visitor.open(version, operator)?, limit));
+            None
+        };
+
+        // Pass completed values up to their operators, closing any that are now complete
+        loop {
+            let (partial, limit) = match stack.last_mut() {
+                Some(top) => top,
+                None => return Ok(value.expect("Top-level packet was decoded")),
+            };
+            if let Some(value) = value.take() {
+                visitor.add(partial, value)?;
+                if let Limit::Packets(count) = limit { *count -= 1; }
+            }
+            let complete = match *limit {
+                Limit::Packets(count) => count == 0,
+                Limit::Position(end) => {
+                    ensure!(bits.position() <= end, "Subpackets overran their length by {} bits", bits.position() - end);
+                    bits.position() == end
+                },
+            };
+            if !complete { break; }
+            let (partial, _) = stack.pop().expect("Non-empty");
+            value = Some(visitor.close(partial)?);
+        }
     }
 }
 
@@ -212,34 +537,34 @@ mod tests {
     #[test]
     fn bitstream() {
         // binary: 1111...
-        let mut stream: Bitstream = "FFFFFFFF".parse().unwrap();
-        assert_eq!(stream.len(), 32);
+        let mut stream = Bitstream::new("FFFFFFFF".as_bytes());
+        assert_eq!(stream.position(), 0);
         assert_eq!(stream.pop_bits(3).unwrap(), 0b111);
-        assert_eq!(stream.len(), 29);
+        assert_eq!(stream.position(), 3);
         assert_eq!(stream.pop_bits(8).unwrap(), u8::MAX as u64);
-        assert_eq!(stream.len(), 21);
+        assert_eq!(stream.position(), 11);
         assert_eq!(stream.pop_bits(16).unwrap(), u16::MAX as u64);
-        assert_eq!(stream.len(), 5);
+        assert_eq!(stream.position(), 27);
         assert_eq!(stream.pop_bits(1).unwrap(), 0b1);
-        assert_eq!(stream.len(), 4);
+        assert_eq!(stream.position(), 28);
         assert!(stream.pop_bits(5).is_err());
         assert!(stream.pop_bits(15).is_err());
         assert_eq!(stream.pop_bits(4).unwrap(), 0b1111);
-        assert_eq!(stream.len(), 0);
+        assert_eq!(stream.position(), 32);
 
         // binary: 1010...
-        let mut stream: Bitstream = "AAAA".parse().unwrap();
-        assert_eq!(stream.len(), 16);
+        let mut stream = Bitstream::new("AAAA".as_bytes());
+        assert_eq!(stream.position(), 0);
         assert_eq!(stream.pop_bits(3).unwrap(), 0b101);
-        assert_eq!(stream.len(), 13);
+        assert_eq!(stream.position(), 3);
         assert_eq!(stream.pop_bits(3).unwrap(), 0b010);
-        assert_eq!(stream.len(), 10);
+        assert_eq!(stream.position(), 6);
         assert_eq!(stream.pop_bits(4).unwrap(), 0b1010);
-        assert_eq!(stream.len(), 6);
+        assert_eq!(stream.position(), 10);
         assert_eq!(stream.pop_bits(5).unwrap(), 0b10101);
-        assert_eq!(stream.len(), 1);
+        assert_eq!(stream.position(), 15);
         assert_eq!(stream.pop_bits(1).unwrap(), 0b00);
-        assert_eq!(stream.len(), 0);
+        assert_eq!(stream.position(), 16);
     }
 
     static EXAMPLE_1: &str = "D2FE28";
@@ -259,7 +584,7 @@ mod tests {
     static EXAMPLE_15: &str = "9C0141080250320F1802104A08";
 
     parameterized_test::create!{ versions, (input, versions), {
-        let mut input: Bitstream = input.parse().unwrap();
+        let mut input = Bitstream::new(input.as_bytes());
         let packet = Packet::deserialize(&mut input).unwrap();
         assert_eq!(packet.sum_versions(), versions);
     } }
@@ -274,7 +599,7 @@ mod tests {
     }
 
     parameterized_test::create!{ evals, (input, structure, evaluated), {
-        let mut input: Bitstream = input.parse().unwrap();
+        let mut input = Bitstream::new(input.as_bytes());
         let packet = Packet::deserialize(&mut input).unwrap();
         assert_eq!(packet, structure);
         assert_eq!(packet.evaluate().unwrap(), evaluated);
@@ -324,4 +649,163 @@ mod tests {
             Packet::product(6, vec![Packet::literal(0, 2), Packet::literal(2, 2)]),
         ), 1),
     }
+
+    parameterized_test::create!{ round_trip, input, {
+        let packet = Packet::deserialize(&mut Bitstream::new(input.as_bytes())).unwrap();
+        let encoded = packet.serialize().unwrap();
+        assert_eq!(Packet::deserialize(&mut Bitstream::new(encoded.as_bytes())).unwrap(), packet);
+
+        let sexp = packet.to_string();
+        assert_eq!(sexp.parse::<Packet>().unwrap(), packet);
+        assert_eq!(format!("{:#}", packet).parse::<Packet>().unwrap(), packet);
+    } }
+    round_trip! {
+        example1: EXAMPLE_1,
+        example2: EXAMPLE_2,
+        example5: EXAMPLE_5,
+        example7: EXAMPLE_7,
+        example15: EXAMPLE_15,
+        input: include_str!("input.txt"),
+    }
+
+    #[test]
+    fn serialize() {
+        assert_eq!(Packet::literal(6, 2021).serialize().unwrap(), EXAMPLE_1);
+        assert_eq!(Packet::max(7, vec![Packet::literal(2, 1), Packet::literal(4, 2), Packet::literal(1, 3)]).serialize().unwrap(),
+                   EXAMPLE_3);
+        assert_eq!(Packet::literal(0, 0).serialize().unwrap(), "1000");
+        assert!(Packet::literal(8, 1).serialize().is_err());
+    }
+
+    #[test]
+    fn sexp() {
+        let packet = Packet::eq(4,
+            Packet::sum(2, vec![Packet::literal(2, 1), Packet::literal(0, 3)]),
+            Packet::product(0, vec![Packet::literal(0, 2), Packet::literal(2, 2)]));
+        assert_eq!(packet.to_string(), "(eq v4 (sum v2 (lit v2 1) 3) (product 2 (lit v2 2)))");
+        assert_eq!(format!("{:#}", packet), "(eq v4\n  (sum v2\n    (lit v2 1)\n    3)\n  (product\n    2\n    (lit v2 2)))");
+        assert_eq!(" ( eq v4 (sum v2 ( lit v2 1 )3)(product 2 (lit v2 2)) ) ".parse::<Packet>().unwrap(), packet);
+        assert_eq!("(sum)".parse::<Packet>().unwrap(), Packet::sum(0, vec![]));
+    }
+
+    parameterized_test::create!{ bad_sexp, (input, message), {
+        let err = input.parse::<Packet>().unwrap_err();
+        assert!(err.to_string().contains(message), "{}", err);
+    } }
+    bad_sexp! {
+        unclosed: ("(sum 1 2", "expected `)`, found end of input"),
+        unknown: ("(div 1 2)", "Unknown operator `div`"),
+        lit_args: ("(lit 1 2)", "lit requires a single number"),
+        lit_nested: ("(lit (lit 1))", "lit requires a single number"),
+        version: ("(sum v8 1)", "Version 8 does not fit"),
+        pair: ("(lt 1 2 3)", "lt requires exactly 2 subpackets, found 3"),
+    }
+
+    parameterized_test::create!{ decode_errors, (input, expected), {
+        let err = Packet::deserialize(&mut Bitstream::new(input.as_bytes())).unwrap_err();
+        assert_eq!(err.downcast_ref::<DecodeError>(), Some(&expected), "{}", err);
+    } }
+    decode_errors! {
+        empty: ("", DecodeError::Truncated { wanted: 3, remaining: 0 }),
+        literal: ("D2FE", DecodeError::Truncated { wanted: 1, remaining: 0 }),
+        length: ("38006F", DecodeError::Truncated { wanted: 3, remaining: 2 }),
+        // (sum 1 2 3) with the type ID changed to gt
+        gt: ("1600C408821060", DecodeError::SubpacketCount { operator: 5, count: 3 }),
+    }
+
+    #[test]
+    fn operator_errors() {
+        assert_eq!(Type::operator(4, vec![]), Err(DecodeError::UnknownOperator(4)));
+        assert_eq!(Type::operator(9, vec![]), Err(DecodeError::UnknownOperator(9)));
+        assert_eq!(Type::operator(7, vec![Packet::literal(0, 1)]), Err(DecodeError::SubpacketCount { operator: 7, count: 1 }));
+        assert_eq!(DecodeError::SubpacketCount { operator: 7, count: 1 }.to_string(), "eq requires exactly 2 subpackets, found 1");
+    }
+
+    parameterized_test::create!{ streaming, input, {
+        let packet = Packet::deserialize(&mut Bitstream::new(input.as_bytes())).unwrap();
+        assert_eq!(decode(&mut Bitstream::new(input.as_bytes()), &mut Versions).unwrap(), packet.sum_versions());
+        assert_eq!(decode(&mut Bitstream::new(input.as_bytes()), &mut Evaluator).unwrap(), packet.evaluate().unwrap());
+    } }
+    streaming! {
+        example1: EXAMPLE_1,
+        example4: EXAMPLE_4,
+        example7: EXAMPLE_7,
+        example13: EXAMPLE_13,
+        example15: EXAMPLE_15,
+        input: include_str!("input.txt"),
+    }
+
+    #[test]
+    fn wide_pops() {
+        let mut stream = Bitstream::new("0FFFFFFFFFFFFFFFF F\n".as_bytes());
+        assert_eq!(stream.pop_bits(0).unwrap(), 0);
+        assert_eq!(stream.pop_bits(4).unwrap(), 0);
+        assert_eq!(stream.pop_bits(64).unwrap(), u64::MAX);
+        assert_eq!(stream.position(), 68);
+        assert_eq!(stream.pop_bits(4).unwrap(), 0xF);
+        assert!(stream.pop_bits(1).is_err());
+
+        assert!(Bitstream::new("0G".as_bytes()).pop_bits(8).unwrap_err().to_string().contains("Invalid digit: G"));
+    }
+
+    #[test]
+    fn deeply_nested() {
+        const DEPTH: u64 = 100_000;
+        let mut bits = BitWriter::new(Order::Msb);
+        for _ in 0..DEPTH {
+            bits.write(3, 1); // version
+            bits.write(3, 0); // sum
+            bits.write(1, 1);
+            bits.write(11, 1);
+        }
+        bits.write(3, 2);
+        bits.write(3, LITERAL);
+        bits.write(5, 7);
+        let hex = bits.to_hex();
+
+        assert_eq!(decode(&mut Bitstream::new(hex.as_bytes()), &mut Evaluator).unwrap(), 7);
+        assert_eq!(decode(&mut Bitstream::new(hex.as_bytes()), &mut Versions).unwrap(), DEPTH + 2);
+        let packet = Packet::deserialize(&mut Bitstream::new(hex.as_bytes())).unwrap();
+        assert_eq!(packet.evaluate().unwrap(), 7);
+        assert_eq!(packet.sum_versions(), DEPTH + 2);
+    }
+
+    parameterized_test::create!{ eval_errors, (sexp, message), {
+        let hex = sexp.parse::<Packet>().unwrap().serialize().unwrap();
+        let err = decode(&mut Bitstream::new(hex.as_bytes()), &mut Evaluator).unwrap_err();
+        assert!(err.to_string().contains(message), "{}", err);
+        let err = Packet::deserialize(&mut Bitstream::new(hex.as_bytes())).unwrap().evaluate().unwrap_err();
+        assert!(err.to_string().contains(message), "{}", err);
+    } }
+    eval_errors! {
+        sum: ("(sum 18446744073709551615 1)", "Overflow in sum"),
+        product: ("(sum 1 (product 4294967296 4294967296))", "Overflow in product"),
+        min: ("(min)", "min requires at least one subpacket"),
+    }
+
+    #[test]
+    fn overrun() {
+        // (sum 1) with its length in bits set one short of the literal's 11 bits
+        let mut bits = BitWriter::new(Order::Msb);
+        bits.write(6, 0);
+        bits.write(1, 0);
+        bits.write(15, 10);
+        bits.write(6, LITERAL);
+        bits.write(5, 1);
+        let err = decode(&mut Bitstream::new(bits.to_hex().as_bytes()), &mut Evaluator).unwrap_err();
+        assert_eq!(err.to_string(), "Subpackets overran their length by 1 bits");
+    }
+
+    #[test]
+    fn literal_overflow() {
+        let mut bits = BitWriter::new(Order::Msb);
+        bits.write(3, 0);
+        bits.write(3, LITERAL);
+        for _ in 0..17 {
+            bits.write(5, 0b11111);
+        }
+        bits.write(5, 0);
+        let err = decode(&mut Bitstream::new(bits.to_hex().as_bytes()), &mut Evaluator).unwrap_err();
+        assert_eq!(err.to_string(), "Literal does not fit in 64 bits");
+    }
 }
@@ -1,19 +1,37 @@
 use std::collections::HashMap;
 use anyhow::{anyhow, Result};
 use advent_2021::euclid::{Point, point, Vector, vector};
+use advent_2021::parsing::pattern_struct;
+use advent_2021::input;
 
-fn main() -> Result<()> {
-    // INPUT: x=70..96, y=-179..-124
-    let target = (point(70, -179), point(96, -124));
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    println!("Best trajectory reaches Y={}", part1(&input)?);
+    println!("Total trajectories: {}", part2(&input)?);
 
-    let trajectories = all_trajectories(target);
+    Ok(())
+}
+
+pub fn part1(input: &str) -> Result<String> {
+    let trajectories = all_trajectories(parse_input(input)?);
     let best = trajectories.values()
         .flat_map(|v| v.iter().map(|p| p.y))
         .max().ok_or_else(|| anyhow!("No trajectories found"))?;
-    println!("Best trajectory reaches Y={}", best);
-    println!("Total trajectories: {}", trajectories.len());
+    Ok(best.to_string())
+}
 
-    Ok(())
+pub fn part2(input: &str) -> Result<String> {
+    Ok(all_trajectories(parse_input(input)?).len().to_string())
+}
+
+fn parse_input(input: &str) -> Result<(Point, Point)> {
+    let target: Target = input.trim().parse()?;
+    Ok((point(target.x1, target.y1), point(target.x2, target.y2)))
+}
+
+pattern_struct! {
+    #[pattern(r"^target area: x=(?P<x1>-?\d+)\.\.(?P<x2>-?\d+), y=(?P<y1>-?\d+)\.\.(?P<y2>-?\d+)$")]
+    struct Target { x1: i32, x2: i32, y1: i32, y2: i32 }
 }
 
 fn all_trajectories(target: (Point, Point)) -> HashMap<Vector, Vec<Point>> {
@@ -1,11 +1,27 @@
 use std::collections::VecDeque;
-use std::fmt::Write;
-use std::ops::{Add, AddAssign};
+use std::fmt::{Display, Write};
+use std::ops::{Add, AddAssign, Range};
 use std::str::FromStr;
-use anyhow::{anyhow, bail, Context, ensure, Error, Result};
+use anyhow::{anyhow, bail, ensure, Error, Result};
+use advent_2021::parsing::combinator::{alt, delimited, int, literal, recursive, seq, Parser, Recursive};
+use advent_2021::input;
+use advent_2021::terminal::Color;
+
+mod tree;
+use tree::Tree;
+
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    match &input::args()[..] {
+        [] => run::<Num>(&input),
+        [arg] if arg == "tree" => run::<Tree>(&input),
+        [arg] if arg == "trace" => trace_sum(&parse_input(&input)?),
+        args => bail!("Unexpected arguments: {:?}", args),
+    }
+}
 
-fn main() -> Result<()> {
-    let nums = parse_input(include_str!("input.txt"))?;
+fn run<N: Snailfish>(input: &str) -> Result<()> {
+    let nums: Vec<N> = input.lines().map(|l| l.parse()).collect::<Result<_>>()?;
 
     let summed = sum_nums(&nums).ok_or_else(|| anyhow!("No numbers in input"))?;
     println!("Sum: {}\nMagnitude: {}", summed, summed.magnitude());
@@ -16,6 +32,57 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+pub fn part1(input: &str) -> Result<String> {
+    let summed = sum_nums(&parse_input(input)?).ok_or_else(|| anyhow!("No numbers in input"))?;
+    Ok(summed.magnitude().to_string())
+}
+
+pub fn part2(input: &str) -> Result<String> {
+    let (_, _, mag) = max_magnitude(&parse_input(input)?).ok_or_else(|| anyhow!("No numbers in input"))?;
+    Ok(mag.to_string())
+}
+
+// The operations needed to solve the puzzle, so that it can be solved with either representation
+trait Snailfish: Clone + Display + FromStr<Err = Error> {
+    fn add(&self, other: &Self) -> Self;
+    fn magnitude(&self) -> i32;
+}
+
+// A single step of reducing a Num
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Action {
+    // The pair that exploded, and the index of the 0 that replaced it
+    Explode { pair: (i32, i32), at: usize },
+    // The number that split, and the index of the pair that replaced it
+    Split { value: i32, at: usize },
+}
+
+impl Action {
+    // The parts of the resulting Num that this action replaced
+    fn changed(&self) -> Range<usize> {
+        match *self {
+            Action::Explode { at, .. } => at..at + 1,
+            Action::Split { at, .. } => at..at + 4,
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Action::Explode { .. } => Color::RED,
+            Action::Split { .. } => Color::GREEN,
+        }
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Action::Explode { pair: (l, r), .. } => write!(f, "explode [{},{}]", l, r),
+            Action::Split { value, .. } => write!(f, "split {}", value),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 enum Part {
     Open,
@@ -51,16 +118,30 @@ impl Num {
         Ok(Num { parts: parts.iter().cloned().collect() })
     }
 
+    // The pair of a and b, without reducing it
+    fn pair(a: &Num, b: &Num) -> Num {
+        let mut parts = VecDeque::new();
+        parts.push_back(Part::Open);
+        for part in a.parts.iter().chain(b.parts.iter()) {
+            parts.push_back(*part);
+        }
+        parts.push_back(Part::Close);
+        Num{ parts }
+    }
+
     fn reduce(&mut self) {
-        loop {
-            if self.do_explode() { continue; }
-            if self.do_split() { continue; }
-            break;
+        self.reduce_traced(|_, _| {});
+    }
+
+    // Reduces the number, passing each action taken and the resulting number to trace
+    fn reduce_traced(&mut self, mut trace: impl FnMut(Action, &Num)) {
+        while let Some(action) = self.do_explode().or_else(|| self.do_split()) {
+            trace(action, self);
         }
         self.parts.make_contiguous();
     }
 
-    fn do_explode(&mut self) -> bool {
+    fn do_explode(&mut self) -> Option<Action> {
         let mut depth = 0;
         for i in 0..self.parts.len() {
             match self.parts[i] {
@@ -91,15 +172,15 @@ impl Num {
                         let removed = self.parts.remove(i-1);
                         debug_assert_eq!(removed, Some(Part::Open));
 
-                        return true;
+                        return Some(Action::Explode { pair: (l, r), at: i - 1 });
                     } else { panic!("Deep pair has unexpected additional nesting @{}: {:?}", i+1, self.parts); }
                 }
             }
         }
-        false
+        None
     }
 
-    fn do_split(&mut self) -> bool {
+    fn do_split(&mut self) -> Option<Action> {
         for i in 0..self.parts.len() {
             if let Part::N(n) = self.parts[i] {
                 if n > 9 {
@@ -107,11 +188,11 @@ impl Num {
                     self.parts.insert(i + 1, Part::Close);
                     self.parts.insert(i + 1, Part::N((n + 1) / 2));
                     self.parts.insert(i, Part::Open);
-                    return true;
+                    return Some(Action::Split { value: n, at: i });
                 }
             }
         }
-        false
+        None
     }
 
     fn magnitude(&self) -> i32 {
@@ -138,17 +219,17 @@ impl Num {
     }
 }
 
-impl<'a, 'b> Add<&'b Num> for &'a Num {
+impl Snailfish for Num {
+    fn add(&self, other: &Num) -> Num { self + other }
+
+    fn magnitude(&self) -> i32 { Num::magnitude(self) }
+}
+
+impl<'b> Add<&'b Num> for &Num {
     type Output = Num;
 
     fn add(self, other: &'b Num) -> Num {
-        let mut parts = VecDeque::new();
-        parts.push_back(Part::Open);
-        for part in self.parts.iter().chain(other.parts.iter()) {
-            parts.push_back(*part);
-        }
-        parts.push_back(Part::Close);
-        let mut new = Num{ parts };
+        let mut new = Num::pair(self, other);
         new.reduce();
         new
     }
@@ -180,63 +261,48 @@ impl FromStr for Num {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let s = s.replace("],", "]");
-
-        let get_num = |i: &mut Option<usize>, j:usize| -> Result<i32> {
-            let t = &s[i.expect("start index not set")..j];
-            let d = t.parse::<i32>().with_context(|| t.to_string())?;
-            *i = None;
-            Ok(d)
-        };
-
-        let mut parts = Vec::new();
-        let mut i = None;
-        for (j, c) in s.chars().enumerate() {
-            match c {
-                '[' => parts.push(Part::Open),
-                ']' => {
-                    if i.is_some() {
-                        parts.push(Part::N(get_num(&mut i, j)?));
-                    }
-                    parts.push(Part::Close);
-                },
-                ',' => {
-                    parts.push(Part::N(get_num(&mut i, j)?));
-                },
-                '0'..='9' => {
-                    if i.is_none() { i = Some(j); }
-                },
-                _ => bail!("Unexpected char {} @{}", c, j),
-            }
-        }
-        if let Some(i) = i {
-            // If i is still Some the string must be a single N literal, otherwise it won't parse
-            let d = s[i..].parse::<i32>().with_context(|| s[i..].to_string())?;
-            parts.push(Part::N(d));
+        // Numbers are parsed often (e.g. by max_magnitude()), so the parser is only built once
+        thread_local! {
+            static PARSER: Recursive<Vec<Part>> = recursive(|num| alt((
+                int().map(|n| vec![Part::N(n)]),
+                delimited(literal("["), seq((num.clone(), literal(","), num)), literal("]"))
+                    .map(|(l, _, r)| [vec![Part::Open], l, r, vec![Part::Close]].concat()),
+            )));
         }
-        Num::create(&parts)
+        Num::create(&PARSER.with(|parser| parser.parse(s))?)
     }
 }
 
-impl std::fmt::Display for Num {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl Num {
+    // Renders the number with the given range of parts (e.g. those changed by an Action) colored
+    fn render(&self, highlight: Range<usize>, color: Color) -> String {
         let mut out = String::new();
-        for p in &self.parts {
+        let mut highlighted = String::new();
+        let mut prev = None;
+        for (i, p) in self.parts.iter().enumerate() {
+            let comma = matches!(prev, Some(Part::N(_)|Part::Close)) && matches!(p, Part::Open|Part::N(_));
+            let inside = highlight.contains(&i);
+            if comma {
+                if inside && i != highlight.start { highlighted.push(','); } else { out.push(','); }
+            }
+            let dest = if inside { &mut highlighted } else { &mut out };
             match p {
-                Part::Open => {
-                    if out.ends_with(']') { out.push(','); }
-                    out.push('[');
-                },
-                Part::Close => out.push(']'),
-                Part::N(n) => {
-                    if out.ends_with(']') { out.push(','); }
-                    let write_comma_after = out.ends_with('[');
-                    write!(out, "{}", n).expect("impossible");
-                    if write_comma_after { out.push(','); }
-                }
+                Part::Open => dest.push('['),
+                Part::Close => dest.push(']'),
+                Part::N(n) => write!(dest, "{}", n).expect("impossible"),
             }
+            if inside && i + 1 == highlight.end {
+                out.push_str(&color.paint(&highlighted));
+            }
+            prev = Some(*p);
         }
-        write!(f, "{}", out)
+        out
+    }
+}
+
+impl std::fmt::Display for Num {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.render(0..0, Color::WHITE))
     }
 }
 
@@ -246,30 +312,56 @@ fn parse_input(input: &str) -> Result<Vec<Num>> {
 
 // Like std::iter::Sum but doesn't return a value if there are no inputs (snailfish numbers don't
 // have documented zero/one values to use as the initial value).
-fn sum_nums(nums: &[Num]) -> Option<Num> {
+fn sum_nums<N: Snailfish>(nums: &[N]) -> Option<N> {
     let mut iter = nums.iter();
     let mut ret = iter.next()?.clone();
     for num in iter {
-        ret += num;
+        ret = ret.add(num);
     }
     Some(ret)
 }
 
-fn max_magnitude(nums: &[Num]) -> Option<(&Num, &Num, i32)> {
+// Prints each addition in the sum and the reduction steps it takes, highlighting what changed
+fn trace_sum(nums: &[Num]) -> Result<()> {
+    let mut iter = nums.iter();
+    let mut sum = iter.next().ok_or_else(|| anyhow!("No numbers in input"))?.clone();
+    for num in iter {
+        println!("  {}\n+ {}", sum, num);
+        let mut next = Num::pair(&sum, num);
+        println!("= {}", next);
+        next.reduce_traced(|action, num| println!("  {:<14} {}", action.to_string(), num.render(action.changed(), action.color())));
+        sum = next;
+        println!();
+    }
+    println!("Sum: {}\nMagnitude: {}", sum, sum.magnitude());
+    Ok(())
+}
+
+fn max_magnitude<N: Snailfish>(nums: &[N]) -> Option<(&N, &N, i32)> {
     (0..nums.len()).flat_map(|i| (0..nums.len()).map(move |j| (i, j)))
         .filter(|(i, j)| i != j)
         .map(|(i, j)| (&nums[i], &nums[j]))
-        .map(|(a, b)| (a, b, (a+b).magnitude()))
+        .map(|(a, b)| (a, b, a.add(b).magnitude()))
         .max_by_key(|(_, _, v)| *v)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use anyhow::Context;
+    use advent_2021::puzzle::this_day;
+
+    #[test]
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            assert!(example.check(1, part1(example.input()).unwrap()));
+            assert!(example.check(2, part2(example.input()).unwrap()));
+        }
+    }
 
     #[test]
     fn example_numbers() {
-        let nums = parse_input(include_str!("example1.txt")).unwrap();
+        let nums = parse_input(this_day!().example("example1").unwrap().input()).unwrap();
         for n in nums {
             let s = n.to_string();
             assert_eq!(n, s.parse().context(format!("Couldn't parse {}", s)).unwrap());
@@ -287,7 +379,7 @@ mod tests {
 
     parameterized_test::create!{ explodes, (input, normalized), {
         let mut input: Num = input.parse().unwrap();
-        assert!(input.do_explode());
+        assert!(input.do_explode().is_some());
         let normalized: Num = normalized.parse().unwrap();
         assert_eq!(input, normalized);
     } }
@@ -302,7 +394,7 @@ mod tests {
 
     parameterized_test::create!{ splits, (input, normalized), {
         let mut input: Num = input.parse().unwrap();
-        assert!(input.do_split());
+        assert!(input.do_split().is_some());
         let normalized: Num = normalized.parse().unwrap();
         assert_eq!(input, normalized);
     } }
@@ -315,15 +407,15 @@ mod tests {
     #[test]
     fn reduce() {
         let mut num: Num = "[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]".parse().unwrap();
-        assert!(num.do_explode());
+        assert!(num.do_explode().is_some());
         assert_eq!(num, "[[[[0,7],4],[7,[[8,4],9]]],[1,1]]".parse().unwrap());
-        assert!(num.do_explode());
+        assert!(num.do_explode().is_some());
         assert_eq!(num, "[[[[0,7],4],[15,[0,13]]],[1,1]]".parse().unwrap());
-        assert!(num.do_split());
+        assert!(num.do_split().is_some());
         assert_eq!(num, "[[[[0,7],4],[[7,8],[0,13]]],[1,1]]".parse().unwrap());
-        assert!(num.do_split());
+        assert!(num.do_split().is_some());
         assert_eq!(num, "[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]".parse().unwrap());
-        assert!(num.do_explode());
+        assert!(num.do_explode().is_some());
         assert_eq!(num, "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]".parse().unwrap());
         let num_copy = num.clone();
         num.reduce(); // ensure no more work to do
@@ -331,6 +423,27 @@ mod tests {
         assert_eq!(num.magnitude(), 1384);
     }
 
+    #[test]
+    fn trace() {
+        let mut num = Num::pair(&"[[[[4,3],4],4],[7,[[8,4],9]]]".parse().unwrap(), &"[1,1]".parse().unwrap());
+        let mut steps = Vec::new();
+        num.reduce_traced(|action, num| steps.push((action, num.to_string(), num.render(action.changed(), action.color()))));
+        assert_eq!(steps, [
+            (Action::Explode { pair: (4, 3), at: 4 }, "[[[[0,7],4],[7,[[8,4],9]]],[1,1]]".into(),
+                format!("[[[[{},7],4],[7,[[8,4],9]]],[1,1]]", Color::RED.paint("0"))),
+            (Action::Explode { pair: (8, 4), at: 12 }, "[[[[0,7],4],[15,[0,13]]],[1,1]]".into(),
+                format!("[[[[0,7],4],[15,[{},13]]],[1,1]]", Color::RED.paint("0"))),
+            (Action::Split { value: 15, at: 10 }, "[[[[0,7],4],[[7,8],[0,13]]],[1,1]]".into(),
+                format!("[[[[0,7],4],[{},[0,13]]],[1,1]]", Color::GREEN.paint("[7,8]"))),
+            (Action::Split { value: 13, at: 16 }, "[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]".into(),
+                format!("[[[[0,7],4],[[7,8],[0,{}]]],[1,1]]", Color::GREEN.paint("[6,7]"))),
+            (Action::Explode { pair: (6, 7), at: 16 }, "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]".into(),
+                format!("[[[[0,7],4],[[7,8],[6,{}]]],[8,1]]", Color::RED.paint("0"))),
+        ]);
+        assert_eq!(steps[0].0.to_string(), "explode [4,3]");
+        assert_eq!(steps[2].0.to_string(), "split 15");
+    }
+
     parameterized_test::create!{ sums, (input, expected, magnitude), {
         let input = parse_input(input).unwrap();
         let summed = sum_nums(&input).unwrap();
@@ -342,16 +455,23 @@ mod tests {
         e1: ("[1,1]\n[2,2]\n[3,3]\n[4,4]", "[[[[1,1],[2,2]],[3,3]],[4,4]]", 445),
         e2: ("[1,1]\n[2,2]\n[3,3]\n[4,4]\n[5,5]", "[[[[3,0],[5,3]],[4,4]],[5,5]]", 791),
         e3: ("[1,1]\n[2,2]\n[3,3]\n[4,4]\n[5,5]\n[6,6]", "[[[[5,0],[7,4]],[5,5]],[6,6]]", 1137),
-        e4: (include_str!("example2.txt"), "[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]", 3488),
-        e5: (include_str!("example3.txt"), "[[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]]", 4140),
+    }
+
+    // The answers only record the magnitudes
+    parameterized_test::create!{ example_sums, (name, expected), {
+        let input = parse_input(this_day!().example(name).unwrap().input()).unwrap();
+        assert_eq!(sum_nums(&input).unwrap(), expected.parse().unwrap());
+    } }
+    example_sums! {
+        e2: ("example2", "[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]"),
+        e3: ("example3", "[[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]]"),
     }
 
     #[test]
     fn max_mag() {
-        let input = parse_input(include_str!("example3.txt")).unwrap();
-        let (a, b, mag) = max_magnitude(&input).unwrap();
+        let input = parse_input(this_day!().example("example3").unwrap().input()).unwrap();
+        let (a, b, _) = max_magnitude(&input).unwrap();
         assert_eq!(a, &"[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]".parse().unwrap());
         assert_eq!(b, &"[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]".parse().unwrap());
-        assert_eq!(mag, 3993);
     }
 }
@@ -0,0 +1,242 @@
+// A tree-based representation of snailfish numbers, as an alternative to Num's flat sequence of
+// parts. Reductions walk the tree rather than scanning for brackets.
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use anyhow::{Error, Result};
+use advent_2021::parsing::combinator::{alt, delimited, int, literal, recursive, seq, Parser, Recursive};
+
+use super::{Num, Part, Snailfish};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Tree {
+    Regular(i32),
+    Pair(Box<Tree>, Box<Tree>),
+}
+
+impl Tree {
+    pub fn pair(left: Tree, right: Tree) -> Tree {
+        Tree::Pair(Box::new(left), Box::new(right))
+    }
+
+    // The depth of the most deeply nested pair, where a top-level pair is 1
+    pub fn depth(&self) -> usize {
+        match self {
+            Tree::Regular(_) => 0,
+            Tree::Pair(l, r) => 1 + l.depth().max(r.depth()),
+        }
+    }
+
+    pub fn reduce(&mut self) {
+        loop {
+            if self.explode() { continue; }
+            if self.split() { continue; }
+            break;
+        }
+        debug_assert!(self.depth() <= 4, "Not fully reduced: {}", self);
+    }
+
+    // Explodes the leftmost pair of regular numbers nested inside four pairs, returning true if
+    // one was found
+    pub fn explode(&mut self) -> bool {
+        // Returns the values still to be added to the nearest regular numbers to the left and right
+        fn explode_at(tree: &mut Tree, depth: usize) -> Option<(Option<i32>, Option<i32>)> {
+            let Tree::Pair(l, r) = tree else { return None };
+            if depth >= 4 {
+                if let (Tree::Regular(a), Tree::Regular(b)) = (&**l, &**r) {
+                    let carry = (Some(*a), Some(*b));
+                    *tree = Tree::Regular(0);
+                    return Some(carry);
+                }
+            }
+            if let Some((left, right)) = explode_at(l, depth + 1) {
+                if let Some(n) = right { r.add_leftmost(n); }
+                return Some((left, None));
+            }
+            if let Some((left, right)) = explode_at(r, depth + 1) {
+                if let Some(n) = left { l.add_rightmost(n); }
+                return Some((None, right));
+            }
+            None
+        }
+        explode_at(self, 0).is_some()
+    }
+
+    // Splits the leftmost regular number greater than 9, returning true if one was found
+    pub fn split(&mut self) -> bool {
+        match self {
+            Tree::Regular(n) if *n > 9 => {
+                let n = *n;
+                *self = Tree::pair(Tree::Regular(n / 2), Tree::Regular((n + 1) / 2));
+                true
+            },
+            Tree::Regular(_) => false,
+            Tree::Pair(l, r) => l.split() || r.split(),
+        }
+    }
+
+    fn add_leftmost(&mut self, n: i32) {
+        match self {
+            Tree::Regular(v) => *v += n,
+            Tree::Pair(l, _) => l.add_leftmost(n),
+        }
+    }
+
+    fn add_rightmost(&mut self, n: i32) {
+        match self {
+            Tree::Regular(v) => *v += n,
+            Tree::Pair(_, r) => r.add_rightmost(n),
+        }
+    }
+
+    pub fn magnitude(&self) -> i32 {
+        match self {
+            Tree::Regular(n) => *n,
+            Tree::Pair(l, r) => 3 * l.magnitude() + 2 * r.magnitude(),
+        }
+    }
+}
+
+impl Snailfish for Tree {
+    fn add(&self, other: &Tree) -> Tree {
+        let mut sum = Tree::pair(self.clone(), other.clone());
+        sum.reduce();
+        sum
+    }
+
+    fn magnitude(&self) -> i32 { Tree::magnitude(self) }
+}
+
+impl From<&Num> for Tree {
+    fn from(num: &Num) -> Tree {
+        let mut stack = Vec::new();
+        for part in &num.parts {
+            match part {
+                Part::Open => {},
+                Part::N(n) => stack.push(Tree::Regular(*n)),
+                Part::Close => {
+                    let right = stack.pop().expect("Num is well-formed");
+                    let left = stack.pop().expect("Num is well-formed");
+                    stack.push(Tree::pair(left, right));
+                },
+            }
+        }
+        debug_assert_eq!(stack.len(), 1);
+        stack.pop().expect("Num is well-formed")
+    }
+}
+
+impl From<&Tree> for Num {
+    fn from(tree: &Tree) -> Num {
+        fn flatten(tree: &Tree, parts: &mut VecDeque<Part>) {
+            match tree {
+                Tree::Regular(n) => parts.push_back(Part::N(*n)),
+                Tree::Pair(l, r) => {
+                    parts.push_back(Part::Open);
+                    flatten(l, parts);
+                    flatten(r, parts);
+                    parts.push_back(Part::Close);
+                },
+            }
+        }
+        let mut parts = VecDeque::new();
+        flatten(tree, &mut parts);
+        Num { parts }
+    }
+}
+
+impl FromStr for Tree {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        // Like Num's parser, only built once
+        thread_local! {
+            static PARSER: Recursive<Tree> = recursive(|tree| alt((
+                int().map(Tree::Regular),
+                delimited(literal("["), seq((tree.clone(), literal(","), tree)), literal("]"))
+                    .map(|(l, _, r)| Tree::pair(l, r)),
+            )));
+        }
+        PARSER.with(|parser| parser.parse(s))
+    }
+}
+
+impl fmt::Display for Tree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tree::Regular(n) => write!(f, "{}", n),
+            Tree::Pair(l, r) => write!(f, "[{},{}]", l, r),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use advent_2021::puzzle::this_day;
+
+    #[test]
+    fn parse() {
+        let tree: Tree = "[[1,2],3]".parse().unwrap();
+        assert_eq!(tree, Tree::pair(Tree::pair(Tree::Regular(1), Tree::Regular(2)), Tree::Regular(3)));
+        assert_eq!(tree.to_string(), "[[1,2],3]");
+        assert_eq!(tree.depth(), 2);
+        assert!("[1,2".parse::<Tree>().is_err());
+        assert!("[1,[2]]".parse::<Tree>().is_err());
+    }
+
+    parameterized_test::create!{ explodes, (input, expected), {
+        let mut tree: Tree = input.parse().unwrap();
+        assert!(tree.explode());
+        assert_eq!(tree.to_string(), expected);
+    } }
+    explodes! {
+        leftmost: ("[[[[[9,8],1],2],3],4]", "[[[[0,9],2],3],4]"),
+        rightmost: ("[7,[6,[5,[4,[3,2]]]]]", "[7,[6,[5,[7,0]]]]"),
+        both: ("[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]", "[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]"),
+    }
+
+    #[test]
+    fn sums() {
+        let example = this_day!().example("example3").unwrap();
+        let trees: Vec<Tree> = example.input().lines().map(|l| l.parse().unwrap()).collect();
+        let summed = trees[1..].iter().fold(trees[0].clone(), |sum, t| sum.add(t));
+        assert_eq!(summed.to_string(), "[[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]]");
+        assert_eq!(summed.magnitude(), 4140);
+    }
+
+    // A random number with pairs nested at most max_depth deep, and regular numbers up to max_value
+    fn random_tree(rng: &fastrand::Rng, max_depth: usize, max_value: i32) -> Tree {
+        if max_depth == 0 || rng.u8(..4) == 0 {
+            return Tree::Regular(rng.i32(0..=max_value));
+        }
+        Tree::pair(random_tree(rng, max_depth - 1, max_value), random_tree(rng, max_depth - 1, max_value))
+    }
+
+    // Checks the two representations agree on thousands of random numbers. The unreduced numbers
+    // are nested no deeper than reduced ones, but have regular numbers large enough to split.
+    #[test]
+    fn representations_agree() {
+        let rng = fastrand::Rng::with_seed(18);
+        for _ in 0..5000 {
+            let tree = random_tree(&rng, 4, 40);
+            let num = Num::from(&tree);
+            assert_eq!(num.to_string(), tree.to_string());
+            assert_eq!(num, tree.to_string().parse().unwrap());
+            assert_eq!(tree, num.to_string().parse().unwrap());
+            assert_eq!(Tree::from(&num), tree);
+            assert_eq!(Snailfish::magnitude(&num), tree.magnitude());
+
+            let (mut tree, mut num) = (tree, num);
+            tree.reduce();
+            num.reduce();
+            assert_eq!(num.to_string(), tree.to_string());
+            assert!(tree.depth() <= 4);
+
+            let other = random_tree(&rng, 4, 9);
+            let (tree_sum, num_sum) = (tree.add(&other), num.add(&Num::from(&other)));
+            assert_eq!(num_sum.to_string(), tree_sum.to_string(), "{} + {}", tree, other);
+            assert_eq!(Snailfish::magnitude(&num_sum), tree_sum.magnitude());
+        }
+    }
+}
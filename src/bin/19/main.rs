@@ -1,23 +1,51 @@
 use std::collections::{BTreeSet, BTreeMap};
 use std::str::FromStr;
-use anyhow::{anyhow, Result, Error};
+use anyhow::{anyhow, bail, Result, Error};
 use lazy_static::lazy_static;
-use advent_2021::euclid3d::{point, Point, Vector};
+use advent_2021::euclid3d::{point, Point, Solid, Vector};
+use advent_2021::terminal::Color;
 use advent_2021::parsing::*;
+use advent_2021::input;
+
+pub fn main() -> Result<()> {
+    let trench = merge_input(&input::read(include_str!("input.txt"))?)?;
+
+    // Pass openscad, stl, or obj to print a model of the beacon map instead
+    if let Some(format) = input::args().into_iter().next() {
+        let map = Solid::voxels(&trench.beacons).colored(Color::WHITE)
+            .union(Solid::voxels(&trench.scanners).colored(Color::RED));
+        match format.as_str() {
+            "openscad" => print!("{}", map.openscad()),
+            "stl" => print!("{}", map.stl("beacons")),
+            "obj" => print!("{}", map.obj()),
+            _ => bail!("Unknown format {}", format),
+        }
+        return Ok(());
+    }
 
-fn main() -> Result<()> {
-    let mut input = parse_input(include_str!("input.txt"))?;
-    let mut trench = Trench::create(input.swap_remove(0));
-
-    trench.merge_scans(input);
     println!("Beacons: {}", trench.beacons.len());
     println!("Distance between scanners: {}", trench.scanner_distance());
 
     Ok(())
 }
 
+fn merge_input(input: &str) -> Result<Trench> {
+    let mut input = parse_input(input)?;
+    let mut trench = Trench::create(input.swap_remove(0));
+    trench.merge_scans(input);
+    Ok(trench)
+}
+
+pub fn part1(input: &str) -> Result<String> {
+    Ok(merge_input(input)?.beacons.len().to_string())
+}
+
+pub fn part2(input: &str) -> Result<String> {
+    Ok(merge_input(input)?.scanner_distance().to_string())
+}
+
 fn parse_input(input: &str) -> Result<Vec<Scanner>> {
-    input.split("\n\n").map(|section| section.parse()).collect()
+    sections(input).iter().map(|section| section.parse()).collect()
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -150,6 +178,7 @@ impl FromStr for Scanner {
 #[cfg(test)]
 mod scanner_tests {
     use super::*;
+    use advent_2021::puzzle::this_day;
     use advent_2021::euclid3d::vector;
 
     #[test]
@@ -174,9 +203,10 @@ mod scanner_tests {
                        vector(1, -2, 0), vector(3, 1, 0), vector(4, -1, 0)].into_iter().collect());
     }
 
+    // One scanner's beacons, seen from several orientations
     #[test]
     fn orientations() {
-        let scanners = parse_input(include_str!("example1.txt")).unwrap();
+        let scanners = parse_input(this_day!().example("orientations").unwrap().input()).unwrap();
         assert_eq!(5, scanners.len());
 
         assert_eq!(scanners[0], scanners[1].reorient(
@@ -255,17 +285,26 @@ impl Trench {
 #[cfg(test)]
 mod trench_tests {
     use super::*;
+    use advent_2021::puzzle::this_day;
+
+    #[test]
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            let trench = merge_input(example.input()).unwrap();
+            assert!(example.check(1, trench.beacons.len()));
+            assert!(example.check(2, trench.scanner_distance()));
+        }
+    }
 
     #[test]
-    fn example2() {
-        let mut input = parse_input(include_str!("example2.txt")).unwrap();
+    fn example_beacons() {
+        let example = this_day!().example("example").unwrap();
+        let mut input = parse_input(example.input()).unwrap();
         let mut trench = Trench::create(input.swap_remove(0));
 
         trench.merge_scans(input);
-        assert_eq!(trench.beacons.len(), 79);
         let expected: BTreeSet<_> =
-            include_str!("expected2.txt").lines().map(|l| l.parse::<Point>().unwrap()).collect();
+            example.fixture("beacons").unwrap().lines().map(|l| l.parse::<Point>().unwrap()).collect();
         assert_eq!(trench.beacons, expected);
-        assert_eq!(trench.scanner_distance(), 3621);
     }
 }
@@ -1,110 +1,117 @@
-use anyhow::{anyhow, ensure, Error, Result};
+use anyhow::{bail, ensure, Context, Error, Result};
 
-use advent_2021::euclid::{point,Point,vector};
+use advent_2021::automaton::{Automaton, Edges, LookupTable, Neighborhood};
+use advent_2021::euclid::Point;
+use advent_2021::hashlife::Hashlife;
+use advent_2021::parsing::{parse_grid, sections};
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::fmt::Display;
 use std::time::Duration;
-use advent_2021::terminal::{Color, Terminal, TerminalImage, TerminalRender};
+use advent_2021::terminal::{Pooling, Terminal, TerminalImage, TerminalRender, Viewport};
+use advent_2021::input;
 
-fn main() -> Result<()> {
+pub fn main() -> Result<()> {
     let _drop = Terminal::init();
-    if std::env::args().len() > 1 {
+    // `conway` animates Conway's Game of Life, `hashlife N` runs it for N generations
+    match &input::args()[..] {
+        [] => {},
         // https://old.reddit.com/r/adventofcode/comments/rkgmx9/2021_day_20_images_come_to_life/
         // https://old.reddit.com/r/adventofcode/comments/rkvfov/2021_day_20_an_image_enhancement_algorithm_that/
-        let (algorithm, mut image) = parse_input(include_str!("conway.txt"))?;
+        [mode] if mode == "conway" => {
+            let (algorithm, mut image) = parse_input(include_str!("conway.txt"))?;
 
-        for _ in 0..300 {
-            image = image.enhance(&algorithm);
-            Terminal::interactive_render(&image, Duration::from_millis(75));
-        }
+            for _ in 0..300 {
+                image = image.enhance(&algorithm);
+                Terminal::interactive_render(&image, Duration::from_millis(75));
+            }
 
-        return Ok(());
+            return Ok(());
+        },
+        // Life-like algorithms can be run for far longer with Hashlife
+        [mode, generations] if mode == "hashlife" => {
+            let generations = generations.parse().with_context(|| format!("Invalid generations: {}", generations))?;
+            let (algorithm, image) = parse_input(include_str!("conway.txt"))?;
+            let life = run_hashlife(&algorithm, &image, generations)?;
+            println!("Population after {} generations: {}", life.generation(), life.population());
+            print!("{}", life.to_rle()?);
+            return Ok(());
+        },
+        args => bail!("Unexpected arguments: {:?}", args),
     }
 
-    let (algorithm, mut image) = parse_input(include_str!("input.txt"))?;
-    image = image.enhance(&algorithm);
-    Terminal::interactive_display(&image, Duration::from_millis(200));
-    image = image.enhance(&algorithm);
-    let after_two = image.lit_pixels()?;
-    Terminal::interactive_display(&image, Duration::from_millis(200));
+    let input = input::read(include_str!("input.txt"))?;
+    if Terminal::active() {
+        let (algorithm, mut image) = parse_input(&input)?;
+        for _ in 0..50 {
+            image = image.enhance(&algorithm);
+            Terminal::interactive_display(&image, Duration::from_millis(200));
+        }
+        Terminal::end_interactive();
+    }
+    println!("Lit pixels after two iterations: {}", part1(&input)?);
+    println!("Lit pixels after 50 iterations: {}", part2(&input)?);
 
+    Ok(())
+}
 
-    for _ in 2..50 {
+fn lit_after(input: &str, iterations: usize) -> Result<String> {
+    let (algorithm, mut image) = parse_input(input)?;
+    for _ in 0..iterations {
         image = image.enhance(&algorithm);
-        Terminal::interactive_display(&image, Duration::from_millis(200));
     }
-    println!("Lit pixels after two iterations: {}", after_two);
-    println!("Lit pixels after 50 iterations: {}", image.lit_pixels()?);
+    Ok(image.lit_pixels()?.to_string())
+}
 
-    Ok(())
+fn run_hashlife(algorithm: &LookupTable, image: &Image, generations: u64) -> Result<Hashlife> {
+    let rule = algorithm.as_totalistic().context("Algorithm is not a life-like rule")?;
+    let mut life = Hashlife::new(&rule, image.automaton.iter().filter(|(_, &v)| v).map(|(p, _)| p))?;
+    life.advance(generations)?;
+    Ok(life)
 }
 
+pub fn part1(input: &str) -> Result<String> { lit_after(input, 2) }
+
+pub fn part2(input: &str) -> Result<String> { lit_after(input, 50) }
+
 #[derive(Clone)]
 struct Image {
-    pixels: HashMap<Point, bool>,
-    background: bool,
+    automaton: Automaton<bool>,
 }
 
 impl Image {
-    fn create(pixels: HashMap<Point, bool>) -> Image {
-        Image{ pixels, background: false, }
+    fn create(pixels: HashMap<Point, bool>) -> Result<Image> {
+        Ok(Image{ automaton: Automaton::new(pixels, false, Edges::Infinite)? })
     }
 
     fn lit_pixels(&self) -> Result<usize> {
-        ensure!(!self.background, "Image has infinitely many lit pixels");
-        Ok(self.pixels.values().filter(|v| **v).count())
+        ensure!(!self.automaton.background(), "Image has infinitely many lit pixels");
+        Ok(self.automaton.count(|&p| p))
     }
 
-    fn enhance(&self, algorithm: &[bool]) -> Image {
-        let background = algorithm[if self.background { 511 } else { 0 }];
-        // Remove empty pixels from the bounds, otherwise we can end up flipping large areas of
-        // nothing indefinitely. This is more of an issue for Conway than the actual problem.
-        let bounds = Point::bounding_box(
-            self.pixels.iter().filter(|(_, v)| **v != self.background).map(|(p, _)| p))
-            .expect("Empty image");
-        let mut pixels = HashMap::new();
-
-        for y in bounds.0.y-1..=bounds.1.y+1 {
-            for x in bounds.0.x-1..=bounds.1.x+1 {
-                let consider = point(x, y);
-                let index = self.pixel_state(consider);
-                pixels.insert(consider, algorithm[index]);
-            }
-        }
-
-        Image{ pixels, background }
-    }
-
-    fn pixel_state(&self, pixel: Point) -> usize {
-        let mut ret = 0;
-        for y in -1..=1 {
-            for x in -1..=1 {
-                let p = pixel + vector(x, y);
-                ret <<= 1;
-                if *self.pixels.get(&p).unwrap_or(&self.background) {
-                    ret |= 1;
-                }
-            }
-        }
-        ret
+    fn enhance(&self, algorithm: &LookupTable) -> Image {
+        let mut image = self.clone();
+        image.automaton.step(algorithm);
+        image
     }
 }
 
 impl TerminalRender for Image {
-    fn render(&self, _w: usize, _h: usize) -> TerminalImage {
-        let (min, max) = Point::bounding_box(self.pixels.keys()).expect("not empty");
-        let pixels =
-            (min.y..=max.y).flat_map(move |y|
-                (min.x..=max.x).map(move |x|
-                    if self.pixels[&point(x, y)] { Color::WHITE } else { Color::BLACK })).collect();
-        TerminalImage{ pixels, width: (max.x-min.x+1) as usize, }
+    fn render(&self, pixel_width: usize, pixel_height: usize) -> TerminalImage {
+        self.automaton.render(pixel_width, pixel_height)
+    }
+
+    // Conway patterns can grow well past the size of the terminal; shrink them to fit rather than
+    // cropping, and max-pool so that lone gliders don't disappear.
+    fn viewport(&self) -> Option<Viewport> {
+        Some(Viewport::fit(Pooling::Max))
     }
 }
 
 impl Display for Image {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", Point::display_point_map_braille(&self.pixels))
+        let pixels = self.automaton.iter().map(|(p, &v)| (p, v)).collect();
+        write!(f, "{}", Point::display_point_map_braille(&pixels))
     }
 }
 
@@ -112,46 +119,69 @@ impl FromStr for Image {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let pixels = s.lines().enumerate()
-            .flat_map(|(y, row)| row.chars().enumerate().map(move |(x, c)| match c {
-                '#' => Ok((point(x as i32, y as i32), true)),
-                '.' => Ok((point(x as i32, y as i32), false)),
-                _ => Err(anyhow!("Unexpected char: {:?}", c)),
-            }))
-            .collect::<Result<HashMap<Point, bool>>>()?;
-        Ok(Image::create(pixels))
+        Image::create(parse_grid(s, pixel)?)
     }
 }
 
-fn parse_input(input: &str) -> Result<(Vec<bool>, Image)> {
-    let parts: Vec<_> = input.split("\n\n").collect();
-    assert_eq!(parts.len(), 2);
-
-    let algorithm = parts[0].chars().map(|c| match c {
+fn pixel(c: char) -> Result<bool> {
+    match c {
         '#' => Ok(true),
         '.' => Ok(false),
-        _ => Err(anyhow!("Unexpected char {:?}", c)),
-    }).collect::<Result<Vec<_>>>()?;
-    ensure!(algorithm.len() == 512);
+        _ => bail!("Unexpected char {:?}", c),
+    }
+}
+
+fn parse_input(input: &str) -> Result<(LookupTable, Image)> {
+    let parts = sections(input);
+    ensure!(parts.len() == 2, "Expected algorithm and image, found {} sections", parts.len());
 
-    let image: Image = parts[1].parse()?;
+    // The algorithm may be wrapped across several lines, as the puzzle's example is
+    let mut algorithm: Vec<_> = parts[0].parse_grid(pixel)?.into_iter().collect();
+    ensure!(algorithm.len() == 512, "Algorithm has {} entries", algorithm.len());
+    algorithm.sort_by_key(|(p, _)| (p.y, p.x));
+    let algorithm = algorithm.into_iter().map(|(_, v)| v).collect();
+    let algorithm = LookupTable::new(Neighborhood::Moore.with_center(), algorithm)?;
+
+    let image = Image::create(parts[1].parse_grid(pixel)?)?;
     Ok((algorithm, image))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_2021::puzzle::this_day;
 
     #[test]
-    fn example() {
-        let (algorithm, mut image) = parse_input(include_str!("example.txt")).unwrap();
-        image = image.enhance(&algorithm);
-        image = image.enhance(&algorithm);
-        assert_eq!(image.lit_pixels().unwrap(), 35);
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            assert!(example.check(1, part1(example.input()).unwrap()));
+            assert!(example.check(2, part2(example.input()).unwrap()));
+        }
+    }
+
+    #[test]
+    fn wrapped_algorithm() {
+        let input = this_day!().example("example").unwrap().input().to_string();
+        let (algorithm, image) = input.split_once('\n').unwrap();
+        let lines: Vec<_> = algorithm.as_bytes().chunks(80).map(|c| std::str::from_utf8(c).unwrap()).collect();
+        let wrapped = format!("{}\n{}", lines.join("\n"), image);
+        assert_eq!(part2(&wrapped).unwrap(), part2(&input).unwrap());
+
+        assert!(parse_input(&format!("{}\n.{}", lines.join("\n"), image)).is_err());
+    }
 
-        for _ in 2..50 {
+    #[test]
+    fn hashlife() {
+        let (algorithm, mut image) = parse_input(include_str!("conway.txt")).unwrap();
+        assert_eq!(algorithm.as_totalistic(), Some(advent_2021::automaton::Totalistic::conway()));
+        let life = run_hashlife(&algorithm, &image, 50).unwrap();
+        for _ in 0..50 {
             image = image.enhance(&algorithm);
         }
-        assert_eq!(image.lit_pixels().unwrap(), 3351);
+        assert_eq!(life.population(), image.lit_pixels().unwrap() as u64);
+        assert_eq!(run_hashlife(&algorithm, &image, 1053).unwrap().population(), 116);
+
+        let (algorithm, image) = parse_input(this_day!().example("example").unwrap().input()).unwrap();
+        assert!(run_hashlife(&algorithm, &image, 2).is_err());
     }
 }
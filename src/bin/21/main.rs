@@ -1,16 +1,42 @@
 use std::collections::HashMap;
+use anyhow::{ensure, Result};
+use advent_2021::input;
+use advent_2021::parsing::{parse_lines, pattern_struct};
 
-fn main() {
-    let input = [10, 7];
-    let mut board = Board::create(input, 1000);
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    println!("Loser's Score * Rolls: {}", part1(&input)?);
+    println!("Most universes won in: {}", part2(&input)?);
+    Ok(())
+}
+
+pub fn part1(input: &str) -> Result<String> {
+    let mut board = Board::create(parse_input(input)?, 1000);
     let (winner, rolls) = play_with_fake_die(&mut board);
-    println!("Winner: Player {} - Rolls: {} - Scores: {:?}", winner+1, rolls, board.scores);
-    println!("Loser's Score * Rolls: {}", rolls * board.scores[(winner + 1) % board.scores.len()]);
+    Ok((rolls * board.scores[(winner + 1) % board.scores.len()]).to_string())
+}
 
-    let mut universes = Universes::create(input);
+pub fn part2(input: &str) -> Result<String> {
+    let mut universes = Universes::create(parse_input(input)?);
     while !universes.roll() {}
-    let (winner, count) = universes.wins.iter().enumerate().max_by_key(|(_, c)| **c).expect("Non-empty");
-    println!("Player {} wins in {} universes", winner, count);
+    Ok(universes.wins.iter().max().expect("Non-empty").to_string())
+}
+
+pattern_struct! {
+    #[pattern(r"^Player (?P<player>\d+) starting position: (?P<position>\d+)$")]
+    struct Start { player: usize, position: u8 }
+}
+
+fn parse_input(input: &str) -> Result<[u8; 2]> {
+    let starts: Vec<Start> = parse_lines(input)?;
+    ensure!(starts.len() == 2, "Expected two players, found {}", starts.len());
+    let mut positions = [0; 2];
+    for start in starts {
+        ensure!((1..=2).contains(&start.player), "Unexpected player {}", start.player);
+        ensure!((1..=10).contains(&start.position), "Invalid position {}", start.position);
+        positions[start.player - 1] = start.position;
+    }
+    Ok(positions)
 }
 
 fn play_with_fake_die(board: &mut Board) -> (usize, u32) {
@@ -1,51 +1,51 @@
 use anyhow::{Result, Error, bail};
 
 use advent_2021::parsing::*;
-use advent_2021::euclid3d::{Point,point};
+use advent_2021::euclid3d::{Point,point,Solid};
 use std::str::FromStr;
-use std::collections::VecDeque;
 use std::cmp;
 use advent_2021::terminal::Terminal;
-
-fn main() -> Result<()> {
-    let input = parse_input(include_str!("input.txt"))?;
-    let constrained = constrain_to_initialization_area(&input);
-
-    if std::env::args().len() > 1 {
-        println!("{}", openscad(&input));
-        return Ok(())
+use advent_2021::input;
+
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    let steps = parse_input(&input)?;
+    let constrained = constrain_to_initialization_area(&steps);
+
+    // Meshing the full input is too expensive, so only the initialization area is exported
+    match input::args().into_iter().next().as_deref() {
+        Some("stl") => { print!("{}", solid(&constrained).stl("reactor")); return Ok(()); },
+        Some("obj") => { print!("{}", solid(&constrained).obj()); return Ok(()); },
+        Some(_) => { print!("{}", solid(&steps).openscad()); return Ok(()); },
+        None => {},
     }
 
     if Terminal::active() {
         println!("Simulated:           {}", simulate(&constrained));
     }
 
-    println!("Initialization Area: {}", emulate(&constrained));
-    println!("Full Area:           {}", emulate(&input));
+    println!("Initialization Area: {}", part1(&input)?);
+    println!("Full Area:           {}", part2(&input)?);
 
     Ok(())
 }
 
-fn openscad(steps: &[Step]) -> String {
-    let mut lines = VecDeque::new();
-    for step in steps {
-        let min = step.region.min;
-        let max = step.region.max;
-        let mut annotation = "";
-        if matches!(step.state, State::Off) {
-            lines.push_front("difference() { union() {".into());
-            lines.push_back("}".into());
-            annotation = ""; // Set to # to see removed regions
-        }
-        lines.push_back(format!("// {} x={}..{},y={}..{},z={}..{}", step.state, min.x, max.x, min.y, max.y, min.z, max.z));
-        lines.push_back(format!("{}ocube({},{}, {},{}, {},{});", annotation, min.x, max.x, min.y, max.y, min.z, max.z));
-        if matches!(step.state, State::Off) {
-            lines.push_back("}".into());
+pub fn part1(input: &str) -> Result<String> {
+    Ok(emulate(&constrain_to_initialization_area(&parse_input(input)?)).to_string())
+}
+
+pub fn part2(input: &str) -> Result<String> {
+    Ok(emulate(&parse_input(input)?).to_string())
+}
+
+fn solid(steps: &[Step]) -> Solid {
+    steps.iter().fold(Solid::empty(), |solid, step| {
+        let cuboid = Solid::cuboid(step.region.min, step.region.max);
+        match step.state {
+            State::On => solid.union(cuboid),
+            State::Off => solid.difference(cuboid),
         }
-    }
-    lines.push_front("".into());
-    lines.push_front("module ocube(x1, x2, y1, y2, z1, z2) { translate([x1, y1, z1]) cube([x2-x1+1, y2-y1+1, z2-z1+1]); }".into());
-    lines.make_contiguous().join("\n")
+    })
 }
 
 fn constrain_to_initialization_area(steps: &[Step]) -> Vec<Step> {
@@ -111,28 +111,29 @@ struct Step {
     region: Cuboid,
 }
 
+impl FromStr for State {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "on" => Ok(State::On),
+            "off" => Ok(State::Off),
+            _ => bail!("Invalid state"),
+        }
+    }
+}
+
+pattern_struct! {
+    #[pattern(r"^(?P<state>on|off) x=(?P<x1>-?\d+)\.\.(?P<x2>-?\d+),y=(?P<y1>-?\d+)\.\.(?P<y2>-?\d+),z=(?P<z1>-?\d+)\.\.(?P<z2>-?\d+)$")]
+    struct StepLine { state: State, x1: i32, x2: i32, y1: i32, y2: i32, z1: i32, z2: i32 }
+}
+
 impl FromStr for Step {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let regex = static_regex!(r"(.*) x=(.*)\.\.(.*),y=(.*)\.\.(.*),z=(.*)\.\.(.*)");
-        let caps = regex_captures(regex, s)?;
-        let state = match capture_group(&caps, 1) { // Could use parse()
-            "on" => State::On,
-            "off" => State::Off,
-            _ => bail!("Invalid {}", s),
-        };
-        let min = point(
-            capture_group(&caps, 2).parse()?,
-            capture_group(&caps, 4).parse()?,
-            capture_group(&caps, 6).parse()?,
-        );
-        let max = point(
-            capture_group(&caps, 3).parse()?,
-            capture_group(&caps, 5).parse()?,
-            capture_group(&caps, 7).parse()?,
-        );
-        Ok(Step{ state, region: Cuboid {min, max, }, })
+        let l: StepLine = s.parse()?;
+        Ok(Step{ state: l.state, region: Cuboid { min: point(l.x1, l.y1, l.z1), max: point(l.x2, l.y2, l.z2), }, })
     }
 }
 
@@ -267,29 +268,16 @@ impl Area {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_2021::puzzle::this_day;
 
     #[test]
-    fn example1() {
-        let example = parse_input(include_str!("example1.txt")).unwrap();
-        assert_eq!(simulate(&example), 39);
-        assert_eq!(emulate(&example), 39);
-    }
-
-    #[test]
-    fn example2() {
-        let example = parse_input(include_str!("example2.txt")).unwrap();
-        let example_init = constrain_to_initialization_area(&example);
-        assert_eq!(simulate(&example_init), 590784);
-        assert_eq!(emulate(&example_init), 590784);
-        assert_eq!(emulate(&example), 39769202357779); // not specified
-    }
-
-    #[test]
-    fn example3() {
-        let example = parse_input(include_str!("example3.txt")).unwrap();
-        let example_init = constrain_to_initialization_area(&example);
-        assert_eq!(simulate(&example_init), 474140);
-        assert_eq!(emulate(&example_init), 474140);
-        assert_eq!(emulate(&example), 2758514936282235);
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            let steps = parse_input(example.input()).unwrap();
+            let steps_init = constrain_to_initialization_area(&steps);
+            assert!(example.check(1, simulate(&steps_init)));
+            assert!(example.check(1, emulate(&steps_init)));
+            assert!(example.check(2, emulate(&steps)));
+        }
     }
 }
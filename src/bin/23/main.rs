@@ -2,40 +2,60 @@ use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 use std::str::FromStr;
 use std::time::Duration;
-use anyhow::{Result, Error, bail};
+use anyhow::{anyhow, Result, Error, bail};
 
 use advent_2021::pathfinding::{Graph, Edge};
-use advent_2021::terminal::{elapsed,Terminal};
+use advent_2021::terminal::{elapsed,Frame,Terminal};
+use advent_2021::input;
 
 // Credit to https://github.com/githuib/AdventOfCode/blob/master/year2021/day23/__init__.py for
 // some of the equations used below.
 
-fn main() -> Result<()> {
+pub fn main() -> Result<()> {
     let _drop = Terminal::init();
-    let burrow: Burrow = include_str!("input.txt").parse()?;
-
-    let route = elapsed!(burrow.use_a_star(|b| b.heuristic_distance()).unwrap());
-    display_route(&route);
-    let cost = route.iter().map(|e| e.weight()).sum::<i32>();
-    compare_algorithms(&burrow, cost);
-    Terminal::end_interactive();
-    println!("Energy required for the initial burrow:  {}", cost);
-
-    let burrow: Burrow = unfold_input(include_str!("input.txt")).parse()?;
-    let route = elapsed!(burrow.use_a_star(|b| b.heuristic_distance()).unwrap());
-    display_route(&route);
-    let cost = route.iter().map(|e| e.weight()).sum::<i32>();
-    compare_algorithms(&burrow, cost);
-    println!("Energy required for the unfolded burrow: {}", cost);
+    let input = input::read(include_str!("input.txt"))?;
+    // Animate each route and, with the timing feature, compare the search algorithms
+    if Terminal::active() || cfg!(feature="timing") {
+        for burrow in [input.parse::<Burrow>()?, unfold_input(&input).parse()?] {
+            let route = elapsed!(least_energy_route(&burrow)?);
+            display_route(&route);
+            compare_algorithms(&burrow, energy(&route));
+            Terminal::end_interactive();
+        }
+    }
+    println!("Energy required for the initial burrow:  {}", part1(&input)?);
+    println!("Energy required for the unfolded burrow: {}", part2(&input)?);
 
     Ok(())
 }
 
+pub fn part1(input: &str) -> Result<String> {
+    Ok(energy(&least_energy_route(&input.parse()?)?).to_string())
+}
+
+pub fn part2(input: &str) -> Result<String> {
+    Ok(energy(&least_energy_route(&unfold_input(input).parse()?)?).to_string())
+}
+
+fn least_energy_route(burrow: &Burrow) -> Result<Vec<Edge<Burrow>>> {
+    burrow.use_a_star(|b| b.heuristic_distance()).ok_or_else(|| anyhow!("No route"))
+}
+
+fn energy(route: &[Edge<Burrow>]) -> i32 {
+    route.iter().map(|e| e.weight()).sum()
+}
+
 fn display_route(route: &[Edge<Burrow>]) {
+    fn frame(burrow: &Burrow, energy: i32) -> Frame {
+        Frame::stack([Frame::text(burrow), Frame::text(format!("Energy used: {}", energy))])
+    }
+
     if Terminal::active() {
-        Terminal::interactive_display(route[0].source(), Duration::from_millis(500));
+        Terminal::interactive_frame(|| frame(route[0].source(), 0), Duration::from_millis(500));
+        let mut energy = 0;
         for edge in route {
-            Terminal::interactive_display(edge.dest(), Duration::from_millis(500));
+            energy += edge.weight();
+            Terminal::interactive_frame(|| frame(edge.dest(), energy), Duration::from_millis(500));
         }
     }
 }
@@ -338,10 +358,26 @@ impl std::fmt::Display for Burrow {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_2021::puzzle::this_day;
+
+    fn example() -> String {
+        this_day!().example("example").unwrap().input().to_string()
+    }
+
+    #[test]
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            assert!(example.check(1, part1(example.input()).unwrap()));
+            // Pretty slow without --release, and part 1 gives reasonable coverage
+            if cfg!(not(debug_assertions)) {
+                assert!(example.check(2, part2(example.input()).unwrap()));
+            }
+        }
+    }
 
     #[test]
     fn display_round_trips() {
-        let input = include_str!("example.txt");
+        let input = &example();
         let a: Burrow = input.parse().unwrap();
         assert_eq!(a.to_string(), input.trim());
         let b: Burrow = a.to_string().parse().unwrap();
@@ -356,14 +392,14 @@ mod tests {
 
     #[test]
     fn neighbors() {
-        let a: Burrow = include_str!("example.txt").parse().unwrap();
+        let a: Burrow = example().parse().unwrap();
         let next = a.neighbors(&a);
         assert_eq!(next.len(), 28, "{:?}", next);
     }
 
     #[test]
     fn can_move() {
-        let a: Burrow = include_str!("example.txt").parse().unwrap();
+        let a: Burrow = example().parse().unwrap();
         for r in 0..4 {
             for h in 0..7 {
                 assert!(a.can_move(r, h), "Can't move from {} to {}", r, h);
@@ -373,7 +409,7 @@ mod tests {
 
     #[test]
     fn path_between() {
-        let burrow: Burrow = include_str!("example.txt").parse().unwrap();
+        let burrow: Burrow = example().parse().unwrap();
         assert!(burrow.can_move(3, 2));
 
         let mut burrow = burrow;
@@ -389,7 +425,7 @@ mod tests {
 
     #[test]
     fn example1_dijkstras() {
-        let burrow: Burrow = include_str!("example.txt").parse().unwrap();
+        let burrow: Burrow = example().parse().unwrap();
         let djk = burrow.use_dijkstras().unwrap();
         assert_eq!(djk.len(), 12);
         let djk_cost = djk.iter().map(|e| e.weight()).sum::<i32>();
@@ -398,7 +434,7 @@ mod tests {
 
     #[test]
     fn example1_a_star() {
-        let burrow: Burrow = include_str!("example.txt").parse().unwrap();
+        let burrow: Burrow = example().parse().unwrap();
         let djk = burrow.use_a_star(|b| b.heuristic_distance()).unwrap();
         assert_eq!(djk.len(), 12);
         let djk_cost = djk.iter().map(|e| e.weight()).sum::<i32>();
@@ -408,7 +444,7 @@ mod tests {
     #[cfg(not(debug_assertions))] // Pretty slow without --release, and example1 gives reasonable coverage
     #[test]
     fn example2_dijkstras() {
-        let burrow: Burrow = unfold_input(include_str!("example.txt")).parse().unwrap();
+        let burrow: Burrow = unfold_input(&example()).parse().unwrap();
         let djk = burrow.use_dijkstras().unwrap();
         assert_eq!(djk.len(), 28); // I count 24 steps in the example, but the cost is what really matters anyways
         let djk_cost = djk.iter().map(|e| e.weight()).sum::<i32>();
@@ -418,7 +454,7 @@ mod tests {
     #[cfg(not(debug_assertions))] // Pretty slow without --release, and example1 gives reasonable coverage
     #[test]
     fn example2_a_star() {
-        let burrow: Burrow = unfold_input(include_str!("example.txt")).parse().unwrap();
+        let burrow: Burrow = unfold_input(&example()).parse().unwrap();
         let djk = burrow.use_a_star(|b| b.heuristic_distance()).unwrap();
         assert_eq!(djk.len(), 28); // I count 24 steps in the example, but the cost is what really matters anyways
         let djk_cost = djk.iter().map(|e| e.weight()).sum::<i32>();
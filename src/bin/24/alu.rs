@@ -0,0 +1,230 @@
+// The ALU's instruction set and interpreter, shared with the alu-debugger binary.
+use anyhow::{anyhow, bail, ensure, Error, Result};
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Argument {
+    Register(char),
+    Literal(i64),
+}
+
+impl FromStr for Argument {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "w"|"x"|"y"|"z" => Argument::Register(s.chars().next().expect("Must be present")),
+            _ => Argument::Literal(s.parse()?),
+        })
+    }
+}
+
+impl fmt::Display for Argument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Argument::Register(c) => write!(f, "{}", c),
+            Argument::Literal(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Instruction {
+    Inp(Argument),
+    Add(Argument, Argument),
+    Mul(Argument, Argument),
+    Div(Argument, Argument),
+    Mod(Argument, Argument),
+    Eql(Argument, Argument),
+}
+
+impl FromStr for Instruction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = s.split(' ').collect();
+        ensure!(parts.len() == 3 || (parts.len() == 2 && parts[0] == "inp"));
+        Ok(match parts[0] {
+            "inp" => Instruction::Inp(parts[1].parse()?),
+            "add" => Instruction::Add(parts[1].parse()?, parts[2].parse()?),
+            "mul" => Instruction::Mul(parts[1].parse()?, parts[2].parse()?),
+            "div" => Instruction::Div(parts[1].parse()?, parts[2].parse()?),
+            "mod" => Instruction::Mod(parts[1].parse()?, parts[2].parse()?),
+            "eql" => Instruction::Eql(parts[1].parse()?, parts[2].parse()?),
+            _ => bail!("Invalid instruction: {:?}", s),
+        })
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, a, b) = match self {
+            Instruction::Inp(a) => return write!(f, "inp {}", a),
+            Instruction::Add(a, b) => ("add", a, b),
+            Instruction::Mul(a, b) => ("mul", a, b),
+            Instruction::Div(a, b) => ("div", a, b),
+            Instruction::Mod(a, b) => ("mod", a, b),
+            Instruction::Eql(a, b) => ("eql", a, b),
+        };
+        write!(f, "{} {} {}", name, a, b)
+    }
+}
+
+#[derive(Debug)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+}
+
+impl Program {
+    // Returns one or more programs, where index 0 is a constant prelude, and each subsequent index
+    // begins with an INP command and contains all subsequent commands until the next INP.
+    pub fn split_at_reads(&self) -> Vec<Program> {
+        let mut ret = vec![Vec::new()];
+
+        for instr in &self.instructions {
+            if let Instruction::Inp(_) = instr {
+                ret.push(Vec::new());
+            }
+            ret.last_mut().expect("Non-empty").push(*instr);
+        }
+
+        ret.into_iter().map(|instructions| Program{ instructions }).collect()
+    }
+}
+
+impl FromStr for Program {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let instructions = s.lines()
+            .filter(|l| !l.trim().is_empty() && !l.trim().starts_with('#'))
+            .map(|l| l.parse())
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Program { instructions })
+    }
+}
+
+// Disassembles the program back into source that can be parsed again. The alternate form ({:#})
+// is a listing with each instruction's index, and a blank line before each inp.
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.instructions.len().saturating_sub(1).to_string().len();
+        for (i, instr) in self.instructions.iter().enumerate() {
+            if f.alternate() {
+                if i > 0 && matches!(instr, Instruction::Inp(_)) {
+                    writeln!(f)?;
+                }
+                write!(f, "{:>width$}  ", i, width = width)?;
+            }
+            writeln!(f, "{}", instr)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Hash, Eq, PartialEq, Copy, Clone)]
+pub struct LogicUnit {
+    pub registers: [i64; 4],
+}
+
+impl LogicUnit {
+    pub fn new() -> LogicUnit {
+        LogicUnit{ registers: [0; 4], }
+    }
+
+    pub fn register_idx(variable: char) -> Result<usize> {
+        ensure!(('w'..='z').contains(&variable));
+        Ok(variable as usize - 'w' as usize)
+    }
+
+    pub fn register_name(index: usize) -> char {
+        (b'w' + index as u8) as char
+    }
+
+    fn read(&self, arg: &Argument) -> Result<i64> {
+        Ok(match arg {
+            Argument::Register(c) => self.registers[LogicUnit::register_idx(*c)?],
+            Argument::Literal(n) => *n,
+        })
+    }
+
+    fn write(&mut self, arg: &Argument, value: i64) -> Result<()> {
+        if let Argument::Register(c) = arg {
+            self.registers[LogicUnit::register_idx(*c)?] = value;
+            return Ok(());
+        }
+        bail!("Cannot write to {:?}", arg)
+    }
+
+    // Executes a single instruction, taking a value from input if it's an inp
+    pub fn apply(&mut self, instr: &Instruction, input: &mut impl Iterator<Item = i64>) -> Result<()> {
+        match instr {
+            Instruction::Inp(a) => {
+                self.write(a, input.next().ok_or_else(|| anyhow!("Insufficient input"))?)?;
+            },
+            Instruction::Add(a, b) => {
+                self.write(a, self.read(a)? + self.read(b)?)?;
+            },
+            Instruction::Mul(a, b) => {
+                self.write(a, self.read(a)? * self.read(b)?)?;
+            },
+            Instruction::Div(a, b) => {
+                let value = self.read(a)?.checked_div(self.read(b)?).ok_or_else(|| anyhow!("Division by zero"))?;
+                self.write(a, value)?;
+            },
+            Instruction::Mod(a, b) => {
+                let value = self.read(a)?.checked_rem(self.read(b)?).ok_or_else(|| anyhow!("Division by zero"))?;
+                self.write(a, value)?;
+            },
+            Instruction::Eql(a, b) => {
+                let eq = self.read(a)? == self.read(b)?;
+                self.write(a, if eq { 1 } else { 0 })?;
+            },
+        }
+        Ok(())
+    }
+
+    pub fn execute(&mut self, program: &Program, input: &[i64]) -> Result<()> {
+        let mut input = input.iter().copied();
+        for instr in &program.instructions {
+            self.apply(instr, &mut input)?;
+        }
+        ensure!(input.next().is_none(), "Not all input consumed.");
+        Ok(())
+    }
+}
+
+impl fmt::Display for LogicUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [w, x, y, z] = self.registers;
+        write!(f, "w={} x={} y={} z={}", w, x, y, z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static SOURCE: &str = "inp w\nadd z w\nmod z 2\ndiv w 2\nadd y w\nmod y 2\ndiv w 2\nadd x w\nmod x 2\ndiv w 2\nmod w 2\n";
+
+    #[test]
+    fn disassemble() {
+        let program: Program = SOURCE.parse().unwrap();
+        assert_eq!(program.to_string(), SOURCE);
+        assert_eq!(program.to_string().parse::<Program>().unwrap().instructions, program.instructions);
+
+        let program: Program = "inp w\nadd x -1\ninp z\neql z w".parse().unwrap();
+        assert_eq!(format!("{:#}", program), "0  inp w\n1  add x -1\n\n2  inp z\n3  eql z w\n");
+    }
+
+    #[test]
+    fn registers() {
+        for (i, r) in ['w', 'x', 'y', 'z'].into_iter().enumerate() {
+            assert_eq!(LogicUnit::register_idx(r).unwrap(), i);
+            assert_eq!(LogicUnit::register_name(i), r);
+        }
+        assert!(LogicUnit::register_idx('a').is_err());
+    }
+}
@@ -0,0 +1,276 @@
+// Compiles ALU programs into register-indexed bytecode. Registers are resolved and validated once,
+// instructions whose result is known at compile time are folded into constants, and writes that
+// are overwritten before being read are dropped.
+use anyhow::{anyhow, bail, ensure, Context, Result};
+
+use std::fmt;
+use super::alu::{Argument, Instruction, LogicUnit, Program};
+use super::symbolic::Op;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Operand {
+    Register(usize),
+    Literal(i64),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Code {
+    Inp(usize),
+    Set(usize, i64),
+    Copy(usize, usize),
+    Apply(Op, usize, Operand),
+}
+
+impl Code {
+    // Whether executing this code could fail, in which case it can't be dropped even if its
+    // result is never read
+    fn fallible(&self) -> bool {
+        match self {
+            Code::Apply(Op::Div | Op::Mod, _, Operand::Literal(n)) => *n == 0 || *n == -1,
+            Code::Apply(Op::Div | Op::Mod, _, Operand::Register(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Code::Inp(d) => write!(f, "{} = inp", LogicUnit::register_name(d)),
+            Code::Set(d, n) => write!(f, "{} = {}", LogicUnit::register_name(d), n),
+            Code::Copy(d, s) => write!(f, "{} = {}", LogicUnit::register_name(d), LogicUnit::register_name(s)),
+            Code::Apply(op, d, src) => {
+                let d = LogicUnit::register_name(d);
+                match src {
+                    Operand::Register(s) => write!(f, "{} = {} {} {}", d, d, op.symbol(), LogicUnit::register_name(s)),
+                    Operand::Literal(n) => write!(f, "{} = {} {} {}", d, d, op.symbol(), n),
+                }
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Compiled {
+    code: Vec<Code>,
+    inputs: usize,
+}
+
+impl Compiled {
+    // Compiles the program, assuming nothing about the registers' initial values
+    pub fn new(program: &Program) -> Result<Compiled> {
+        let mut known = [None; 4];
+        let mut code = Vec::new();
+        for (i, instr) in program.instructions.iter().enumerate() {
+            code.extend(fold(instr, &mut known).with_context(|| format!("At instruction {}: {}", i, instr))?);
+        }
+        let code = eliminate_dead_stores(code);
+        let inputs = code.iter().filter(|c| matches!(c, Code::Inp(_))).count();
+        Ok(Compiled { code, inputs })
+    }
+
+    pub fn code(&self) -> &[Code] { &self.code }
+
+    // Executes the program with the same results as LogicUnit::execute(), though if an error
+    // occurs the registers may not be left in the same state.
+    pub fn execute(&self, alu: &mut LogicUnit, input: &[i64]) -> Result<()> {
+        ensure!(input.len() >= self.inputs, "Insufficient input");
+        ensure!(input.len() <= self.inputs, "Not all input consumed.");
+        let registers = &mut alu.registers;
+        let mut input = input.iter();
+        for code in &self.code {
+            match *code {
+                Code::Inp(d) => registers[d] = *input.next().expect("Input count checked"),
+                Code::Set(d, n) => registers[d] = n,
+                Code::Copy(d, s) => registers[d] = registers[s],
+                Code::Apply(op, d, src) => {
+                    let a = registers[d];
+                    let b = match src {
+                        Operand::Register(s) => registers[s],
+                        Operand::Literal(n) => n,
+                    };
+                    registers[d] = match op {
+                        Op::Add => a + b,
+                        Op::Mul => a * b,
+                        Op::Div => a.checked_div(b).ok_or_else(|| anyhow!("Division by zero"))?,
+                        Op::Mod => a.checked_rem(b).ok_or_else(|| anyhow!("Division by zero"))?,
+                        Op::Eql => if a == b { 1 } else { 0 },
+                    };
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Compiled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for code in &self.code {
+            writeln!(f, "{}", code)?;
+        }
+        Ok(())
+    }
+}
+
+fn destination(arg: &Argument) -> Result<usize> {
+    match arg {
+        Argument::Register(c) => LogicUnit::register_idx(*c),
+        Argument::Literal(_) => bail!("Cannot write to {}", arg),
+    }
+}
+
+// Translates a single instruction, given the registers whose values are known at this point, and
+// updates them with its result. Returns None if the instruction has no effect.
+fn fold(instr: &Instruction, known: &mut [Option<i64>; 4]) -> Result<Option<Code>> {
+    let (op, a, b) = match Op::of(instr) {
+        Some(parts) => parts,
+        None => {
+            let Instruction::Inp(a) = instr else { unreachable!() };
+            let d = destination(a)?;
+            known[d] = None;
+            return Ok(Some(Code::Inp(d)));
+        },
+    };
+    let d = destination(&a)?;
+    let src = match b {
+        Argument::Register(c) => {
+            let s = LogicUnit::register_idx(c)?;
+            known[s].map(Operand::Literal).unwrap_or(Operand::Register(s))
+        },
+        Argument::Literal(n) => Operand::Literal(n),
+    };
+
+    let code = match (op, known[d], src) {
+        (_, Some(x), Operand::Literal(y)) if op.eval(x, y).is_some() => {
+            let n = op.eval(x, y).expect("Checked");
+            if known[d] == Some(n) { return Ok(None); }
+            Code::Set(d, n)
+        },
+        (Op::Add, _, Operand::Literal(0)) | (Op::Mul | Op::Div, _, Operand::Literal(1)) => return Ok(None),
+        (Op::Mul, _, Operand::Literal(0)) | (Op::Mul, Some(0), _) | (Op::Mod, _, Operand::Literal(1)) => Code::Set(d, 0),
+        (Op::Eql, _, Operand::Register(s)) if s == d => Code::Set(d, 1),
+        (Op::Add, Some(0), Operand::Register(s)) | (Op::Mul, Some(1), Operand::Register(s)) => Code::Copy(d, s),
+        _ => Code::Apply(op, d, src),
+    };
+    known[d] = match code {
+        Code::Set(_, n) => Some(n),
+        _ => None,
+    };
+    Ok(Some(code))
+}
+
+// Removes code whose result is overwritten before it's read. All registers are live at the end.
+fn eliminate_dead_stores(code: Vec<Code>) -> Vec<Code> {
+    let mut live = [true; 4];
+    let mut kept: Vec<_> = code.into_iter().rev()
+        .filter(|code| {
+            match *code {
+                Code::Inp(d) => { live[d] = false; true },
+                Code::Set(d, _) => std::mem::replace(&mut live[d], false),
+                Code::Copy(d, s) => {
+                    let keep = std::mem::replace(&mut live[d], false);
+                    if keep { live[s] = true; }
+                    keep
+                },
+                Code::Apply(_, d, src) => {
+                    let keep = live[d] || code.fallible();
+                    if keep {
+                        live[d] = true;
+                        if let Operand::Register(s) = src { live[s] = true; }
+                    }
+                    keep
+                },
+            }
+        })
+        .collect();
+    kept.reverse();
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folding() {
+        let program: Program = "inp w\nmul x 0\nadd x z\nmod x 26\ndiv z 1\nadd x 12\neql x w\neql x 0\n\
+            mul y 0\nadd y 25\nmul y x\nadd y 1\nmul z y\nmul y 0\nadd y w\nadd y 4\nmul y x\nadd z y".parse().unwrap();
+        let compiled = Compiled::new(&program).unwrap();
+        assert_eq!(compiled.to_string(), "\
+            w = inp\nx = z\nx = x % 26\nx = x + 12\nx = x == w\nx = x == 0\n\
+            y = 25\ny = y * x\ny = y + 1\nz = z * y\ny = w\ny = y + 4\ny = y * x\nz = z + y\n");
+
+        let program: Program = "mul x 0\nadd x 3\nmul x 2\ninp y\nadd y x\nmul w 0\nmul z 0\nadd z 7\neql z z".parse().unwrap();
+        let compiled = Compiled::new(&program).unwrap();
+        assert_eq!(compiled.code(), [
+            Code::Set(1, 6), Code::Inp(2), Code::Apply(Op::Add, 2, Operand::Literal(6)), Code::Set(0, 0), Code::Set(3, 1),
+        ]);
+    }
+
+    #[test]
+    fn fallible_stores_kept() {
+        let program: Program = "inp w\ndiv x w\nmod y 0\nmul x 0\nmul y 0".parse().unwrap();
+        let compiled = Compiled::new(&program).unwrap();
+        assert_eq!(compiled.to_string(), "w = inp\nx = x / w\ny = y % 0\nx = 0\ny = 0\n");
+        assert_eq!(compiled.execute(&mut LogicUnit::new(), &[0]).unwrap_err().to_string(), "Division by zero");
+        assert_eq!(compiled.execute(&mut LogicUnit::new(), &[]).unwrap_err().to_string(), "Insufficient input");
+        assert_eq!(compiled.execute(&mut LogicUnit::new(), &[1, 2]).unwrap_err().to_string(), "Not all input consumed.");
+    }
+
+    #[test]
+    fn errors() {
+        let program = Program { instructions: vec![Instruction::Add(Argument::Literal(3), Argument::Register('x'))] };
+        assert_eq!(format!("{:#}", Compiled::new(&program).unwrap_err()), "At instruction 0: add 3 x: Cannot write to 3");
+    }
+
+    #[test]
+    fn matches_interpreter() {
+        let program: Program = include_str!("input.txt").parse().unwrap();
+        let compiled = Compiled::new(&program).unwrap();
+        assert!(compiled.code().len() < program.instructions.len());
+        let digits = [1, 3, 5, 7, 9, 2, 4, 6, 8, 9, 9, 9, 9, 9];
+        let mut expected = LogicUnit::new();
+        expected.execute(&program, &digits).unwrap();
+        let mut alu = LogicUnit::new();
+        compiled.execute(&mut alu, &digits).unwrap();
+        assert_eq!(alu, expected);
+    }
+
+    // Compares the compiled and interpreted results of many random programs, with small literals so
+    // that folding and identities come up often (and only multiplying by literals, to avoid overflow)
+    #[test]
+    fn random_programs() {
+        let rng = fastrand::Rng::with_seed(24);
+        let registers = ['w', 'x', 'y', 'z'];
+        for _ in 0..2000 {
+            let mut instructions = Vec::new();
+            for _ in 0..rng.usize(1..20) {
+                let a = Argument::Register(registers[rng.usize(..4)]);
+                let b = if rng.bool() { Argument::Register(registers[rng.usize(..4)]) } else { Argument::Literal(rng.i64(-2..4)) };
+                instructions.push(match rng.usize(..6) {
+                    0 => Instruction::Inp(a),
+                    1 => Instruction::Add(a, b),
+                    2 => Instruction::Mul(a, Argument::Literal(rng.i64(-2..4))),
+                    3 => Instruction::Div(a, b),
+                    4 => Instruction::Mod(a, b),
+                    _ => Instruction::Eql(a, b),
+                });
+            }
+            let program = Program { instructions };
+            let compiled = Compiled::new(&program).unwrap();
+            let initial = LogicUnit { registers: [(); 4].map(|_| rng.i64(-5..6)) };
+            let inputs = program.instructions.iter().filter(|i| matches!(i, Instruction::Inp(_))).count();
+            let input: Vec<_> = (0..inputs).map(|_| rng.i64(-9..10)).collect();
+
+            let mut expected = initial;
+            let expected = expected.execute(&program, &input).map(|_| expected);
+            let mut actual = initial;
+            let actual = compiled.execute(&mut actual, &input).map(|_| actual);
+            match (expected, actual) {
+                (Ok(e), Ok(a)) => assert_eq!(a, e, "{}\n{}", program, compiled),
+                (Err(e), Err(a)) => assert_eq!(a.to_string(), e.to_string()),
+                (e, a) => panic!("{:?} != {:?}\n{}\n{}", a, e, program, compiled),
+            }
+        }
+    }
+}
@@ -0,0 +1,381 @@
+// Symbolic execution of ALU programs, tracking each register as an expression of the inputs along
+// with the range of values it can take.
+use anyhow::{bail, Result};
+
+use std::collections::HashSet;
+use std::fmt;
+use std::rc::Rc;
+use super::alu::{Argument, Instruction, LogicUnit, Program};
+use super::compiler::Compiled;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Op { Add, Mul, Div, Mod, Eql }
+
+impl Op {
+    pub fn of(instr: &Instruction) -> Option<(Op, Argument, Argument)> {
+        Some(match *instr {
+            Instruction::Inp(_) => return None,
+            Instruction::Add(a, b) => (Op::Add, a, b),
+            Instruction::Mul(a, b) => (Op::Mul, a, b),
+            Instruction::Div(a, b) => (Op::Div, a, b),
+            Instruction::Mod(a, b) => (Op::Mod, a, b),
+            Instruction::Eql(a, b) => (Op::Eql, a, b),
+        })
+    }
+
+    // None if the operation is invalid or overflows
+    pub fn eval(self, a: i64, b: i64) -> Option<i64> {
+        match self {
+            Op::Add => a.checked_add(b),
+            Op::Mul => a.checked_mul(b),
+            Op::Div => a.checked_div(b),
+            Op::Mod => a.checked_rem(b),
+            Op::Eql => Some((a == b) as i64),
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self { Op::Add => "+", Op::Mul => "*", Op::Div => "/", Op::Mod => "%", Op::Eql => "==" }
+    }
+}
+
+// An inclusive range of values
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Range {
+    pub min: i64,
+    pub max: i64,
+}
+
+impl Range {
+    pub const FULL: Range = Range { min: i64::MIN, max: i64::MAX };
+
+    pub fn new(min: i64, max: i64) -> Range {
+        assert!(min <= max);
+        Range { min, max }
+    }
+
+    pub fn constant(n: i64) -> Range { Range::new(n, n) }
+
+    pub fn contains(&self, n: i64) -> bool { self.min <= n && n <= self.max }
+
+    fn single(&self) -> Option<i64> { if self.min == self.max { Some(self.min) } else { None } }
+
+    // The range of possible results of applying op to values in the two ranges
+    fn apply(op: Op, a: Range, b: Range) -> Range {
+        // Add, Mul and (when b excludes 0) Div are monotonic in each argument, so their extremes
+        // are at the corners
+        let corners = |f: fn(i64, i64) -> Option<i64>| {
+            let values = [f(a.min, b.min), f(a.min, b.max), f(a.max, b.min), f(a.max, b.max)];
+            match values.iter().copied().collect::<Option<Vec<_>>>() {
+                Some(v) => Range::new(*v.iter().min().expect("Non-empty"), *v.iter().max().expect("Non-empty")),
+                None => Range::FULL,
+            }
+        };
+        match op {
+            Op::Add => corners(i64::checked_add),
+            Op::Mul => corners(i64::checked_mul),
+            Op::Div if b.contains(0) => Range::FULL,
+            Op::Div => corners(i64::checked_div),
+            Op::Mod if b.contains(0) || b.min == i64::MIN => Range::FULL,
+            Op::Mod => {
+                // The result has the sign of a and a smaller magnitude than b, and is a itself if
+                // a's magnitude is always smaller than b's
+                let smallest = if b.min > 0 { b.min } else { -b.max };
+                let limit = b.min.abs().max(b.max.abs()) - 1;
+                if a.min > -smallest && a.max < smallest {
+                    a
+                } else {
+                    Range::new(a.min.max(-limit).min(0), a.max.min(limit).max(0))
+                }
+            },
+            Op::Eql => match (a.single(), b.single()) {
+                (Some(x), Some(y)) => Range::constant((x == y) as i64),
+                _ if a.intersect(b).is_none() => Range::constant(0),
+                _ => Range::new(0, 1),
+            },
+        }
+    }
+
+    fn intersect(self, other: Range) -> Option<Range> {
+        let (min, max) = (self.min.max(other.min), self.max.min(other.max));
+        if min <= max { Some(Range::new(min, max)) } else { None }
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.single() {
+            Some(n) => write!(f, "{}", n),
+            None => write!(f, "{}..={}", self.min, self.max),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Expr {
+    Const(i64),
+    // The n'th input
+    Input(usize),
+    // A register's value before execution began
+    Initial(char),
+    Binary(Op, Value, Value),
+}
+
+// An expression, along with the range of values it can take
+#[derive(Clone, Debug)]
+pub struct Value {
+    pub expr: Rc<Expr>,
+    pub range: Range,
+}
+
+impl Value {
+    pub fn constant(n: i64) -> Value {
+        Value { expr: Rc::new(Expr::Const(n)), range: Range::constant(n) }
+    }
+
+    pub fn initial(register: char, range: Range) -> Value {
+        Value { expr: Rc::new(Expr::Initial(register)), range }
+    }
+
+    fn is(&self, n: i64) -> bool { self.range.single() == Some(n) }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &*self.expr {
+            Expr::Const(n) => write!(f, "{}", n),
+            Expr::Input(n) => write!(f, "in{}", n),
+            Expr::Initial(c) => write!(f, "{}", c),
+            Expr::Binary(op, a, b) => write!(f, "({} {} {})", a, op.symbol(), b),
+        }
+    }
+}
+
+// The values registers are tracked as during symbolic execution
+pub trait Domain: Clone {
+    fn constant(n: i64) -> Self;
+    fn input(index: usize, range: Range) -> Self;
+    fn apply(op: Op, a: &Self, b: &Self) -> Self;
+}
+
+impl Domain for Range {
+    fn constant(n: i64) -> Self { Range::constant(n) }
+    fn input(_: usize, range: Range) -> Self { range }
+    fn apply(op: Op, a: &Self, b: &Self) -> Self { Range::apply(op, *a, *b) }
+}
+
+impl Domain for Value {
+    fn constant(n: i64) -> Self { Value::constant(n) }
+
+    fn input(index: usize, range: Range) -> Self {
+        Value { expr: Rc::new(Expr::Input(index)), range }
+    }
+
+    // Simplifies the expression where the ranges make the result constant or an identity, e.g.
+    // an eql of values with disjoint ranges is 0, and a mod by n of a value in 0..n is a no-op.
+    fn apply(op: Op, a: &Self, b: &Self) -> Self {
+        let range = Range::apply(op, a.range, b.range);
+        if let Some(n) = range.single() {
+            return Value::constant(n);
+        }
+        if let (Some(x), Some(y)) = (a.range.single(), b.range.single()) {
+            if let Some(n) = op.eval(x, y) { return Value::constant(n); }
+        }
+        match op {
+            Op::Add if a.is(0) => return b.clone(),
+            Op::Add if b.is(0) => return a.clone(),
+            Op::Mul if a.is(1) => return b.clone(),
+            Op::Mul | Op::Div if b.is(1) => return a.clone(),
+            Op::Mod if b.range.min > 0 && a.range.min >= 0 && a.range.max < b.range.min => return a.clone(),
+            _ => {},
+        }
+        Value { expr: Rc::new(Expr::Binary(op, a.clone(), b.clone())), range }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SymbolicUnit<D> {
+    pub registers: [D; 4],
+    inputs: usize,
+}
+
+impl<D: Domain> SymbolicUnit<D> {
+    pub fn new(registers: [D; 4]) -> SymbolicUnit<D> {
+        SymbolicUnit { registers, inputs: 0 }
+    }
+
+    pub fn from_alu(alu: &LogicUnit) -> SymbolicUnit<D> {
+        SymbolicUnit::new(alu.registers.map(D::constant))
+    }
+
+    fn read(&self, arg: &Argument) -> Result<D> {
+        Ok(match arg {
+            Argument::Register(c) => self.registers[LogicUnit::register_idx(*c)?].clone(),
+            Argument::Literal(n) => D::constant(*n),
+        })
+    }
+
+    fn write(&mut self, arg: &Argument, value: D) -> Result<()> {
+        match arg {
+            Argument::Register(c) => self.registers[LogicUnit::register_idx(*c)?] = value,
+            Argument::Literal(_) => bail!("Cannot write to {:?}", arg),
+        }
+        Ok(())
+    }
+
+    // Executes the program, treating each input as an unknown value in the given range
+    pub fn execute(&mut self, program: &Program, input: Range) -> Result<()> {
+        for instr in &program.instructions {
+            match Op::of(instr) {
+                None => {
+                    let Instruction::Inp(a) = instr else { unreachable!() };
+                    self.write(a, D::input(self.inputs, input))?;
+                    self.inputs += 1;
+                },
+                Some((op, a, b)) => {
+                    let value = D::apply(op, &self.read(&a)?, &self.read(&b)?);
+                    self.write(&a, value)?;
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+// Searches for the largest or smallest sequence of digits (1-9) for which the program ends with
+// z == 0. Candidates are tried in order, skipping any state that range analysis shows can't reach
+// z == 0 and any state already known to fail, so the first sequence found is the answer.
+pub fn solve(program: &Program, largest: bool) -> Result<Option<u64>> {
+    struct Search<'a> {
+        blocks: &'a [Program],
+        compiled: Vec<Compiled>,
+        order: [i64; 9],
+        digits: Vec<i64>,
+        dead: HashSet<(usize, LogicUnit)>,
+    }
+
+    impl Search<'_> {
+        fn feasible(&self, block: usize, alu: &LogicUnit) -> Result<bool> {
+            let mut ranges = SymbolicUnit::<Range>::from_alu(alu);
+            for part in &self.blocks[block..] {
+                ranges.execute(part, Range::new(1, 9))?;
+            }
+            Ok(ranges.registers[3].contains(0))
+        }
+
+        fn search(&mut self, block: usize, alu: LogicUnit) -> Result<bool> {
+            if block == self.blocks.len() {
+                return Ok(alu.registers[3] == 0);
+            }
+            if self.dead.contains(&(block, alu)) || !self.feasible(block, &alu)? {
+                self.dead.insert((block, alu));
+                return Ok(false);
+            }
+            for d in self.order {
+                let mut next = alu;
+                self.compiled[block].execute(&mut next, &[d])?;
+                self.digits.push(d);
+                if self.search(block + 1, next)? { return Ok(true); }
+                self.digits.pop();
+            }
+            self.dead.insert((block, alu));
+            Ok(false)
+        }
+    }
+
+    let mut parts = program.split_at_reads();
+    let mut alu = LogicUnit::new();
+    alu.execute(&parts.remove(0), &[])?;
+    let mut order = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+    if largest { order.reverse(); }
+    let compiled = parts.iter().map(Compiled::new).collect::<Result<_>>()?;
+    let mut search = Search { blocks: &parts, compiled, order, digits: Vec::new(), dead: HashSet::new() };
+    if !search.search(0, alu)? {
+        return Ok(None);
+    }
+    Ok(Some(search.digits.iter().fold(0, |n, &d| n * 10 + d as u64)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_contain_results() {
+        let ranges = [Range::new(-30, -3), Range::new(-5, 7), Range::new(0, 25), Range::new(1, 9), Range::new(26, 80)];
+        for op in [Op::Add, Op::Mul, Op::Div, Op::Mod, Op::Eql] {
+            for a in ranges {
+                for b in ranges {
+                    let range = Range::apply(op, a, b);
+                    for x in a.min..=a.max {
+                        for y in b.min..=b.max {
+                            if let Some(n) = op.eval(x, y) {
+                                assert!(range.contains(n), "{} {} {} = {} not in {}", x, op.symbol(), y, n, range);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn range_precision() {
+        assert_eq!(Range::apply(Op::Mod, Range::new(0, 100), Range::constant(26)), Range::new(0, 25));
+        assert_eq!(Range::apply(Op::Mod, Range::new(3, 9), Range::constant(26)), Range::new(3, 9));
+        assert_eq!(Range::apply(Op::Div, Range::new(0, 25), Range::constant(26)), Range::constant(0));
+        assert_eq!(Range::apply(Op::Eql, Range::new(10, 35), Range::new(1, 9)), Range::constant(0));
+        assert_eq!(Range::apply(Op::Eql, Range::new(5, 35), Range::new(1, 9)), Range::new(0, 1));
+        assert_eq!(Range::apply(Op::Mul, Range::new(2, i64::MAX), Range::new(2, 3)), Range::FULL);
+        assert_eq!(Range::apply(Op::Div, Range::new(2, 3), Range::new(-1, 1)), Range::FULL);
+    }
+
+    fn symbolic(source: &str, initial: [Range; 4]) -> [String; 4] {
+        let program: Program = source.parse().unwrap();
+        let mut registers = ['w', 'x', 'y', 'z'].iter().zip(initial).map(|(&r, range)| Value::initial(r, range));
+        let mut unit = SymbolicUnit::new([(); 4].map(|_| registers.next().unwrap()));
+        unit.execute(&program, Range::new(1, 9)).unwrap();
+        unit.registers.map(|r| r.to_string())
+    }
+
+    #[test]
+    fn simplification() {
+        let any = Range::FULL;
+        assert_eq!(symbolic("inp w\nadd x w\nmul x 1\nadd y 0\nmul z 0", [any; 4]), ["in0", "(x + in0)", "y", "0"]);
+
+        // With z non-negative, z % 26 + 12 can never equal a digit
+        let block = "inp w\nmul x 0\nadd x z\nmod x 26\nadd x 12\neql x w\neql x 0\nmul y 0\nadd y 25\nmul y x\nadd y 1\nmul z y";
+        assert_eq!(symbolic(block, [any, any, any, Range::FULL])[3], "(z * ((25 * ((((z % 26) + 12) == in0) == 0)) + 1))");
+        assert_eq!(symbolic(block, [any, any, any, Range::new(0, i64::MAX / 26)]), ["in0", "1", "26", "(z * 26)"]);
+
+        // A mod of a value already in range is dropped, and a div of one is 0, but only when the
+        // range is known
+        assert_eq!(symbolic("inp w\nadd x w\nmod x 10\nadd y w\ndiv y 10", [any; 4])[1..3], ["((x + in0) % 10)", "((y + in0) / 10)"]);
+        assert_eq!(symbolic("inp w\nmul x 0\nadd x w\nmod x 10\nmul y 0\nadd y w\ndiv y 10", [any; 4])[1..3], ["in0", "0"]);
+    }
+
+    #[test]
+    fn concrete_matches_ranges() {
+        let program: Program = include_str!("input.txt").parse().unwrap();
+        let digits = [1, 3, 5, 7, 9, 2, 4, 6, 8, 9, 9, 9, 9, 9];
+        let mut alu = LogicUnit::new();
+        alu.execute(&program, &digits).unwrap();
+        let mut ranges = SymbolicUnit::<Range>::from_alu(&LogicUnit::new());
+        ranges.execute(&program, Range::new(1, 9)).unwrap();
+        for (value, range) in alu.registers.iter().zip(&ranges.registers) {
+            assert!(range.contains(*value), "{} not in {}", value, range);
+        }
+    }
+
+    parameterized_test::create!{ solving, (source, max, min), {
+        let program: Program = source.parse().unwrap();
+        assert_eq!(solve(&program, true).unwrap(), max);
+        assert_eq!(solve(&program, false).unwrap(), min);
+    } }
+    solving! {
+        equal: ("inp w\ninp x\nadd z w\nmul x -1\nadd z x", Some(99), Some(11)),
+        offset: ("inp w\ninp x\nadd z w\nadd z 3\nmul x -1\nadd z x", Some(69), Some(14)),
+        impossible: ("inp w\nadd z w", None, None),
+        prelude: ("add z 5\ninp w\nmul w -1\nadd z w", Some(5), Some(5)),
+    }
+}
@@ -0,0 +1,357 @@
+// Step debugger for Day 24 ALU programs.
+//
+// Usage: alu-debugger [--input PATH] [--list | --trace] [DIGITS]
+//
+//   --input PATH  the program to debug, defaulting to Day 24's input
+//   --list        print a numbered listing of the program and exit
+//   --trace       run to completion, printing every instruction and its effect
+//   DIGITS        the input values, one per digit, e.g. a model number
+//
+// Without --list or --trace commands are read from stdin, run `help` to list them.
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt;
+use std::io::BufRead;
+use anyhow::{bail, Context, Result};
+
+use advent_2021::input;
+
+#[path = "24/alu.rs"]
+mod alu;
+use alu::{Instruction, LogicUnit, Program};
+
+static HELP: &str = "\
+step [N]      execute the next N (default 1) instructions
+continue      run until a breakpoint, watched register change, or the end
+break N       stop before executing instruction N
+delete N      remove the breakpoint at instruction N
+watch R       stop after any instruction that changes register R
+unwatch R     stop watching register R
+registers     print the current registers
+list [N]      print the N (default 5) instructions around the next one
+trace         toggle printing each instruction as it executes
+quit";
+
+// The effect of executing a single instruction
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Step {
+    pub index: usize,
+    pub instruction: Instruction,
+    pub before: LogicUnit,
+    pub after: LogicUnit,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>4}  {:<12} {}  ->  {}", self.index, self.instruction.to_string(), self.before, self.after)
+    }
+}
+
+// Executes a program one instruction at a time, e.g. for debugging
+#[derive(Debug)]
+pub struct Machine<'a> {
+    program: &'a Program,
+    pc: usize,
+    input: VecDeque<i64>,
+    alu: LogicUnit,
+}
+
+impl<'a> Machine<'a> {
+    pub fn new(program: &'a Program, input: &[i64]) -> Machine<'a> {
+        Machine { program, pc: 0, input: input.iter().copied().collect(), alu: LogicUnit::new() }
+    }
+
+    pub fn program(&self) -> &'a Program { self.program }
+
+    // The index of the next instruction to execute
+    pub fn pc(&self) -> usize { self.pc }
+
+    pub fn alu(&self) -> &LogicUnit { &self.alu }
+
+    pub fn remaining_input(&self) -> usize { self.input.len() }
+
+    pub fn is_finished(&self) -> bool { self.pc >= self.program.instructions.len() }
+
+    // Executes the next instruction, or returns None if the program is finished
+    pub fn step(&mut self) -> Result<Option<Step>> {
+        let instruction = match self.program.instructions.get(self.pc) {
+            Some(instruction) => *instruction,
+            None => return Ok(None),
+        };
+        let before = self.alu;
+        let input = &mut self.input;
+        self.alu.apply(&instruction, &mut std::iter::from_fn(|| input.pop_front()))
+            .map_err(|e| e.context(format!("At instruction {}: {}", self.pc, instruction)))?;
+        let step = Step { index: self.pc, instruction, before, after: self.alu };
+        self.pc += 1;
+        Ok(Some(step))
+    }
+}
+
+struct Debugger<'a> {
+    machine: Machine<'a>,
+    breakpoints: BTreeSet<usize>,
+    watches: BTreeSet<usize>,
+    trace: bool,
+}
+
+impl<'a> Debugger<'a> {
+    fn new(machine: Machine<'a>) -> Debugger<'a> {
+        Debugger { machine, breakpoints: BTreeSet::new(), watches: BTreeSet::new(), trace: false }
+    }
+
+    // Executes up to limit instructions (or until the end), appending the output to display to out,
+    // stopping early at breakpoints and watched register changes. Output from the instructions that
+    // ran is kept even if a later one fails.
+    fn run(&mut self, limit: Option<usize>, out: &mut Vec<String>) -> Result<()> {
+        let mut executed = 0;
+        while limit.map(|l| executed < l).unwrap_or(true) {
+            // Don't stop at a breakpoint we've only just stopped at
+            if executed > 0 && self.breakpoints.contains(&self.machine.pc()) {
+                out.push(format!("Breakpoint at {}", self.machine.pc()));
+                break;
+            }
+            let step = match self.machine.step()? {
+                Some(step) => step,
+                None => break,
+            };
+            executed += 1;
+            if self.trace || limit.is_some() {
+                out.push(step.to_string());
+            }
+            let changed: Vec<_> = self.watches.iter()
+                .filter(|&&r| step.before.registers[r] != step.after.registers[r])
+                .map(|&r| format!("{}: {} -> {}", LogicUnit::register_name(r), step.before.registers[r], step.after.registers[r]))
+                .collect();
+            if !changed.is_empty() {
+                if !self.trace && limit.is_none() {
+                    out.push(step.to_string());
+                }
+                out.push(format!("Watched {}", changed.join(", ")));
+                break;
+            }
+        }
+        if self.machine.is_finished() {
+            out.push(format!("Finished: {}", self.machine.alu()));
+        }
+        Ok(())
+    }
+
+    fn listing(&self, context: usize) -> Vec<String> {
+        let instructions = &self.machine.program().instructions;
+        let pc = self.machine.pc();
+        let start = pc.saturating_sub(context / 2);
+        (start..instructions.len()).take(context)
+            .map(|i| {
+                let marker = if i == pc { "=>" } else if self.breakpoints.contains(&i) { " *" } else { "  " };
+                format!("{} {:>4}  {}", marker, i, instructions[i])
+            })
+            .collect()
+    }
+
+    // Executes a single command, appending its output to out. Returns false if the debugger should
+    // exit.
+    fn command(&mut self, line: &str, out: &mut Vec<String>) -> Result<bool> {
+        let parts: Vec<_> = line.split_whitespace().collect();
+        let arg = |i: usize| -> Result<usize> {
+            let arg = parts.get(i).with_context(|| format!("{} requires an argument", parts[0]))?;
+            arg.parse().with_context(|| format!("Invalid argument: {}", arg))
+        };
+        let register = |i: usize| -> Result<usize> {
+            let arg = parts.get(i).with_context(|| format!("{} requires a register", parts[0]))?;
+            match arg.chars().collect::<Vec<_>>()[..] {
+                [c] => LogicUnit::register_idx(c).with_context(|| format!("Invalid register: {}", arg)),
+                _ => bail!("Invalid register: {}", arg),
+            }
+        };
+
+        let lines = match parts.first().copied().unwrap_or("") {
+            "" => vec![],
+            "s"|"step" => { self.run(Some(if parts.len() > 1 { arg(1)? } else { 1 }), out)?; vec![] },
+            "c"|"continue" => { self.run(None, out)?; vec![] },
+            "b"|"break" => {
+                let index = arg(1)?;
+                if index >= self.machine.program().instructions.len() { bail!("No instruction {}", index); }
+                self.breakpoints.insert(index);
+                vec![format!("Breakpoint at {}", index)]
+            },
+            "d"|"delete" => {
+                let index = arg(1)?;
+                if !self.breakpoints.remove(&index) { bail!("No breakpoint at {}", index); }
+                vec![]
+            },
+            "w"|"watch" => { self.watches.insert(register(1)?); vec![] },
+            "unwatch" => { self.watches.remove(&register(1)?); vec![] },
+            "r"|"registers" => vec![self.machine.alu().to_string()],
+            "l"|"list" => self.listing(if parts.len() > 1 { arg(1)? } else { 5 }),
+            "t"|"trace" => {
+                self.trace = !self.trace;
+                vec![format!("Tracing {}", if self.trace { "on" } else { "off" })]
+            },
+            "h"|"help" => HELP.lines().map(String::from).collect(),
+            "q"|"quit" => return Ok(false),
+            other => bail!("Unknown command: {} (try help)", other),
+        };
+        out.extend(lines);
+        Ok(true)
+    }
+}
+
+fn main() -> Result<()> {
+    let program: Program = input::read(include_str!("24/input.txt"))?.parse()?;
+    let mut args = input::args();
+    let list = take_flag(&mut args, "--list");
+    let trace = take_flag(&mut args, "--trace");
+    let digits = match &args[..] {
+        [] => vec![],
+        [digits] => digits.chars()
+            .map(|c| c.to_digit(10).map(|d| d as i64).with_context(|| format!("Invalid digit: {}", c)))
+            .collect::<Result<_>>()?,
+        _ => bail!("Unexpected arguments: {:?}", args),
+    };
+
+    if list {
+        print!("{:#}", program);
+        return Ok(());
+    }
+
+    let mut debugger = Debugger::new(Machine::new(&program, &digits));
+    if trace {
+        debugger.trace = true;
+        let mut out = Vec::new();
+        let result = debugger.run(None, &mut out);
+        out.iter().for_each(|l| println!("{}", l));
+        return result;
+    }
+
+    println!("{} instructions, {} inputs; type help for commands", program.instructions.len(), digits.len());
+    for line in std::io::stdin().lock().lines() {
+        let mut out = Vec::new();
+        let result = debugger.command(&line?, &mut out);
+        out.iter().for_each(|l| println!("{}", l));
+        match result {
+            Ok(true) => {},
+            Ok(false) => break,
+            Err(e) => println!("Error: {:#}", e),
+        }
+    }
+    Ok(())
+}
+
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let len = args.len();
+    args.retain(|a| a != flag);
+    args.len() != len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alu::Argument;
+
+    static SOURCE: &str = "inp w\nadd z w\nmul z 3\ninp x\nadd y x\neql y z";
+
+    fn run(program: &Program, input: &[i64], commands: &[&str]) -> Vec<String> {
+        let mut debugger = Debugger::new(Machine::new(program, input));
+        let mut out = Vec::new();
+        for command in commands {
+            assert!(debugger.command(command, &mut out).unwrap());
+        }
+        out
+    }
+
+    #[test]
+    fn breakpoints() {
+        let program: Program = SOURCE.parse().unwrap();
+        let out = run(&program, &[2, 6], &["break 3", "continue", "registers", "continue"]);
+        assert_eq!(out, [
+            "Breakpoint at 3", "Breakpoint at 3", "w=2 x=0 y=0 z=6", "Finished: w=2 x=6 y=1 z=6",
+        ]);
+    }
+
+    #[test]
+    fn stepping() {
+        let program: Program = SOURCE.parse().unwrap();
+        let out = run(&program, &[2, 6], &["step", "step 2", "list 3"]);
+        assert_eq!(out, [
+            "   0  inp w        w=0 x=0 y=0 z=0  ->  w=2 x=0 y=0 z=0",
+            "   1  add z w      w=2 x=0 y=0 z=0  ->  w=2 x=0 y=0 z=2",
+            "   2  mul z 3      w=2 x=0 y=0 z=2  ->  w=2 x=0 y=0 z=6",
+            "      2  mul z 3", "=>    3  inp x", "      4  add y x",
+        ]);
+    }
+
+    #[test]
+    fn watches() {
+        let program: Program = SOURCE.parse().unwrap();
+        let out = run(&program, &[2, 6], &["watch y", "continue", "continue"]);
+        assert_eq!(out, [
+            "   4  add y x      w=2 x=6 y=0 z=6  ->  w=2 x=6 y=6 z=6",
+            "Watched y: 0 -> 6",
+            "   5  eql y z      w=2 x=6 y=6 z=6  ->  w=2 x=6 y=1 z=6",
+            "Watched y: 6 -> 1",
+            "Finished: w=2 x=6 y=1 z=6",
+        ]);
+    }
+
+    #[test]
+    fn errors() {
+        let program: Program = SOURCE.parse().unwrap();
+        let mut debugger = Debugger::new(Machine::new(&program, &[]));
+        let mut out = Vec::new();
+        assert!(debugger.command("break 10", &mut out).is_err());
+        assert!(debugger.command("delete 1", &mut out).is_err());
+        assert!(debugger.command("watch q", &mut out).is_err());
+        assert!(debugger.command("jump", &mut out).is_err());
+        assert!(debugger.command("continue", &mut out).unwrap_err().to_string().contains("At instruction 0"));
+        assert!(!debugger.command("quit", &mut out).unwrap());
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn machine() {
+        let program: Program = "inp w\nadd z w\nmod z 2\ndiv w 2\nadd y w\nmod y 2".parse().unwrap();
+        let mut machine = Machine::new(&program, &[10]);
+        let first = machine.step().unwrap().unwrap();
+        assert_eq!(first.index, 0);
+        assert_eq!(first.instruction, Instruction::Inp(Argument::Register('w')));
+        assert_eq!(first.after.registers, [10, 0, 0, 0]);
+        assert_eq!(first.to_string(), "   0  inp w        w=0 x=0 y=0 z=0  ->  w=10 x=0 y=0 z=0");
+        assert_eq!(machine.remaining_input(), 0);
+
+        while machine.step().unwrap().is_some() {}
+        assert!(machine.is_finished());
+        assert_eq!(machine.pc(), program.instructions.len());
+
+        let mut alu = LogicUnit::new();
+        alu.execute(&program, &[10]).unwrap();
+        assert_eq!(machine.alu(), &alu);
+    }
+
+    #[test]
+    fn machine_errors() {
+        let program: Program = "inp w\ndiv x w".parse().unwrap();
+        let mut machine = Machine::new(&program, &[]);
+        assert_eq!(machine.step().unwrap_err().to_string(), "At instruction 0: inp w");
+        assert_eq!(machine.pc(), 0);
+
+        let mut machine = Machine::new(&program, &[0]);
+        machine.step().unwrap();
+        assert_eq!(format!("{:#}", machine.step().unwrap_err()), "At instruction 1: div x w: Division by zero");
+    }
+
+    #[test]
+    fn trace_until_error() {
+        let program: Program = SOURCE.parse().unwrap();
+        let mut debugger = Debugger::new(Machine::new(&program, &[2]));
+        let mut out = Vec::new();
+        assert!(debugger.command("trace", &mut out).unwrap());
+        let err = debugger.command("continue", &mut out).unwrap_err();
+        assert_eq!(err.to_string(), "At instruction 3: inp x");
+        assert_eq!(out, [
+            "Tracing on",
+            "   0  inp w        w=0 x=0 y=0 z=0  ->  w=2 x=0 y=0 z=0",
+            "   1  add z w      w=2 x=0 y=0 z=0  ->  w=2 x=0 y=0 z=2",
+            "   2  mul z 3      w=2 x=0 y=0 z=2  ->  w=2 x=0 y=0 z=6",
+        ]);
+    }
+}
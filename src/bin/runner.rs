@@ -0,0 +1,203 @@
+// Runs each day's solutions against its input (and optionally its examples), checks the results
+// against the recorded answers, and prints a Markdown table of the results and timings.
+//
+// Usage: runner [DAYS] [--examples] [--repeat N] [--save]
+//
+//   DAYS        the days to run, e.g. 1..5,15,23; defaults to all of them
+//   --examples  also run each example that has recorded answers
+//   --repeat N  run each part N times and report the average runtime
+//   --save      record the answers for any input that doesn't have any yet
+use std::time::{Duration, Instant};
+use anyhow::{bail, Context, Result};
+
+use advent_2021::puzzle::{self, Answers, Case, Day};
+
+macro_rules! days {
+    ($($day:ident: $path:literal),* $(,)?) => {
+        $(
+            #[path = $path]
+            mod $day;
+            // Each day's main() is only called when the day is run as its own binary
+            const _: fn() -> Result<()> = $day::main;
+        )*
+    }
+}
+
+days! {
+    day01: "01/main.rs", day02: "02/main.rs", day03: "03/main.rs", day04: "04/main.rs",
+    day05: "05/main.rs", day06: "06/main.rs", day07: "07/main.rs", day08: "08/main.rs",
+    day09: "09/main.rs", day10: "10/main.rs", day11: "11/main.rs", day12: "12/main.rs",
+    day13: "13/main.rs", day14: "14/main.rs", day15: "15/main.rs", day16: "16/main.rs",
+    day17: "17/main.rs", day18: "18/main.rs", day19: "19/main.rs", day20: "20/main.rs",
+    day21: "21/main.rs", day22: "22/main.rs", day23: "23/main.rs", day24: "24/main.rs",
+    day25: "25/main.rs",
+}
+
+type Part = fn(&str) -> Result<String>;
+
+// Day 25 only has one part.
+const DAYS: [(u32, Part, Option<Part>); 25] = [
+    (1, day01::part1, Some(day01::part2)),
+    (2, day02::part1, Some(day02::part2)),
+    (3, day03::part1, Some(day03::part2)),
+    (4, day04::part1, Some(day04::part2)),
+    (5, day05::part1, Some(day05::part2)),
+    (6, day06::part1, Some(day06::part2)),
+    (7, day07::part1, Some(day07::part2)),
+    (8, day08::part1, Some(day08::part2)),
+    (9, day09::part1, Some(day09::part2)),
+    (10, day10::part1, Some(day10::part2)),
+    (11, day11::part1, Some(day11::part2)),
+    (12, day12::part1, Some(day12::part2)),
+    (13, day13::part1, Some(day13::part2)),
+    (14, day14::part1, Some(day14::part2)),
+    (15, day15::part1, Some(day15::part2)),
+    (16, day16::part1, Some(day16::part2)),
+    (17, day17::part1, Some(day17::part2)),
+    (18, day18::part1, Some(day18::part2)),
+    (19, day19::part1, Some(day19::part2)),
+    (20, day20::part1, Some(day20::part2)),
+    (21, day21::part1, Some(day21::part2)),
+    (22, day22::part1, Some(day22::part2)),
+    (23, day23::part1, Some(day23::part2)),
+    (24, day24::part1, Some(day24::part2)),
+    (25, day25::part1, None),
+];
+
+struct Options {
+    days: Vec<u32>,
+    examples: bool,
+    repeat: u32,
+    save: bool,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options> {
+    let mut options = Options { days: (1..=25).collect(), examples: false, repeat: 1, save: false };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--examples" => options.examples = true,
+            "--save" => options.save = true,
+            "--repeat" => {
+                let n = args.next().context("--repeat requires a count")?;
+                options.repeat = n.parse().with_context(|| format!("Invalid --repeat `{}`", n))?;
+                if options.repeat == 0 { bail!("--repeat must be positive"); }
+            },
+            flag if flag.starts_with("--") => bail!("Unknown flag {}", flag),
+            days => options.days = puzzle::parse_days(days)?,
+        }
+    }
+    Ok(options)
+}
+
+enum Outcome {
+    Correct(String),
+    Unchecked(String),
+    Wrong { actual: String, expected: String },
+    Failed(String),
+}
+
+impl Outcome {
+    fn cell(&self) -> String {
+        match self {
+            Outcome::Correct(a) | Outcome::Unchecked(a) | Outcome::Wrong { actual: a, .. } => {
+                let mut lines = a.lines();
+                let first = lines.next().unwrap_or_default();
+                if lines.next().is_some() { format!("{}…", first) } else { first.to_string() }
+            },
+            Outcome::Failed(_) => "error".into(),
+        }
+    }
+
+    fn problem(&self) -> Option<String> {
+        match self {
+            Outcome::Wrong { actual, expected } => Some(format!("expected:\n{}\nactual:\n{}", expected, actual)),
+            Outcome::Failed(error) => Some(error.clone()),
+            _ => None,
+        }
+    }
+}
+
+// Runs a part repeat times, returning the outcome of the first run and the average runtime.
+fn run(part: Part, case: &Case, number: u32, repeat: u32) -> (Outcome, Duration) {
+    let start = Instant::now();
+    let result = part(case.input());
+    for _ in 1..repeat {
+        let _ = part(case.input());
+    }
+    let elapsed = start.elapsed() / repeat;
+
+    let outcome = match (result, case.answer(number)) {
+        (Err(e), _) => Outcome::Failed(format!("{:?}", e)),
+        (Ok(actual), None) => Outcome::Unchecked(actual),
+        (Ok(actual), Some(expected)) if actual == expected => Outcome::Correct(actual),
+        (Ok(actual), Some(expected)) => Outcome::Wrong { actual, expected: expected.to_string() },
+    };
+    (outcome, elapsed)
+}
+
+fn main() -> Result<()> {
+    let options = parse_args(std::env::args().skip(1))?;
+
+    println!("| {:>12} | {:>16} | {:>16} | {:>10} | {:>9} |", "Day", "Part 1", "Part 2", "Time", "Status");
+    println!("|{:-<14}|{:-<18}|{:-<18}|{:-<12}|{:-<11}|", "", "", "", "", "");
+    let mut problems = Vec::new();
+    let mut total = Duration::ZERO;
+    for &(number, part1, part2) in DAYS.iter().filter(|(n, _, _)| options.days.contains(n)) {
+        let day = Day::new(number);
+        let mut cases = vec![day.input()?];
+        if options.examples {
+            // Days without examples are fine; only examples with answers can be checked
+            cases.extend(day.examples().unwrap_or_default().into_iter().filter(|e| !e.answers().is_empty()));
+        }
+
+        for case in cases {
+            let is_input = case.name() == "input";
+            let mut outcomes = Vec::new();
+            let mut elapsed = Duration::ZERO;
+            for (n, part) in [(1, Some(part1)), (2, part2)] {
+                let part = match part {
+                    // Examples often only apply to one part
+                    Some(part) if is_input || case.answer(n).is_some() => part,
+                    _ => { outcomes.push(None); continue; },
+                };
+                let (outcome, time) = run(part, &case, n, options.repeat);
+                elapsed += time;
+                if let Some(problem) = outcome.problem() {
+                    problems.push(format!("Day {} {} part {}: {}", number, case.name(), n, problem));
+                }
+                outcomes.push(Some(outcome));
+            }
+            if is_input { total += elapsed; }
+
+            let status = if outcomes.iter().flatten().any(|o| o.problem().is_some()) { "FAIL" }
+                else if outcomes.iter().flatten().any(|o| matches!(o, Outcome::Unchecked(_))) { "unchecked" }
+                else { "ok" };
+            let label = if is_input { number.to_string() } else { format!("{} {}", number, case.name()) };
+            let cells: Vec<_> = outcomes.iter().map(|o| o.as_ref().map(|o| o.cell()).unwrap_or_default()).collect();
+            println!("| {:>12} | {:>16} | {:>16} | {:>10} | {:>9} |",
+                     label, cells[0], cells[1], format!("{:.3?}", elapsed), status);
+
+            if options.save && is_input && case.answers().is_empty() && !outcomes.iter().flatten().any(|o| o.problem().is_some()) {
+                let mut answers = Answers::default();
+                for (n, outcome) in outcomes.iter().enumerate() {
+                    if let Some(Outcome::Unchecked(answer)) = outcome {
+                        answers.insert(n as u32 + 1, answer.as_str());
+                    }
+                }
+                std::fs::write(case.answers_path(), answers.to_string())
+                    .with_context(|| format!("Failed to write {}", case.answers_path().display()))?;
+            }
+        }
+    }
+    println!("| {:>12} | {:>16} | {:>16} | {:>10} | {:>9} |", "Total", "", "", format!("{:.3?}", total), "");
+
+    if !problems.is_empty() {
+        println!();
+        for problem in &problems {
+            println!("{}\n", problem);
+        }
+        bail!("{} part(s) failed", problems.len());
+    }
+    Ok(())
+}
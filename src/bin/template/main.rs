@@ -5,14 +5,20 @@
 use anyhow::Result;
 
 use advent_2021::parsing::*;
+use advent_2021::input;
 
-fn main() -> Result<()> {
-    let input = parse_input(include_str!("input.txt"));
+pub fn main() -> Result<()> {
+    let input = input::read(include_str!("input.txt"))?;
+    let input = parse_input(&input);
     println!("HELLO {}!", some_regex(input)?);
 
     Ok(())
 }
 
+pub fn part1(input: &str) -> Result<String> {
+    some_regex(parse_input(input))
+}
+
 fn parse_input(input: &str) -> &str {
     input.trim()
 }
@@ -26,9 +32,15 @@ fn some_regex(s: &str) -> Result<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_2021::puzzle::this_day;
 
     #[test]
-    fn delete_me() { parse_input(include_str!("example.txt")); }
+    fn examples() {
+        for example in this_day!().examples().unwrap() {
+            let input = parse_input(example.input());
+            example.check(1, some_regex(input).unwrap_or_default());
+        }
+    }
 
     parameterized_test::create!{ delete, n, { assert_eq!(n % 2, 0); } }
     delete! {
@@ -1,15 +1,22 @@
 use std::time::Duration;
-use advent_2021::terminal::{Color, Terminal, TerminalImage, TerminalRender};
+use advent_2021::terminal::{Color, Frame, Heatmap, Palette, Terminal, TerminalImage, TerminalRender};
 
 fn main() {
-    let _drop = Terminal::init();
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    let _drop = if args.first().map(|a| a == "--full-screen").unwrap_or(false) {
+        args.remove(0);
+        Terminal::init_full_screen()
+    } else {
+        Terminal::init()
+    };
 
-    let args = std::env::args().skip(1).collect::<Vec<_>>();
-    match args.get(0) {
+    match args.first() {
         Some(name) => {
             match name.as_str() {
                 "expand" => expand(&args[1..]),
-                "image" => image(),
+                "image" => image(&args[1..]),
+                "frame" => frame(),
+                "heatmap" => heatmap(),
                 "one_line" => one_line(),
                 _ => panic!("Unknown: {}", name),
             }
@@ -19,12 +26,14 @@ fn main() {
 
     fn demo() {
         expand(&[]);
-        image();
+        image(&[]);
+        frame();
+        heatmap();
         one_line();
     }
 
     fn expand(args: &[String]) {
-        let n = args.get(0).map(|a| a.parse().expect("Invalid num")).unwrap_or(50);
+        let n = args.first().map(|a| a.parse().expect("Invalid num")).unwrap_or(50);
         for i in (0..=n).chain((0..n).rev()) {
             let mut print = i.to_string();
             print.push('\n');
@@ -37,30 +46,54 @@ fn main() {
         }
     }
 
-    fn image() {
-        static COLORS: [Color; 7] = [Color::RED, Color::ORANGE, Color::YELLOW, Color::GREEN, Color::CYAN, Color::BLUE, Color::MAGENTA];
-        struct Rainbow {
-            offset: usize,
-        }
-        impl TerminalRender for Rainbow {
-            fn render(&self, _w: usize, _h: usize) -> TerminalImage {
-                let width = 20;
-                let mut pixels = Vec::new();
-                for i in 0..(width-1) { // Notice the image an odd number of pixels tall
-                    for j in 0..width {
-                        let idx = 100 - i - j + self.offset;
-                        pixels.push(COLORS[idx as usize % COLORS.len()]);
-                    }
+    static COLORS: [Color; 7] = [Color::RED, Color::ORANGE, Color::YELLOW, Color::GREEN, Color::CYAN, Color::BLUE, Color::MAGENTA];
+    struct Rainbow {
+        offset: usize,
+    }
+    impl TerminalRender for Rainbow {
+        fn render(&self, _w: usize, _h: usize) -> TerminalImage {
+            let width = 20;
+            let mut pixels = Vec::new();
+            for i in 0..(width-1) { // Notice the image an odd number of pixels tall
+                for j in 0..width {
+                    let idx = 100 - i - j + self.offset;
+                    pixels.push(COLORS[idx % COLORS.len()]);
                 }
-                TerminalImage{ pixels, width, }
             }
+            TerminalImage{ pixels, width, }
         }
+    }
 
+    fn image(args: &[String]) {
+        if let Some(glyphs) = args.first() {
+            Terminal::set_glyphs(glyphs.parse().expect("Invalid glyphs"));
+        }
         for offset in 0..100 {
             Terminal::interactive_render(&Rainbow{offset}, Duration::from_millis(100));
         }
     }
 
+    fn frame() {
+        for offset in 0..100 {
+            Terminal::interactive_frame(|| Frame::stack([
+                Frame::text("Rainbows"),
+                Frame::beside([
+                    Frame::image(Rainbow{offset}.render(0, 0)),
+                    Frame::text(format!("Offset:\n{}", offset)),
+                ]),
+            ]), Duration::from_millis(100));
+        }
+    }
+
+    fn heatmap() {
+        let palette = Palette::viridis(-1.0, 1.0);
+        for t in 0..100 {
+            let grid: Vec<Vec<_>> = (0..20).map(|y| (0..40).map(|x|
+                (x as f64 / 4.0 + t as f64 / 5.0).sin() * (y as f64 / 3.0).cos()).collect()).collect();
+            Terminal::interactive_frame(|| Heatmap::from_grid(&grid, &palette).frame(Terminal::glyphs()), Duration::from_millis(100));
+        }
+    }
+
     fn one_line() {
         for i in (0..=200).rev() {
             Terminal::interactive_display(i, Duration::from_millis(20));
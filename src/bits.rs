@@ -0,0 +1,292 @@
+// Bit-level reading and writing, for puzzles with binary formats.
+//
+// The Order determines both which end of each byte is consumed first and how the bits of a
+// multi-bit value are arranged: with Msb the first bit read is the most significant bit of the
+// value (as in most puzzle formats), with Lsb it's the least significant (as in e.g. DEFLATE).
+use anyhow::{anyhow, bail, ensure, Result};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Order {
+    Msb,
+    Lsb,
+}
+
+impl Order {
+    // The mask of the index'th bit of a byte, in this order
+    fn mask(self, index: usize) -> u8 {
+        match self {
+            Order::Msb => 0x80 >> index,
+            Order::Lsb => 1 << index,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitReader {
+    bytes: Vec<u8>,
+    len: usize,
+    position: usize,
+    order: Order,
+}
+
+impl BitReader {
+    pub fn new(bytes: impl Into<Vec<u8>>, order: Order) -> BitReader {
+        let bytes = bytes.into();
+        let len = bytes.len() * 8;
+        BitReader { bytes, len, position: 0, order }
+    }
+
+    // Each pair of hex digits is a byte; whitespace is ignored. With Msb order a trailing odd digit
+    // is the first four bits of a final half-byte.
+    pub fn from_hex(hex: &str, order: Order) -> Result<BitReader> {
+        let digits = hex.chars().filter(|c| !c.is_whitespace())
+            .map(|c| c.to_digit(16).map(|d| d as u8).ok_or_else(|| anyhow!("Invalid hex digit: {}", c)))
+            .collect::<Result<Vec<_>>>()?;
+        ensure!(order == Order::Msb || digits.len().is_multiple_of(2), "Odd number of hex digits with Lsb order");
+        let bytes = digits.chunks(2).map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0)).collect();
+        Ok(BitReader { bytes, len: digits.len() * 4, position: 0, order })
+    }
+
+    // A string of 0s and 1s, in the order they'll be read; whitespace is ignored.
+    pub fn from_binary(binary: &str, order: Order) -> Result<BitReader> {
+        let mut writer = BitWriter::new(order);
+        for c in binary.chars().filter(|c| !c.is_whitespace()) {
+            match c {
+                '0' => writer.write_bool(false),
+                '1' => writer.write_bool(true),
+                _ => bail!("Invalid binary digit: {}", c),
+            }
+        }
+        Ok(writer.into_reader())
+    }
+
+    pub fn order(&self) -> Order { self.order }
+
+    // The total number of bits, read or not
+    pub fn len(&self) -> usize { self.len }
+
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    // The number of bits read (or skipped) so far
+    pub fn position(&self) -> usize { self.position }
+
+    pub fn remaining(&self) -> usize { self.len - self.position }
+
+    fn bit(&self, index: usize) -> bool {
+        self.bytes[index / 8] & self.order.mask(index % 8) != 0
+    }
+
+    // Returns the next bits bits without consuming them.
+    pub fn peek(&self, bits: usize) -> Result<u64> {
+        ensure!(bits <= 64, "Cannot read {} bits at once", bits);
+        ensure!(bits <= self.remaining(), "Wanted {} bits but only {} remain", bits, self.remaining());
+        let mut value = 0;
+        let mut index = self.position;
+        let mut done = 0;
+        // Consume a byte-aligned chunk at a time
+        while done < bits {
+            let offset = index % 8;
+            let take = (8 - offset).min(bits - done);
+            let byte = self.bytes[index / 8] as u64;
+            let chunk = match self.order {
+                Order::Msb => (byte >> (8 - offset - take)) & ((1 << take) - 1),
+                Order::Lsb => (byte >> offset) & ((1 << take) - 1),
+            };
+            value = match self.order {
+                Order::Msb => if take == 64 { chunk } else { (value << take) | chunk },
+                Order::Lsb => value | (chunk << done),
+            };
+            index += take;
+            done += take;
+        }
+        Ok(value)
+    }
+
+    pub fn read(&mut self, bits: usize) -> Result<u64> {
+        let value = self.peek(bits)?;
+        self.position += bits;
+        Ok(value)
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool> {
+        ensure!(self.remaining() > 0, "No bits remain");
+        let bit = self.bit(self.position);
+        self.position += 1;
+        Ok(bit)
+    }
+
+    pub fn skip(&mut self, bits: usize) -> Result<()> {
+        ensure!(bits <= self.remaining(), "Cannot skip {} bits, only {} remain", bits, self.remaining());
+        self.position += bits;
+        Ok(())
+    }
+
+    // The remaining bits, as 0s and 1s
+    pub fn to_binary(&self) -> String {
+        (self.position..self.len).map(|i| if self.bit(i) { '1' } else { '0' }).collect()
+    }
+
+    // The underlying bytes, including any already read; the final byte may be partially used.
+    pub fn into_bytes(self) -> Vec<u8> { self.bytes }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    len: usize,
+    order: Order,
+}
+
+impl BitWriter {
+    pub fn new(order: Order) -> BitWriter {
+        BitWriter { bytes: Vec::new(), len: 0, order }
+    }
+
+    pub fn order(&self) -> Order { self.order }
+
+    pub fn len(&self) -> usize { self.len }
+
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    pub fn write_bool(&mut self, bit: bool) {
+        if self.len.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().expect("Just pushed") |= self.order.mask(self.len % 8);
+        }
+        self.len += 1;
+    }
+
+    // Writes the low bits bits of value; panics if value doesn't fit.
+    pub fn write(&mut self, bits: usize, value: u64) {
+        assert!(bits <= 64, "Cannot write {} bits at once", bits);
+        assert!(bits == 64 || value >> bits == 0, "{} does not fit in {} bits", value, bits);
+        match self.order {
+            Order::Msb => (0..bits).rev().for_each(|i| self.write_bool(value & (1 << i) != 0)),
+            Order::Lsb => (0..bits).for_each(|i| self.write_bool(value & (1 << i) != 0)),
+        }
+    }
+
+    // Appends the unread bits of another stream.
+    pub fn append(&mut self, mut other: BitReader) {
+        while let Ok(bit) = other.read_bool() {
+            self.write_bool(bit);
+        }
+    }
+
+    // Two digits per byte, including the unused bits (which are 0) of a partial final byte.
+    pub fn to_hex(&self) -> String {
+        self.bytes.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    pub fn to_binary(&self) -> String {
+        self.clone().into_reader().to_binary()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> { self.bytes }
+
+    pub fn into_reader(self) -> BitReader {
+        BitReader { bytes: self.bytes, len: self.len, position: 0, order: self.order }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    parameterized_test::create!{ reads, (order, expected), {
+        // 1010 0011, 1111 0000
+        let mut reader = BitReader::new(vec![0xA3, 0xF0], order);
+        assert_eq!(reader.len(), 16);
+        let values: Vec<_> = [3, 6, 1, 6].iter().map(|&n| reader.read(n).unwrap()).collect();
+        assert_eq!(values, expected);
+        assert_eq!(reader.remaining(), 0);
+        assert!(reader.read(1).is_err());
+    } }
+    reads! {
+        msb: (Order::Msb, [0b101, 0b000111, 0b1, 0b110000]),
+        lsb: (Order::Lsb, [0b011, 0b010100, 0b0, 0b111100]),
+    }
+
+    #[test]
+    fn peek_and_skip() {
+        let mut reader = BitReader::from_hex("F0F", Order::Msb).unwrap();
+        assert_eq!(reader.len(), 12);
+        assert_eq!(reader.peek(6).unwrap(), 0b111100);
+        assert_eq!(reader.position(), 0);
+        reader.skip(2).unwrap();
+        assert_eq!(reader.read(4).unwrap(), 0b1100);
+        assert_eq!(reader.to_binary(), "001111");
+        assert!(reader.skip(7).is_err());
+        assert!(reader.peek(7).is_err());
+        assert_eq!(reader.position(), 6);
+        assert_eq!(reader.peek(0).unwrap(), 0);
+    }
+
+    #[test]
+    fn wide_reads() {
+        let mut reader = BitReader::from_hex("0123456789ABCDEF01", Order::Msb).unwrap();
+        reader.skip(4).unwrap();
+        assert_eq!(reader.read(64).unwrap(), 0x123456789ABCDEF0);
+        assert!(reader.read(65).is_err());
+
+        let mut reader = BitReader::new(vec![0xFF; 9], Order::Lsb);
+        reader.skip(3).unwrap();
+        assert_eq!(reader.read(64).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(BitReader::from_binary("1011 0", Order::Msb).unwrap().into_bytes(), [0b10110000]);
+        assert_eq!(BitReader::from_binary("1011 0", Order::Lsb).unwrap().into_bytes(), [0b00001101]);
+        assert_eq!(BitReader::from_hex("A\n", Order::Msb).unwrap().to_binary(), "1010");
+        assert_eq!(BitReader::from_hex("A1", Order::Lsb).unwrap().to_binary(), "10000101");
+        assert!(BitReader::from_hex("A", Order::Lsb).is_err());
+        assert!(BitReader::from_binary("102", Order::Msb).is_err());
+        assert!(BitReader::from_hex("AG", Order::Msb).is_err());
+    }
+
+    parameterized_test::create!{ round_trip, order, {
+        let mut writer = BitWriter::new(order);
+        writer.write(3, 0b110);
+        writer.write_bool(true);
+        writer.write(64, u64::MAX - 1);
+        writer.write(0, 0);
+        writer.write(7, 42);
+        assert_eq!(writer.len(), 75);
+
+        let mut reader = writer.clone().into_reader();
+        assert_eq!(reader.read(3).unwrap(), 0b110);
+        assert!(reader.read_bool().unwrap());
+        assert_eq!(reader.read(64).unwrap(), u64::MAX - 1);
+        assert_eq!(reader.read(7).unwrap(), 42);
+        assert!(reader.read_bool().is_err());
+
+        let hex = BitReader::from_hex(&writer.to_hex(), order).unwrap();
+        assert_eq!(hex.into_bytes(), writer.clone().into_bytes());
+        let binary = BitReader::from_binary(&writer.to_binary(), order).unwrap();
+        assert_eq!(binary, writer.into_reader());
+    } }
+    round_trip! {
+        msb: Order::Msb,
+        lsb: Order::Lsb,
+    }
+
+    #[test]
+    fn append() {
+        let mut writer = BitWriter::new(Order::Msb);
+        writer.write(2, 0b10);
+        let mut other = BitReader::from_binary("0111", Order::Msb).unwrap();
+        other.skip(1).unwrap();
+        writer.append(other);
+        assert_eq!(writer.to_binary(), "10111");
+        assert_eq!(writer.to_hex(), "B8");
+    }
+
+    #[test]
+    #[should_panic(expected = "8 does not fit in 3 bits")]
+    fn overfull_write() {
+        BitWriter::new(Order::Msb).write(3, 8);
+    }
+}
@@ -10,6 +10,7 @@ mod point {
     use std::str::FromStr;
     use anyhow::{Error, Result};
     use crate::parsing::{static_regex,capture_group,regex_captures};
+    use crate::terminal::Glyphs;
 
     #[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
     pub struct Point {
@@ -80,41 +81,43 @@ mod point {
             out
         }
 
-        // https://en.wikipedia.org/wiki/Braille_Patterns#Identifying,_naming_and_ordering
         pub fn points_to_braille<F: Fn(&Point)->bool>(left_corner: Point, contains: F) -> char {
-            let braille_blank = 0x2800;
-            // in bit order from left-to-right, dot 1 is bit 8, dot 8 is bit 1
-            let dots = [
-                vector(1, 3),
-                vector(0, 3),
-                vector(1, 2),
-                vector(1, 1),
-                vector(1, 0),
-                vector(0, 2),
-                vector(0,1),
-                vector(0, 0),
-            ];
-            let mut braille_parts = 0;
-            for dot in dots {
-                braille_parts <<= 1;
-                if contains(&(left_corner + dot)) {
-                    braille_parts |= 1;
+            let mut pixels = 0;
+            for y in 0..4 {
+                for x in 0..2 {
+                    if contains(&(left_corner + vector(x, y))) {
+                        pixels |= 1 << (y * 2 + x);
+                    }
                 }
             }
-            char::from_u32(braille_parts + braille_blank).expect("Valid Braille char")
+            Glyphs::Braille.glyph(pixels)
+        }
+
+        // Renders the bounding box of the given points using the given Glyphs, with each row
+        // terminated by a newline.
+        pub fn display_point_set_glyphs(points: &HashSet<Point>, glyphs: Glyphs) -> String {
+            Self::display_glyphs(points.iter(), |p| points.contains(p), glyphs)
+        }
+
+        pub fn display_point_map_glyphs(points: &HashMap<Point, bool>, glyphs: Glyphs) -> String {
+            Self::display_glyphs(points.keys(), |p| *points.get(p).unwrap_or(&false), glyphs)
+        }
+
+        fn display_glyphs<'a>(bounds: impl IntoIterator<Item = &'a Point>, contains: impl Fn(&Point) -> bool, glyphs: Glyphs) -> String {
+            match Point::bounding_box(bounds) {
+                Some((min, max)) => {
+                    let (width, height) = ((max.x - min.x + 1) as usize, (max.y - min.y + 1) as usize);
+                    glyphs.render_bits(width, height, |x, y| contains(&(min + vector(x as i32, y as i32))))
+                },
+                None => String::new(),
+            }
         }
 
         pub fn display_point_set_braille(points: &HashSet<Point>) -> String {
             let mut out = String::new();
             out.push_str("\u{001B}[1m");
             if points.is_empty() { return out; }
-            let (min, max) = Point::bounding_box(points).expect("Non-empty");
-            for y in (min.y..=max.y).step_by(4) {
-                for x in (min.x..=max.x).step_by(2) {
-                    out.push(Point::points_to_braille(point(x, y), |p| points.contains(p)));
-                }
-                out.push('\n');
-            }
+            out.push_str(&Self::display_point_set_glyphs(points, Glyphs::Braille));
             out.push_str("\u{001B}[0m");
             out
         }
@@ -123,13 +126,7 @@ mod point {
             let mut out = String::new();
             out.push_str("\u{001B}[1m");
             if points.is_empty() { return out; }
-            let (min, max) = Point::bounding_box(points.keys()).expect("Non-empty");
-            for y in (min.y..=max.y).step_by(4) {
-                for x in (min.x..=max.x).step_by(2) {
-                    out.push(Point::points_to_braille(point(x, y), |p| *points.get(p).unwrap_or(&false)));
-                }
-                out.push('\n');
-            }
+            out.push_str(&Self::display_point_map_glyphs(points, Glyphs::Braille));
             out.push_str("\u{001B}[0m");
             out
         }
@@ -310,6 +307,18 @@ mod point {
             assert_eq!(&Point::display_point_set_braille(&points), "\u{1b}[1m⡈⠢⡀\n⠀⠀⠈\n\u{1b}[0m");
         }
 
+        #[test]
+        fn glyphs_display() {
+            let points: HashSet<_> = [point(0,0), point(1,1), point(2,2), point(3,3)].into_iter().collect();
+            assert_eq!(Point::display_point_set_glyphs(&points, Glyphs::HalfBlock), "▀▄  \n  ▀▄\n");
+            assert_eq!(Point::display_point_set_glyphs(&points, Glyphs::Quadrant), "▚ \n ▚\n");
+            assert_eq!(Point::display_point_set_glyphs(&points, Glyphs::Sextant), "🬈🬏\n 🬁\n");
+            assert_eq!(Point::display_point_set_glyphs(&points, Glyphs::Braille), "⠑⢄\n");
+
+            let map: HashMap<_, _> = points.iter().map(|p| (*p, p.x % 2 == 0)).collect();
+            assert_eq!(Point::display_point_map_glyphs(&map, Glyphs::Quadrant), "▘ \n ▘\n");
+        }
+
         #[test]
         fn in_bounds() {
             let zero_zero = point(0, 0);
@@ -461,3 +470,159 @@ mod vector {
     }
 }
 pub use self::vector::{Vector,vector};
+
+mod svg {
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::path::Path;
+    use anyhow::{Context, Result};
+    use crate::pathfinding::Edge;
+    use crate::terminal::Color;
+    use super::{point, Point};
+
+    // Builds up an SVG drawing where each Point occupies a cell_size square. Unlike the terminal
+    // renderers the output is deterministic (point sets are drawn in sorted order), so it can be
+    // checked in or regenerated for documentation.
+    #[derive(Clone, Debug)]
+    pub struct Svg {
+        cell_size: u32,
+        bounds: Option<(Point, Point)>,
+        elements: Vec<String>,
+    }
+
+    impl Svg {
+        pub fn new(cell_size: u32) -> Svg {
+            assert!(cell_size > 0, "cell_size must be positive");
+            Svg { cell_size, bounds: None, elements: Vec::new() }
+        }
+
+        pub fn points<'a>(mut self, points: impl IntoIterator<Item = &'a Point>, color: Color) -> Svg {
+            let mut points: Vec<_> = points.into_iter().cloned().collect();
+            points.sort_by_key(|p| (p.y, p.x));
+            for p in points {
+                self.cell(p, color);
+            }
+            self
+        }
+
+        // Fills each point with the color returned by the closure, or leaves it empty on None.
+        pub fn point_map<V>(mut self, map: &HashMap<Point, V>, color: impl Fn(&V) -> Option<Color>) -> Svg {
+            let mut points: Vec<_> = map.iter().collect();
+            points.sort_by_key(|(p, _)| (p.y, p.x));
+            for (&p, v) in points {
+                match color(v) {
+                    Some(c) => self.cell(p, c),
+                    None => self.include(p),
+                }
+            }
+            self
+        }
+
+        // Lines (and routes) connect the centers of their endpoints' cells.
+        pub fn line(mut self, from: Point, to: Point, color: Color) -> Svg {
+            self.include(from);
+            self.include(to);
+            let ((x1, y1), (x2, y2)) = (self.center(from), self.center(to));
+            self.elements.push(format!(
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}" stroke-linecap="round"/>"#,
+                x1, y1, x2, y2, color.to_hex(), self.stroke_width()));
+            self
+        }
+
+        pub fn route(mut self, route: &[Edge<Point>], color: Color) -> Svg {
+            if route.is_empty() { return self; }
+            let nodes: Vec<Point> = std::iter::once(*route[0].source())
+                .chain(route.iter().map(|e| *e.dest()))
+                .collect();
+            let coords: Vec<_> = nodes.iter().map(|&p| {
+                self.include(p);
+                let (x, y) = self.center(p);
+                format!("{},{}", x, y)
+            }).collect();
+            self.elements.push(format!(
+                r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+                coords.join(" "), color.to_hex(), self.stroke_width()));
+            self
+        }
+
+        pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+            let path = path.as_ref();
+            std::fs::write(path, self.to_string()).with_context(|| format!("Failed to write {}", path.display()))
+        }
+
+        fn cell(&mut self, p: Point, color: Color) {
+            self.include(p);
+            let size = self.cell_size as i64;
+            self.elements.push(format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                p.x as i64 * size, p.y as i64 * size, size, size, color.to_hex()));
+        }
+
+        fn include(&mut self, p: Point) {
+            self.bounds = Point::bounding_box(self.bounds.iter().flat_map(|(a, b)| [a, b]).chain([&p]));
+        }
+
+        fn center(&self, p: Point) -> (f64, f64) {
+            let size = self.cell_size as f64;
+            ((p.x as f64 + 0.5) * size, (p.y as f64 + 0.5) * size)
+        }
+
+        fn stroke_width(&self) -> f64 {
+            (self.cell_size as f64 / 4.0).max(1.0)
+        }
+    }
+
+    impl fmt::Display for Svg {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let size = self.cell_size as i64;
+            let (min, max) = self.bounds.unwrap_or((point(0, 0), point(-1, -1)));
+            let (width, height) = ((max.x - min.x + 1) as i64 * size, (max.y - min.y + 1) as i64 * size);
+            writeln!(f, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+                     width, height, min.x as i64 * size, min.y as i64 * size, width, height)?;
+            for element in &self.elements {
+                writeln!(f, "  {}", element)?;
+            }
+            writeln!(f, "</svg>")
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn empty() {
+            assert_eq!(Svg::new(5).to_string(),
+                       "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"0\" height=\"0\" viewBox=\"0 0 0 0\">\n</svg>\n");
+        }
+
+        #[test]
+        fn points() {
+            let svg = Svg::new(10).points(&[point(1, 2), point(-1, 0)], Color::RED).to_string();
+            assert_eq!(svg, concat!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"30\" height=\"30\" viewBox=\"-10 0 30 30\">\n",
+                "  <rect x=\"-10\" y=\"0\" width=\"10\" height=\"10\" fill=\"#cd0000\"/>\n",
+                "  <rect x=\"10\" y=\"20\" width=\"10\" height=\"10\" fill=\"#cd0000\"/>\n",
+                "</svg>\n"));
+        }
+
+        #[test]
+        fn point_map() {
+            let map: HashMap<_, _> = [(point(0, 0), true), (point(3, 1), false)].into_iter().collect();
+            let svg = Svg::new(2).point_map(&map, |&v| if v { Some(Color::WHITE) } else { None }).to_string();
+            assert!(svg.contains("width=\"8\" height=\"4\" viewBox=\"0 0 8 4\""), "{}", svg);
+            assert_eq!(svg.matches("<rect").count(), 1);
+            assert!(svg.contains("fill=\"#ffffff\""));
+        }
+
+        #[test]
+        fn lines_and_routes() {
+            let route = vec![Edge::new(1, point(0, 0), point(1, 0)), Edge::new(1, point(1, 0), point(1, 1))];
+            let svg = Svg::new(4).line(point(0, 2), point(2, 2), Color::BLUE).route(&route, Color::GREEN).to_string();
+            assert!(svg.contains(r##"<line x1="2" y1="10" x2="10" y2="10" stroke="#0000ee" stroke-width="1""##), "{}", svg);
+            assert!(svg.contains(r##"<polyline points="2,2 6,2 6,6" fill="none" stroke="#00cd00""##), "{}", svg);
+            assert!(svg.contains("viewBox=\"0 0 12 12\""), "{}", svg);
+        }
+    }
+}
+pub use self::svg::Svg;
@@ -251,3 +251,320 @@ mod vector {
     }
 }
 pub use self::vector::{Vector,vector};
+
+mod export {
+    use std::collections::{BTreeSet, HashMap};
+    use std::fmt::Write;
+    use super::*;
+    use crate::terminal::Color;
+
+    // A constructive solid geometry tree of unit voxels, which can be written out as OpenSCAD
+    // source or as a triangle mesh. Cuboids are inclusive of both corners, like Point::in_bounds.
+    #[derive(Clone, Debug)]
+    pub enum Solid {
+        Cuboid(Point, Point),
+        Voxels(BTreeSet<Point>),
+        Union(Vec<Solid>),
+        Difference(Box<Solid>, Vec<Solid>),
+        // The innermost color applies; uncolored regions are left to the viewer's default.
+        Colored(Color, Box<Solid>),
+    }
+
+    // Whether a unit voxel is in the solid, and if so its color.
+    type Fill = Option<Option<Color>>;
+
+    struct Face {
+        corners: [[i32; 3]; 4], // counter-clockwise when viewed from outside
+        normal: [i32; 3],
+        color: Option<Color>,
+    }
+
+    impl Solid {
+        pub fn empty() -> Solid {
+            Solid::Union(Vec::new())
+        }
+
+        pub fn cuboid(a: Point, b: Point) -> Solid {
+            Solid::Cuboid(
+                point(cmp::min(a.x, b.x), cmp::min(a.y, b.y), cmp::min(a.z, b.z)),
+                point(cmp::max(a.x, b.x), cmp::max(a.y, b.y), cmp::max(a.z, b.z)))
+        }
+
+        pub fn voxels<'a>(points: impl IntoIterator<Item = &'a Point>) -> Solid {
+            Solid::Voxels(points.into_iter().cloned().collect())
+        }
+
+        pub fn union(mut self, other: Solid) -> Solid {
+            if let Solid::Union(parts) = &mut self {
+                parts.push(other);
+                return self;
+            }
+            Solid::Union(vec![self, other])
+        }
+
+        pub fn difference(mut self, other: Solid) -> Solid {
+            if self.is_empty() { return self; }
+            if let Solid::Difference(_, removed) = &mut self {
+                removed.push(other);
+                return self;
+            }
+            Solid::Difference(Box::new(self), vec![other])
+        }
+
+        pub fn colored(self, color: Color) -> Solid {
+            Solid::Colored(color, Box::new(self))
+        }
+
+        fn is_empty(&self) -> bool {
+            matches!(self, Solid::Union(parts) if parts.is_empty())
+        }
+
+        fn fill(&self, p: Point) -> Fill {
+            match self {
+                Solid::Cuboid(min, max) => p.in_bounds(*min, *max).then_some(None),
+                Solid::Voxels(points) => points.contains(&p).then_some(None),
+                Solid::Union(parts) => parts.iter().find_map(|s| s.fill(p)),
+                Solid::Difference(base, removed) =>
+                    base.fill(p).filter(|_| !removed.iter().any(|s| s.fill(p).is_some())),
+                Solid::Colored(color, solid) => solid.fill(p).map(|c| c.or(Some(*color))),
+            }
+        }
+
+        fn bounds(&self, out: &mut Vec<(Point, Point)>) {
+            match self {
+                Solid::Cuboid(min, max) => out.push((*min, *max)),
+                Solid::Voxels(points) => out.extend(points.iter().map(|&p| (p, p))),
+                Solid::Union(parts) => parts.iter().for_each(|s| s.bounds(out)),
+                Solid::Difference(base, removed) => {
+                    base.bounds(out);
+                    removed.iter().for_each(|s| s.bounds(out));
+                },
+                Solid::Colored(_, solid) => solid.bounds(out),
+            }
+        }
+
+        pub fn openscad(&self) -> String {
+            let mut out = String::from(
+                "module ocube(x1, x2, y1, y2, z1, z2) { translate([x1, y1, z1]) cube([x2-x1+1, y2-y1+1, z2-z1+1]); }\n\n");
+            self.write_openscad(&mut out, 0);
+            out
+        }
+
+        fn write_openscad(&self, out: &mut String, depth: usize) {
+            let indent = "  ".repeat(depth);
+            let block = |out: &mut String, name: &str, children: &mut dyn Iterator<Item = &Solid>| {
+                writeln!(out, "{}{} {{", indent, name).expect("String write");
+                children.for_each(|s| s.write_openscad(out, depth + 1));
+                writeln!(out, "{}}}", indent).expect("String write");
+            };
+            match self {
+                Solid::Cuboid(min, max) =>
+                    writeln!(out, "{}ocube({},{}, {},{}, {},{});", indent, min.x, max.x, min.y, max.y, min.z, max.z).expect("String write"),
+                Solid::Voxels(points) => {
+                    let voxels: Vec<_> = points.iter().map(|&p| Solid::Cuboid(p, p)).collect();
+                    block(out, "union()", &mut voxels.iter());
+                },
+                Solid::Union(parts) => block(out, "union()", &mut parts.iter()),
+                Solid::Difference(base, removed) =>
+                    block(out, "difference()", &mut std::iter::once(base.as_ref()).chain(removed)),
+                Solid::Colored(color, solid) =>
+                    block(out, &format!("color(\"{}\")", color.to_hex()), &mut std::iter::once(solid.as_ref())),
+            }
+        }
+
+        // Finds the solid's exposed faces by splitting space along every primitive's boundaries;
+        // each resulting cell is uniformly in or out of the solid, so only cells covered by some
+        // primitive need to be checked. When by_color is set faces are also emitted between
+        // differently-colored cells.
+        fn faces(&self, by_color: bool) -> Vec<Face> {
+            let mut bounds = Vec::new();
+            self.bounds(&mut bounds);
+            let axes: [Vec<i32>; 3] = [0, 1, 2].map(|a| {
+                let mut coords: Vec<_> = bounds.iter()
+                    .flat_map(|(min, max)| [coord(min, a), coord(max, a) + 1])
+                    .collect();
+                coords.sort_unstable();
+                coords.dedup();
+                coords
+            });
+            let index = |a: usize, v: i32| axes[a].binary_search(&v).expect("boundary");
+
+            let mut cells = BTreeSet::new();
+            for (min, max) in &bounds {
+                let [xs, ys, zs] = [0, 1, 2].map(|a| index(a, coord(min, a))..index(a, coord(max, a) + 1));
+                for x in xs {
+                    for y in ys.clone() {
+                        for z in zs.clone() {
+                            cells.insert([x, y, z]);
+                        }
+                    }
+                }
+            }
+
+            let mut fills = HashMap::new();
+            let mut fill = |cell: [usize; 3]| -> Fill {
+                *fills.entry(cell).or_insert_with(|| self.fill(point(axes[0][cell[0]], axes[1][cell[1]], axes[2][cell[2]])))
+            };
+            let mut faces = Vec::new();
+            for cell in cells {
+                let color = match fill(cell) {
+                    Some(color) => color,
+                    None => continue,
+                };
+                let lo = [0, 1, 2].map(|a| axes[a][cell[a]]);
+                let hi = [0, 1, 2].map(|a| axes[a][cell[a] + 1]);
+                for axis in 0..3 {
+                    for positive in [false, true] {
+                        let mut neighbor = cell;
+                        let exposed = if positive {
+                            neighbor[axis] += 1;
+                            neighbor[axis] + 1 >= axes[axis].len() || match fill(neighbor) {
+                                Some(c) => by_color && c != color,
+                                None => true,
+                            }
+                        } else {
+                            cell[axis] == 0 || {
+                                neighbor[axis] -= 1;
+                                match fill(neighbor) {
+                                    Some(c) => by_color && c != color,
+                                    None => true,
+                                }
+                            }
+                        };
+                        if exposed {
+                            faces.push(Face::create(lo, hi, axis, positive, color));
+                        }
+                    }
+                }
+            }
+            faces
+        }
+
+        // An ASCII STL mesh of the solid's surface; colors are ignored.
+        pub fn stl(&self, name: &str) -> String {
+            let mut out = format!("solid {}\n", name);
+            for face in self.faces(false) {
+                let c = face.corners;
+                for triangle in [[c[0], c[1], c[2]], [c[0], c[2], c[3]]] {
+                    let n = face.normal;
+                    writeln!(out, "  facet normal {} {} {}\n    outer loop", n[0], n[1], n[2]).expect("String write");
+                    for v in triangle {
+                        writeln!(out, "      vertex {} {} {}", v[0], v[1], v[2]).expect("String write");
+                    }
+                    out.push_str("    endloop\n  endfacet\n");
+                }
+            }
+            writeln!(out, "endsolid {}", name).expect("String write");
+            out
+        }
+
+        // A Wavefront OBJ mesh of the solid's surface, using quad faces. Colors are written as
+        // per-vertex RGB values, an extension most viewers (e.g. Blender, MeshLab) understand.
+        pub fn obj(&self) -> String {
+            let mut vertices = HashMap::new();
+            let mut out = String::new();
+            let mut faces = String::new();
+            for face in self.faces(true) {
+                let rgb = face.color.map(|c| c.to_rgb());
+                let ids: Vec<_> = face.corners.iter().map(|&v| {
+                    let next = vertices.len() + 1;
+                    *vertices.entry((v, rgb)).or_insert_with(|| {
+                        write!(out, "v {} {} {}", v[0], v[1], v[2]).expect("String write");
+                        if let Some((r, g, b)) = rgb {
+                            write!(out, " {:.3} {:.3} {:.3}", r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0).expect("String write");
+                        }
+                        out.push('\n');
+                        next
+                    })
+                }).collect();
+                writeln!(faces, "f {} {} {} {}", ids[0], ids[1], ids[2], ids[3]).expect("String write");
+            }
+            out + &faces
+        }
+    }
+
+    fn coord(p: &Point, axis: usize) -> i32 {
+        [p.x, p.y, p.z][axis]
+    }
+
+    impl Face {
+        fn create(lo: [i32; 3], hi: [i32; 3], axis: usize, positive: bool, color: Option<Color>) -> Face {
+            // u and v follow axis cyclically, so (u, v) winds counter-clockwise around +axis
+            let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+            let at = |a: i32, b: i32| {
+                let mut corner = [0; 3];
+                corner[axis] = if positive { hi[axis] } else { lo[axis] };
+                corner[u] = a;
+                corner[v] = b;
+                corner
+            };
+            let corners = if positive {
+                [at(lo[u], lo[v]), at(hi[u], lo[v]), at(hi[u], hi[v]), at(lo[u], hi[v])]
+            } else {
+                [at(lo[u], lo[v]), at(lo[u], hi[v]), at(hi[u], hi[v]), at(hi[u], lo[v])]
+            };
+            let mut normal = [0; 3];
+            normal[axis] = if positive { 1 } else { -1 };
+            Face { corners, normal, color }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn openscad() {
+            let solid = Solid::cuboid(point(2, 2, 2), point(0, 0, 0))
+                .union(Solid::voxels(&[point(3, 0, 0)]).colored(Color::RED))
+                .difference(Solid::cuboid(point(1, 1, 1), point(1, 1, 1)));
+            assert_eq!(solid.openscad().lines().skip(2).collect::<Vec<_>>(), [
+                "difference() {",
+                "  union() {",
+                "    ocube(0,2, 0,2, 0,2);",
+                "    color(\"#cd0000\") {",
+                "      union() {",
+                "        ocube(3,3, 0,0, 0,0);",
+                "      }",
+                "    }",
+                "  }",
+                "  ocube(1,1, 1,1, 1,1);",
+                "}",
+            ]);
+        }
+
+        parameterized_test::create!{ face_counts, (solid, expected), {
+            assert_eq!(solid.faces(false).len(), expected);
+        }}
+        face_counts! {
+            empty: (Solid::empty(), 0),
+            cube: (Solid::cuboid(point(0, 0, 0), point(4, 4, 4)), 6),
+            adjacent: (Solid::voxels(&[point(0, 0, 0), point(1, 0, 0)]), 10),
+            separate: (Solid::voxels(&[point(0, 0, 0), point(2, 0, 0)]), 12),
+            carved: (Solid::cuboid(point(0, 0, 0), point(2, 0, 0)).difference(Solid::voxels(&[point(1, 0, 0)])), 12),
+            hollow: (Solid::cuboid(point(0, 0, 0), point(2, 2, 2)).difference(Solid::voxels(&[point(1, 1, 1)])), 6 * 9 + 6),
+            overlapping: (Solid::cuboid(point(0, 0, 0), point(2, 2, 2)).union(Solid::cuboid(point(1, 1, 1), point(3, 3, 3))), 42),
+        }
+
+        #[test]
+        fn stl() {
+            let stl = Solid::voxels(&[point(0, 0, 0)]).stl("cube");
+            assert!(stl.starts_with("solid cube\n  facet normal -1 0 0\n    outer loop\n      vertex 0 0 0\n      vertex 0 0 1\n      vertex 0 1 1\n"), "{}", stl);
+            assert_eq!(stl.matches("facet normal").count(), 12);
+            assert!(stl.ends_with("endsolid cube\n"));
+        }
+
+        #[test]
+        fn obj() {
+            let obj = Solid::cuboid(point(0, 0, 0), point(1, 1, 1)).obj();
+            assert_eq!(obj.lines().filter(|l| l.starts_with("v ")).count(), 8);
+            assert_eq!(obj.lines().filter(|l| l.starts_with("f ")).count(), 6);
+
+            let colored = Solid::voxels(&[point(0, 0, 0)]).colored(Color::WHITE)
+                .union(Solid::voxels(&[point(1, 0, 0)]).colored(Color::BLACK)).obj();
+            assert_eq!(colored.lines().filter(|l| l.starts_with("f ")).count(), 12);
+            assert!(colored.starts_with("v 0 0 0 1.000 1.000 1.000\n"), "{}", colored);
+        }
+    }
+}
+pub use self::export::Solid;
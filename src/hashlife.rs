@@ -0,0 +1,463 @@
+// Gosper's Hashlife algorithm for two-state, Moore-neighborhood (life-like) automata. The universe
+// is a quadtree whose identical subtrees are shared, and the future of each subtree is memoized,
+// so patterns with repetition in space and time can be advanced by billions of generations.
+//
+// References:
+// https://en.wikipedia.org/wiki/Hashlife
+// https://www.drdobbs.com/jvm/an-algorithm-for-compressing-space-and-t/184406478
+use std::collections::HashMap;
+use anyhow::{bail, ensure, Context, Result};
+
+use crate::automaton::{Neighborhood, Rule, Totalistic};
+use crate::euclid::{point, Point};
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+// The root is kept below this level so that cell coordinates, and the offsets between them, fit in
+// an i64. This limits step_pow2() to 2^(MAX_LEVEL - 3) generations at a time.
+const MAX_LEVEL: u8 = 62;
+
+// A square of 2^level cells; level 0 nodes are single cells.
+#[derive(Copy, Clone, Debug)]
+struct Node {
+    level: u8,
+    // nw, ne, sw, se
+    children: [NodeId; 4],
+    population: u64,
+}
+
+pub struct Hashlife {
+    rule: Totalistic,
+    nodes: Vec<Node>,
+    interned: HashMap<[NodeId; 4], NodeId>,
+    // The center of a node advanced 2^j generations, keyed by the node and j
+    results: HashMap<(NodeId, u8), NodeId>,
+    empty: Vec<NodeId>,
+    root: NodeId,
+    // The coordinates of the root's top-left cell
+    origin: (i64, i64),
+    generation: u64,
+}
+
+impl Hashlife {
+    pub fn new(rule: &Totalistic, cells: impl IntoIterator<Item = Point>) -> Result<Hashlife> {
+        ensure!(rule.neighborhood() == Neighborhood::Moore, "Hashlife requires a Moore neighborhood");
+        ensure!(!rule.next(&false, &[false; 8]), "Rules where empty space comes alive (B0) are not supported");
+        let leaf = |population| Node { level: 0, children: [DEAD; 4], population };
+        let mut life = Hashlife {
+            rule: rule.clone(),
+            nodes: vec![leaf(0), leaf(1)],
+            interned: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            origin: (0, 0),
+            generation: 0,
+        };
+
+        let cells: Vec<_> = cells.into_iter().collect();
+        let (min, max) = Point::bounding_box(&cells).unwrap_or((point(0, 0), point(0, 0)));
+        let size = (max.x - min.x).max(max.y - min.y) as u64 + 1;
+        let level = (u64::BITS - (size - 1).leading_zeros()).max(3) as u8;
+        life.root = life.empty(level);
+        life.origin = (min.x as i64, min.y as i64);
+        for cell in cells {
+            life.root = life.set(life.root, (cell.x - min.x) as u64, (cell.y - min.y) as u64);
+        }
+        Ok(life)
+    }
+
+    // Reads a pattern in RLE format, using its rule (or Conway's, if it doesn't specify one)
+    pub fn from_rle(rle: &str) -> Result<Hashlife> {
+        let (cells, rule) = parse_rle(rle)?;
+        Hashlife::new(&rule.unwrap_or_else(Totalistic::conway), cells)
+    }
+
+    pub fn rule(&self) -> &Totalistic { &self.rule }
+
+    pub fn generation(&self) -> u64 { self.generation }
+
+    pub fn population(&self) -> u64 { self.nodes[self.root as usize].population }
+
+    fn node(&self, id: NodeId) -> Node { self.nodes[id as usize] }
+
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.interned.get(&children) {
+            return id;
+        }
+        let level = self.node(children[0]).level + 1;
+        let population = children.iter().map(|&c| self.node(c).population).sum();
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node { level, children, population });
+        self.interned.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let e = *self.empty.last().expect("Non-empty");
+            let next = self.join([e; 4]);
+            self.empty.push(next);
+        }
+        self.empty[level as usize]
+    }
+
+    // Returns the node with the cell at (x, y), relative to its top-left corner, alive
+    fn set(&mut self, id: NodeId, x: u64, y: u64) -> NodeId {
+        let node = self.node(id);
+        if node.level == 0 {
+            return ALIVE;
+        }
+        let half = 1 << (node.level - 1);
+        let quadrant = (y >= half) as usize * 2 + (x >= half) as usize;
+        let mut children = node.children;
+        children[quadrant] = self.set(children[quadrant], x % half, y % half);
+        self.join(children)
+    }
+
+    // The central square half the size of the given node, which must be at least level 2
+    fn center(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.node(id).children.map(|c| self.node(c).children);
+        self.join([nw[3], ne[2], sw[1], se[0]])
+    }
+
+    // The nine overlapping squares, each half the size of the given node, in reading order
+    fn subsquares(&mut self, id: NodeId) -> [NodeId; 9] {
+        let [nw, ne, sw, se] = self.node(id).children;
+        let [a, b, c, d] = [nw, ne, sw, se].map(|c| self.node(c).children);
+        [
+            nw, self.join([a[1], b[0], a[3], b[2]]), ne,
+            self.join([a[2], a[3], c[0], c[1]]), self.center(id), self.join([b[2], b[3], d[0], d[1]]),
+            sw, self.join([c[1], d[0], c[3], d[2]]), se,
+        ]
+    }
+
+    // The center of a level-2 node (4x4 cells) after one generation
+    fn step_base(&mut self, id: NodeId) -> NodeId {
+        let mut cells = [[false; 4]; 4];
+        for (quadrant, &child) in self.node(id).children.iter().enumerate() {
+            for (i, &leaf) in self.node(child).children.iter().enumerate() {
+                let (x, y) = ((quadrant % 2) * 2 + i % 2, (quadrant / 2) * 2 + i / 2);
+                cells[y][x] = leaf == ALIVE;
+            }
+        }
+        let offsets = Neighborhood::Moore.offsets();
+        let next = [(1, 1), (2, 1), (1, 2), (2, 2)].map(|(x, y): (i32, i32)| {
+            let neighbors: Vec<_> = offsets.iter().map(|v| cells[(y + v.y) as usize][(x + v.x) as usize]).collect();
+            if self.rule.next(&cells[y as usize][x as usize], &neighbors) { ALIVE } else { DEAD }
+        });
+        self.join(next)
+    }
+
+    // The center of the given node, of level k, after 2^j generations, where j <= k - 2
+    fn step(&mut self, id: NodeId, j: u8) -> NodeId {
+        let node = self.node(id);
+        debug_assert!(node.level >= 2 && j <= node.level - 2);
+        if node.population == 0 {
+            return self.empty(node.level - 1);
+        }
+        if let Some(&result) = self.results.get(&(id, j)) {
+            return result;
+        }
+
+        let result = if node.level == 2 {
+            self.step_base(id)
+        } else {
+            // Advance the nine subsquares (or, if stepping less than the maximum amount, just take
+            // their centers), then advance the four squares they combine into for the remainder.
+            let full = j == node.level - 2;
+            let inner = if full { node.level - 3 } else { j };
+            let r = self.subsquares(id).map(|s| if full { self.step(s, inner) } else { self.center(s) });
+            let quadrants = [
+                [r[0], r[1], r[3], r[4]], [r[1], r[2], r[4], r[5]],
+                [r[3], r[4], r[6], r[7]], [r[4], r[5], r[7], r[8]],
+            ];
+            let children = quadrants.map(|q| { let q = self.join(q); self.step(q, inner) });
+            self.join(children)
+        };
+        self.results.insert((id, j), result);
+        result
+    }
+
+    // Surrounds the root with empty space, doubling its size
+    fn expand(&mut self) {
+        let root = self.node(self.root);
+        let e = self.empty(root.level - 1);
+        let [nw, ne, sw, se] = root.children;
+        let children = [[e, e, e, nw], [e, e, ne, e], [e, sw, e, e], [se, e, e, e]].map(|c| self.join(c));
+        self.root = self.join(children);
+        let shift = 1i64 << (root.level - 1);
+        self.origin = (self.origin.0 - shift, self.origin.1 - shift);
+    }
+
+    // Advances 2^j generations, where j <= MAX_LEVEL - 3. Fails if the pattern grows too large
+    // for the universe's coordinates, in which case it is not advanced.
+    pub fn step_pow2(&mut self, j: u8) -> Result<()> {
+        ensure!(j <= MAX_LEVEL - 3, "Cannot advance 2^{} generations at once", j);
+        let generation = self.generation.checked_add(1 << j).context("Too many generations")?;
+        // Live cells move at most one cell per generation, so as long as the pattern lies within
+        // the center quarter of a large enough root the result (the root's center half) holds it.
+        loop {
+            let level = self.node(self.root).level;
+            if level >= j + 3 {
+                let center = self.center(self.root);
+                let inner = self.center(center);
+                if self.node(inner).population == self.population() { break; }
+            }
+            ensure!(level < MAX_LEVEL, "Pattern is too large to advance");
+            self.expand();
+        }
+        let level = self.node(self.root).level;
+        self.root = self.step(self.root, j);
+        let shift = 1i64 << (level - 2);
+        self.origin = (self.origin.0 + shift, self.origin.1 + shift);
+        self.generation = generation;
+        Ok(())
+    }
+
+    // Advances the given number of generations, which must be less than 2^(MAX_LEVEL - 2). If the
+    // pattern grows too large partway through it's left at the last generation reached.
+    pub fn advance(&mut self, generations: u64) -> Result<()> {
+        ensure!(generations >> (MAX_LEVEL - 2) == 0, "Cannot advance {} generations", generations);
+        ensure!(self.generation.checked_add(generations).is_some(), "Too many generations");
+        for j in 0..MAX_LEVEL - 2 {
+            if generations & (1 << j) != 0 {
+                self.step_pow2(j)?;
+            }
+        }
+        Ok(())
+    }
+
+    // The live cells, which must fit within Point's coordinates
+    pub fn cells(&self) -> Result<Vec<Point>> {
+        let mut out = Vec::new();
+        let mut stack = vec![(self.root, self.origin)];
+        while let Some((id, (x, y))) = stack.pop() {
+            let node = self.node(id);
+            if node.population == 0 { continue; }
+            if node.level == 0 {
+                let (px, py) = (i32::try_from(x), i32::try_from(y));
+                out.push(point(px.context("Pattern is too large")?, py.context("Pattern is too large")?));
+                continue;
+            }
+            let half = 1i64 << (node.level - 1);
+            for (i, &child) in node.children.iter().enumerate() {
+                stack.push((child, (x + (i % 2) as i64 * half, y + (i / 2) as i64 * half)));
+            }
+        }
+        out.sort_by_key(|p| (p.y, p.x));
+        Ok(out)
+    }
+
+    pub fn to_rle(&self) -> Result<String> {
+        Ok(to_rle(&self.cells()?, &self.rule))
+    }
+}
+
+// Parses a pattern in Golly's run-length encoded format, returning its live cells and its rule,
+// if the header specifies one. See https://conwaylife.com/wiki/Run_Length_Encoded
+pub fn parse_rle(rle: &str) -> Result<(Vec<Point>, Option<Totalistic>)> {
+    let mut rule = None;
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    let mut count = String::new();
+    for line in rle.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('x') {
+            for (key, value) in line.split(',').filter_map(|f| f.split_once('=')) {
+                if key.trim() == "rule" {
+                    rule = Some(value.trim().parse().with_context(|| format!("Invalid rule: {}", value.trim()))?);
+                }
+            }
+            continue;
+        }
+        for c in line.chars() {
+            if c.is_ascii_digit() {
+                count.push(c);
+                continue;
+            }
+            let n: i32 = if count.is_empty() { 1 } else { count.parse().context("Invalid run length")? };
+            count.clear();
+            match c {
+                'b' | '.' => x += n,
+                'o' | 'A' => {
+                    cells.extend((x..x + n).map(|x| point(x, y)));
+                    x += n;
+                },
+                '$' => { x = 0; y += n; },
+                '!' => return Ok((cells, rule)),
+                _ => bail!("Unexpected character in RLE: {:?}", c),
+            }
+        }
+    }
+    bail!("RLE pattern is missing its terminating '!'")
+}
+
+// Encodes the cells in RLE format, with lines of at most 70 characters.
+pub fn to_rle(cells: &[Point], rule: &Totalistic) -> String {
+    let (min, max) = match Point::bounding_box(cells) {
+        Some(bounds) => bounds,
+        None => return format!("x = 0, y = 0, rule = {}\n!\n", rule),
+    };
+    // Only the live cells are visited, since patterns can be extremely sparse
+    let mut sorted: Vec<_> = cells.to_vec();
+    sorted.sort_by_key(|p| (p.y, p.x));
+    sorted.dedup();
+    let run = |n: i32, c: char| if n == 1 { c.to_string() } else { format!("{}{}", n, c) };
+
+    let mut tokens = Vec::new();
+    let mut cursor = min;
+    let mut cells = sorted.into_iter().peekable();
+    while let Some(start) = cells.next() {
+        if start.y > cursor.y {
+            tokens.push(run(start.y - cursor.y, '$'));
+            cursor = point(min.x, start.y);
+        }
+        if start.x > cursor.x {
+            tokens.push(run(start.x - cursor.x, 'b'));
+        }
+        let mut end = start.x + 1;
+        while cells.next_if_eq(&point(end, start.y)).is_some() {
+            end += 1;
+        }
+        tokens.push(run(end - start.x, 'o'));
+        cursor.x = end;
+    }
+    tokens.push("!".into());
+
+    let mut out = format!("x = {}, y = {}, rule = {}\n", max.x - min.x + 1, max.y - min.y + 1, rule);
+    let mut line = String::new();
+    for token in tokens {
+        if line.len() + token.len() > 70 {
+            out.push_str(&line);
+            out.push('\n');
+            line.clear();
+        }
+        line.push_str(&token);
+    }
+    out.push_str(&line);
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::{Automaton, Edges};
+    use crate::euclid::vector;
+
+    static GLIDER: &str = "#N Glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n";
+
+    fn automaton_cells(cells: &[Point], rule: &Totalistic, generations: usize) -> Vec<Point> {
+        let mut automaton = Automaton::new(cells.iter().map(|&p| (p, true)).collect(), false, Edges::Infinite).unwrap();
+        for _ in 0..generations {
+            automaton.step(rule);
+        }
+        let mut cells: Vec<_> = automaton.iter().filter(|(_, &s)| s).map(|(p, _)| p).collect();
+        cells.sort_by_key(|p| (p.y, p.x));
+        cells
+    }
+
+    #[test]
+    fn rle() {
+        let (cells, rule) = parse_rle(GLIDER).unwrap();
+        assert_eq!(cells, [point(1, 0), point(2, 1), point(0, 2), point(1, 2), point(2, 2)]);
+        assert_eq!(rule, Some(Totalistic::conway()));
+        assert_eq!(to_rle(&cells, &Totalistic::conway()), GLIDER.lines().skip(1).collect::<Vec<_>>().join("\n") + "\n");
+
+        let (cells, rule) = parse_rle("2o2$\n3bo!").unwrap();
+        assert_eq!(cells, [point(0, 0), point(1, 0), point(3, 2)]);
+        assert_eq!(rule, None);
+        assert_eq!(to_rle(&cells, &"B36/S23".parse().unwrap()), "x = 4, y = 3, rule = B36/S23\n2o2$3bo!\n");
+        assert_eq!(to_rle(&[], &Totalistic::conway()), "x = 0, y = 0, rule = B3/S23\n!\n");
+
+        let wide: Vec<_> = (0..100).map(|x| point(x * 2, 0)).collect();
+        let encoded = to_rle(&wide, &Totalistic::conway());
+        assert!(encoded.lines().all(|l| l.len() <= 70));
+        assert_eq!(parse_rle(&encoded).unwrap().0, wide);
+
+        assert!(parse_rle("bo$2bo").is_err());
+        assert!(parse_rle("bo$2bq!").is_err());
+        assert!(parse_rle("x = 1, y = 1, rule = B3/Q\no!").is_err());
+    }
+
+    #[test]
+    fn glider() {
+        let mut life = Hashlife::from_rle(GLIDER).unwrap();
+        let start = life.cells().unwrap();
+        life.advance(4).unwrap();
+        assert_eq!(life.cells().unwrap(), start.iter().map(|p| p + vector(1, 1)).collect::<Vec<_>>());
+
+        life.advance((1 << 20) - 4).unwrap();
+        assert_eq!(life.generation(), 1 << 20);
+        assert_eq!(life.population(), 5);
+        let shift = 1 << 18;
+        assert_eq!(life.cells().unwrap(), start.iter().map(|p| p + vector(shift, shift)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn limits() {
+        let mut life = Hashlife::from_rle(GLIDER).unwrap();
+        assert!(life.advance(u64::MAX).is_err());
+        assert!(life.step_pow2(MAX_LEVEL - 2).is_err());
+        assert_eq!(life.generation(), 0);
+
+        life.advance((1 << (MAX_LEVEL - 2)) - 1).unwrap();
+        assert_eq!(life.population(), 5);
+        // The glider has long since left i32's range
+        assert!(life.cells().is_err());
+    }
+
+    #[test]
+    fn r_pentomino() {
+        // Stabilizes after 1103 generations with 116 cells, including six escaping gliders
+        let mut life = Hashlife::from_rle("b2o$2o$bo!").unwrap();
+        life.advance(1103).unwrap();
+        assert_eq!(life.population(), 116);
+        life.advance(1 << 30).unwrap();
+        assert_eq!(life.population(), 116);
+        // The gliders are now hundreds of millions of cells apart
+        let (cells, _) = parse_rle(&life.to_rle().unwrap()).unwrap();
+        assert_eq!(cells.len(), 116);
+    }
+
+    // Compares against the (much slower) generic automaton on random soups, advancing by irregular
+    // amounts to exercise the partial-speed steps.
+    parameterized_test::create!{ matches_automaton, rule, {
+        let rule: Totalistic = rule.parse().unwrap();
+        let rng = fastrand::Rng::with_seed(20);
+        for _ in 0..3 {
+            let cells: Vec<_> = (0..16).flat_map(|y| (0..16).map(move |x| point(x, y))).filter(|_| rng.bool()).collect();
+            let mut life = Hashlife::new(&rule, cells.iter().copied()).unwrap();
+            life.advance(37).unwrap();
+            assert_eq!(life.cells().unwrap(), automaton_cells(&cells, &rule, 37));
+            life.advance(26).unwrap();
+            assert_eq!(life.cells().unwrap(), automaton_cells(&cells, &rule, 63));
+        }
+    } }
+    matches_automaton! {
+        conway: "B3/S23",
+        highlife: "B36/S23",
+        seeds: "B2/S",
+    }
+
+    #[test]
+    fn unsupported_rules() {
+        assert!(Hashlife::new(&"B03/S23".parse().unwrap(), []).is_err());
+        let von_neumann = Totalistic::new(Neighborhood::VonNeumann, &[1], &[1]).unwrap();
+        assert!(Hashlife::new(&von_neumann, []).is_err());
+    }
+
+    #[test]
+    fn empty() {
+        let mut life = Hashlife::new(&Totalistic::conway(), []).unwrap();
+        life.advance(1000).unwrap();
+        assert_eq!(life.population(), 0);
+        assert_eq!(life.cells().unwrap(), []);
+    }
+}
@@ -0,0 +1,95 @@
+use std::borrow::Cow;
+use std::io::Read;
+use anyhow::{bail, Context, Result};
+
+// Environment variable consulted when no --input flag is passed.
+pub const INPUT_VAR: &str = "ADVENT_INPUT";
+
+#[derive(Debug, PartialEq, Eq)]
+enum Source {
+    Embedded,
+    Stdin,
+    File(String),
+}
+
+impl Source {
+    fn from(path: &str) -> Source {
+        if path == "-" { Source::Stdin } else { Source::File(path.to_string()) }
+    }
+}
+
+// Splits an --input PATH (or --input=PATH) flag out of the arguments, returning its value and the
+// remaining arguments.
+fn split_args(args: impl IntoIterator<Item = String>) -> Result<(Option<String>, Vec<String>)> {
+    let mut input = None;
+    let mut rest = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = if arg == "--input" {
+            args.next().context("--input requires a path, or - for stdin")?
+        } else if let Some(value) = arg.strip_prefix("--input=") {
+            value.to_string()
+        } else {
+            rest.push(arg);
+            continue;
+        };
+        if input.is_some() { bail!("--input passed more than once"); }
+        input = Some(value);
+    }
+    Ok((input, rest))
+}
+
+fn resolve(flag: Option<String>, env: Option<String>) -> Source {
+    flag.or(env).filter(|p| !p.is_empty()).map(|p| Source::from(&p)).unwrap_or(Source::Embedded)
+}
+
+// Returns the puzzle input to use: the file passed via --input PATH (- reads stdin), otherwise the
+// file named by $ADVENT_INPUT (with the same syntax), otherwise the embedded default, which is
+// generally include_str!("input.txt").
+pub fn read(embedded: &'static str) -> Result<Cow<'static, str>> {
+    let (flag, _) = split_args(std::env::args().skip(1))?;
+    match resolve(flag, std::env::var(INPUT_VAR).ok()) {
+        Source::Embedded => Ok(Cow::Borrowed(embedded)),
+        Source::Stdin => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input).context("Failed to read stdin")?;
+            Ok(Cow::Owned(input))
+        },
+        Source::File(path) => {
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path)).map(Cow::Owned)
+        },
+    }
+}
+
+// The command line arguments (excluding the program name) without any --input flag, for days that
+// accept their own arguments.
+pub fn args() -> Vec<String> {
+    split_args(std::env::args().skip(1)).map(|(_, rest)| rest).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(args: &[&str]) -> Result<(Option<String>, Vec<String>)> {
+        split_args(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn flags() {
+        assert_eq!(split(&["stl"]).unwrap(), (None, vec!["stl".into()]));
+        assert_eq!(split(&["--input", "in.txt", "stl"]).unwrap(), (Some("in.txt".into()), vec!["stl".into()]));
+        assert_eq!(split(&["stl", "--input=-"]).unwrap(), (Some("-".into()), vec!["stl".into()]));
+        assert!(split(&["--input"]).is_err());
+        assert!(split(&["--input", "a", "--input", "b"]).is_err());
+    }
+
+    #[test]
+    fn sources() {
+        assert_eq!(resolve(None, None), Source::Embedded);
+        assert_eq!(resolve(None, Some("".into())), Source::Embedded);
+        assert_eq!(resolve(None, Some("-".into())), Source::Stdin);
+        assert_eq!(resolve(Some("a.txt".into()), Some("b.txt".into())), Source::File("a.txt".into()));
+        assert_eq!(resolve(None, Some("b.txt".into())), Source::File("b.txt".into()));
+    }
+}
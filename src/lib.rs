@@ -2,8 +2,13 @@ extern crate lazy_static;
 extern crate regex;
 extern crate anyhow;
 
+pub mod automaton;
+pub mod bits;
 pub mod euclid3d;
 pub mod euclid;
+pub mod hashlife;
+pub mod input;
 pub mod parsing;
 pub mod pathfinding;
+pub mod puzzle;
 pub mod terminal;
@@ -1,4 +1,9 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+use crate::euclid::{point, Point};
 use regex::{Captures, Regex};
 
 #[macro_export]
@@ -19,6 +24,503 @@ pub fn capture_group<'a>(captures: &'a Captures, group: usize) -> &'a str {
         .ok_or_else(|| format!("Invalid capture group {} for {:?}", group, captures)).unwrap().as_str()
 }
 
+// Parses the named group, reporting the group and the offending text if it's missing or invalid.
+pub fn named_group<T>(captures: &Captures, name: &str) -> Result<T> where T: FromStr, T::Err: Display {
+    let text = captures.name(name)
+        .ok_or_else(|| anyhow!("No `{}` group matched in `{}`", name, capture_group(captures, 0)))?
+        .as_str();
+    text.parse().map_err(|e| anyhow!("Invalid {} `{}`: {}", name, text, e))
+}
+
+// Declares a struct along with a FromStr impl that matches the pattern and populates each field
+// from the named group of the same name, e.g.:
+//
+//   pattern_struct! {
+//       #[pattern(r"(?P<name>\w+) is (?P<age>\d+)")]
+//       #[derive(Debug)]
+//       struct Person { name: String, age: u32 }
+//   }
+#[macro_export]
+macro_rules! pattern_struct {
+  (#[pattern($pattern:literal)] $(#[$meta:meta])* $vis:vis struct $name:ident {
+    $($fvis:vis $field:ident : $type:ty),* $(,)?
+  }) => {
+    $(#[$meta])* $vis struct $name { $($fvis $field: $type),* }
+
+    impl std::str::FromStr for $name {
+      type Err = anyhow::Error;
+
+      fn from_str(s: &str) -> anyhow::Result<Self> {
+        let caps = $crate::parsing::regex_captures($crate::static_regex!($pattern), s)?;
+        Ok($name { $($field: $crate::parsing::named_group(&caps, stringify!($field))?),* })
+      }
+    }
+  }
+}
+pub use pattern_struct;
+
+// A failure to parse part of the input, reporting where it happened (1-based) and the line's text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}, column {}: {}\n  {}\n  {}^",
+               self.line, self.column, self.message, self.text, " ".repeat(self.column - 1))
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// A block of input lines, which remembers where it started so errors can report positions in the
+// original input.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Section<'a> {
+    text: &'a str,
+    first_line: usize,
+}
+
+impl<'a> Section<'a> {
+    pub fn new(text: &'a str) -> Section<'a> {
+        Section { text, first_line: 1 }
+    }
+
+    pub fn text(&self) -> &'a str { self.text }
+
+    // Each line of the section along with its line number in the original input.
+    pub fn lines(&self) -> impl Iterator<Item = (usize, &'a str)> {
+        let first_line = self.first_line;
+        self.text.lines().enumerate().map(move |(i, l)| (first_line + i, l))
+    }
+
+    pub fn error(line: usize, column: usize, text: &str, message: impl Display) -> anyhow::Error {
+        ParseError { line, column, text: text.to_string(), message: message.to_string() }.into()
+    }
+
+    // Parses the whole section as one value; errors point at its first line.
+    pub fn parse<T>(&self) -> Result<T> where T: FromStr, T::Err: Display {
+        self.text.parse().map_err(|e| Section::error(
+            self.first_line, 1, self.text.lines().next().unwrap_or(""), e))
+    }
+
+    pub fn parse_lines<T>(&self) -> Result<Vec<T>> where T: FromStr, T::Err: Display {
+        self.lines()
+            .map(|(n, l)| l.parse().map_err(|e| Section::error(n, 1, l, e)))
+            .collect()
+    }
+
+    // Parses comma-separated values, ignoring whitespace around each value.
+    pub fn parse_csv<T>(&self) -> Result<Vec<T>> where T: FromStr, T::Err: Display {
+        self.parse_tokens(|line| line.split(',').map(str::trim).collect())
+    }
+
+    // Parses whitespace-separated values, across all lines of the section.
+    pub fn parse_whitespace<T>(&self) -> Result<Vec<T>> where T: FromStr, T::Err: Display {
+        self.parse_tokens(|line| line.split_whitespace().collect())
+    }
+
+    // The tokens must be slices of the line they came from, so their column can be recovered.
+    fn parse_tokens<T>(&self, tokenize: impl Fn(&str) -> Vec<&str>) -> Result<Vec<T>> where T: FromStr, T::Err: Display {
+        let mut ret = Vec::new();
+        for (n, line) in self.lines().filter(|(_, l)| !l.trim().is_empty()) {
+            for token in tokenize(line) {
+                ret.push(token.parse().map_err(|e| {
+                    let offset = token.as_ptr() as usize - line.as_ptr() as usize;
+                    Section::error(n, line[..offset].chars().count() + 1, line, e)
+                })?);
+            }
+        }
+        Ok(ret)
+    }
+
+    // Parses each character of the section, with the top-left character at the origin.
+    pub fn parse_grid<T>(&self, parse: impl Fn(char) -> Result<T>) -> Result<HashMap<Point, T>> {
+        let mut ret = HashMap::new();
+        for (y, (n, line)) in self.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let value = parse(c).map_err(|e| Section::error(n, x + 1, line, e))?;
+                ret.insert(point(x as i32, y as i32), value);
+            }
+        }
+        Ok(ret)
+    }
+}
+
+// Splits the input into blocks separated by one or more blank lines.
+pub fn sections(input: &str) -> Vec<Section<'_>> {
+    let mut ret = Vec::new();
+    let mut current: Option<(usize, usize)> = None; // (byte offset, line number)
+    let (mut offset, mut end) = (0, 0);
+    for (i, line) in input.split_inclusive('\n').enumerate() {
+        let content = line.trim_end_matches(['\n', '\r']);
+        if content.trim().is_empty() {
+            if let Some((start, first_line)) = current.take() {
+                ret.push(Section { text: &input[start..end], first_line });
+            }
+        } else {
+            current.get_or_insert((offset, i + 1));
+            end = offset + content.len();
+        }
+        offset += line.len();
+    }
+    if let Some((start, first_line)) = current {
+        ret.push(Section { text: &input[start..end], first_line });
+    }
+    ret
+}
+
+pub fn parse_lines<T>(input: &str) -> Result<Vec<T>> where T: FromStr, T::Err: Display {
+    Section::new(input).parse_lines()
+}
+
+pub fn parse_csv<T>(input: &str) -> Result<Vec<T>> where T: FromStr, T::Err: Display {
+    Section::new(input).parse_csv()
+}
+
+pub fn parse_whitespace<T>(input: &str) -> Result<Vec<T>> where T: FromStr, T::Err: Display {
+    Section::new(input).parse_whitespace()
+}
+
+pub fn parse_grid<T>(input: &str, parse: impl Fn(char) -> Result<T>) -> Result<HashMap<Point, T>> {
+    Section::new(input).parse_grid(parse)
+}
+
+// Composable parsers for formats regular expressions can't handle, such as nested brackets. Each
+// parser consumes a prefix of its input and returns the value along with the unconsumed rest;
+// Parser::parse() requires the whole input be consumed and reports failures as a ParseError.
+pub mod combinator {
+    use std::cell::OnceCell;
+    use std::cmp::Ordering;
+    use std::fmt::Display;
+    use std::rc::{Rc, Weak};
+    use std::str::FromStr;
+    use super::ParseError;
+
+    // Where parsing failed, tracked as the length of the input remaining at that point, and what
+    // was expected there.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Failure {
+        remaining: usize,
+        expected: Vec<String>,
+    }
+
+    impl Failure {
+        pub fn new(input: &str, expected: impl Display) -> Failure {
+            Failure { remaining: input.len(), expected: vec![expected.to_string()] }
+        }
+
+        // Keeps whichever failure got further, merging the expectations of failures at the same
+        // point, so alternatives report the most relevant error.
+        fn or(mut self, other: Failure) -> Failure {
+            match self.remaining.cmp(&other.remaining) {
+                Ordering::Less => self,
+                Ordering::Greater => other,
+                Ordering::Equal => {
+                    for e in other.expected {
+                        if !self.expected.contains(&e) { self.expected.push(e); }
+                    }
+                    self
+                },
+            }
+        }
+
+        fn into_error(self, input: &str) -> ParseError {
+            let offset = input.len() - self.remaining;
+            let line_start = input[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+            let found = input[offset..].chars().next()
+                .map(|c| format!("{:?}", c))
+                .unwrap_or_else(|| "end of input".into());
+            let expected = match self.expected.split_last() {
+                Some((last, [])) => last.clone(),
+                Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+                None => "nothing".into(),
+            };
+            ParseError {
+                line: input[..offset].matches('\n').count() + 1,
+                column: input[line_start..offset].chars().count() + 1,
+                text: input[line_start..].lines().next().unwrap_or("").to_string(),
+                message: format!("expected {}, found {}", expected, found),
+            }
+        }
+    }
+
+    pub type Step<'a, T> = Result<(T, &'a str), Failure>;
+
+    pub trait Parser<T> {
+        fn parse_partial<'a>(&self, input: &'a str) -> Step<'a, T>;
+
+        fn parse(&self, input: &str) -> anyhow::Result<T> {
+            self.parse_partial(input)
+                .and_then(|(value, rest)|
+                    if rest.is_empty() { Ok(value) } else { Err(Failure::new(rest, "end of input")) })
+                .map_err(|f| f.into_error(input).into())
+        }
+
+        fn map<U>(self, f: impl Fn(T) -> U) -> impl Parser<U> where Self: Sized {
+            parser(move |input| self.parse_partial(input).map(|(v, rest)| (f(v), rest)))
+        }
+    }
+
+    // Any function or closure of the right shape is a Parser, which allows grammars to be written
+    // as ordinary (possibly recursive) functions.
+    impl<T, F> Parser<T> for F where F: Fn(&str) -> Step<'_, T> {
+        fn parse_partial<'a>(&self, input: &'a str) -> Step<'a, T> {
+            self(input)
+        }
+    }
+
+    // Closures only infer the right lifetimes when passed somewhere expecting a Parser.
+    fn parser<T, F>(f: F) -> F where F: Fn(&str) -> Step<'_, T> { f }
+
+    pub fn literal(lit: &'static str) -> impl Parser<&'static str> {
+        parser(move |input| match input.strip_prefix(lit) {
+            Some(rest) => Ok((lit, rest)),
+            None => Err(Failure::new(input, format!("`{}`", lit))),
+        })
+    }
+
+    // An optionally-negative decimal integer.
+    pub fn int<T>() -> impl Parser<T> where T: FromStr, T::Err: Display {
+        parser(|input| {
+            let digits = input.strip_prefix('-').unwrap_or(input);
+            let len = input.len() - digits.len() + digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len());
+            if len == input.len() - digits.len() {
+                return Err(Failure::new(input, "integer"));
+            }
+            let value = input[..len].parse().map_err(|e| Failure::new(input, format!("integer ({})", e)))?;
+            Ok((value, &input[len..]))
+        })
+    }
+
+    pub fn optional<T>(p: impl Parser<T>) -> impl Parser<Option<T>> {
+        parser(move |input| Ok(match p.parse_partial(input) {
+            Ok((v, rest)) => (Some(v), rest),
+            Err(_) => (None, input),
+        }))
+    }
+
+    // Zero or more repetitions, stopping at the first failure (or if p stops consuming input).
+    pub fn many<T>(p: impl Parser<T>) -> impl Parser<Vec<T>> {
+        parser(move |mut input| {
+            let mut ret = Vec::new();
+            while let Ok((v, rest)) = p.parse_partial(input) {
+                if rest.len() == input.len() { break; }
+                ret.push(v);
+                input = rest;
+            }
+            Ok((ret, input))
+        })
+    }
+
+    pub fn many1<T>(p: impl Parser<T>) -> impl Parser<Vec<T>> {
+        let p = Rc::new(p);
+        let rest = many(Rc::clone(&p));
+        parser(move |input| {
+            let (first, input) = p.parse_partial(input)?;
+            let (mut ret, input) = rest.parse_partial(input)?;
+            ret.insert(0, first);
+            Ok((ret, input))
+        })
+    }
+
+    // Zero or more values separated by sep; a separator must be followed by another value.
+    pub fn sep_by<T, S>(p: impl Parser<T>, sep: impl Parser<S>) -> impl Parser<Vec<T>> {
+        parser(move |input| {
+            let mut ret = Vec::new();
+            let mut input = match p.parse_partial(input) {
+                Ok((v, rest)) => { ret.push(v); rest },
+                Err(_) => return Ok((ret, input)),
+            };
+            while let Ok((_, rest)) = sep.parse_partial(input) {
+                let (v, rest) = p.parse_partial(rest)?;
+                ret.push(v);
+                input = rest;
+            }
+            Ok((ret, input))
+        })
+    }
+
+    pub fn delimited<O, T, C>(open: impl Parser<O>, p: impl Parser<T>, close: impl Parser<C>) -> impl Parser<T> {
+        parser(move |input| {
+            let (_, input) = open.parse_partial(input)?;
+            let (v, input) = p.parse_partial(input)?;
+            let (_, input) = close.parse_partial(input)?;
+            Ok((v, input))
+        })
+    }
+
+    impl<T, P: Parser<T>> Parser<T> for Rc<P> {
+        fn parse_partial<'a>(&self, input: &'a str) -> Step<'a, T> {
+            self.as_ref().parse_partial(input)
+        }
+    }
+
+    // Implemented for tuples of parsers, see seq().
+    pub trait Sequence<T> {
+        fn parse_sequence<'a>(&self, input: &'a str) -> Step<'a, T>;
+    }
+
+    // Implemented for tuples of parsers, see alt().
+    pub trait Alternatives<T> {
+        fn parse_alternatives<'a>(&self, input: &'a str) -> Step<'a, T>;
+    }
+
+    macro_rules! tuple_impls {
+        ($($P:ident $T:ident $v:ident $i:tt),+) => {
+            impl<$($T, $P: Parser<$T>),+> Sequence<($($T,)+)> for ($($P,)+) {
+                fn parse_sequence<'a>(&self, input: &'a str) -> Step<'a, ($($T,)+)> {
+                    $(let ($v, input) = self.$i.parse_partial(input)?;)+
+                    Ok((($($v,)+), input))
+                }
+            }
+
+            impl<T, $($P: Parser<T>),+> Alternatives<T> for ($($P,)+) {
+                fn parse_alternatives<'a>(&self, input: &'a str) -> Step<'a, T> {
+                    let mut failure: Option<Failure> = None;
+                    $(
+                        match self.$i.parse_partial(input) {
+                            Ok(result) => return Ok(result),
+                            Err(f) => failure = Some(match failure { Some(prev) => prev.or(f), None => f }),
+                        }
+                    )+
+                    Err(failure.expect("non-empty"))
+                }
+            }
+        }
+    }
+    tuple_impls!(P0 T0 v0 0, P1 T1 v1 1);
+    tuple_impls!(P0 T0 v0 0, P1 T1 v1 1, P2 T2 v2 2);
+    tuple_impls!(P0 T0 v0 0, P1 T1 v1 1, P2 T2 v2 2, P3 T3 v3 3);
+    tuple_impls!(P0 T0 v0 0, P1 T1 v1 1, P2 T2 v2 2, P3 T3 v3 3, P4 T4 v4 4);
+    tuple_impls!(P0 T0 v0 0, P1 T1 v1 1, P2 T2 v2 2, P3 T3 v3 3, P4 T4 v4 4, P5 T5 v5 5);
+
+    // Runs each parser in turn, returning a tuple of their values.
+    pub fn seq<T>(parsers: impl Sequence<T>) -> impl Parser<T> {
+        parser(move |input| parsers.parse_sequence(input))
+    }
+
+    // Returns the value of the first parser to succeed.
+    pub fn alt<T>(parsers: impl Alternatives<T>) -> impl Parser<T> {
+        parser(move |input| parsers.parse_alternatives(input))
+    }
+
+    type Slot<T> = OnceCell<Box<dyn Parser<T>>>;
+
+    // A parser that can refer to itself, see recursive().
+    pub struct Recursive<T> {
+        parser: Rc<Slot<T>>,
+    }
+
+    // The self-reference handed to recursive()'s builder; it's only valid while the Recursive
+    // parser it came from is alive.
+    pub struct RecursiveRef<T> {
+        parser: Weak<Slot<T>>,
+    }
+
+    impl<T> Clone for RecursiveRef<T> {
+        fn clone(&self) -> Self { RecursiveRef { parser: Weak::clone(&self.parser) } }
+    }
+
+    // Builds a parser for a recursive grammar, e.g. a list of ints or nested lists:
+    //   recursive(|list| delimited(literal("["), sep_by(alt((int().map(..), list.map(..))), literal(",")), literal("]")))
+    pub fn recursive<T: 'static, P: Parser<T> + 'static>(build: impl FnOnce(RecursiveRef<T>) -> P) -> Recursive<T> {
+        let slot = Rc::new(OnceCell::new());
+        let parser = build(RecursiveRef { parser: Rc::downgrade(&slot) });
+        if slot.set(Box::new(parser) as Box<dyn Parser<T>>).is_err() {
+            unreachable!("slot is only set once");
+        }
+        Recursive { parser: slot }
+    }
+
+    impl<T> Parser<T> for Recursive<T> {
+        fn parse_partial<'a>(&self, input: &'a str) -> Step<'a, T> {
+            self.parser.get().expect("initialized by recursive()").parse_partial(input)
+        }
+    }
+
+    impl<T> Parser<T> for RecursiveRef<T> {
+        fn parse_partial<'a>(&self, input: &'a str) -> Step<'a, T> {
+            self.parser.upgrade().expect("Recursive parser was dropped")
+                .get().expect("Recursive parser used while it was being built")
+                .parse_partial(input)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[derive(Debug, PartialEq)]
+        enum Tree {
+            Leaf(i32),
+            Pair(Box<Tree>, Box<Tree>),
+        }
+
+        fn tree() -> Recursive<Tree> {
+            recursive(|tree| alt((
+                int().map(Tree::Leaf),
+                delimited(literal("["), seq((tree.clone(), literal(","), tree)), literal("]"))
+                    .map(|(l, _, r)| Tree::Pair(Box::new(l), Box::new(r))),
+            )))
+        }
+
+        fn error(result: anyhow::Result<impl std::fmt::Debug>) -> ParseError {
+            result.expect_err("Should fail").downcast::<ParseError>().expect("Should be a ParseError")
+        }
+
+        #[test]
+        fn primitives() {
+            assert_eq!(literal("ab").parse_partial("abc"), Ok(("ab", "c")));
+            assert_eq!(int::<i32>().parse_partial("-12,3"), Ok((-12, ",3")));
+            assert_eq!(int::<u8>().parse_partial("-").unwrap_err(), Failure::new("-", "integer"));
+            assert!(error(int::<u8>().parse("300")).message.contains("too large"));
+            assert_eq!(seq((int::<i32>(), literal("x"), int::<i32>())).parse("3x4").unwrap(), (3, "x", 4));
+        }
+
+        #[test]
+        fn repetition() {
+            let list = delimited(literal("("), sep_by(int::<i32>(), literal(" ")), literal(")"));
+            assert_eq!(list.parse("(1 2 3)").unwrap(), [1, 2, 3]);
+            assert_eq!(list.parse("()").unwrap(), []);
+            assert_eq!(error(list.parse("(1 2 )")).message, "expected integer, found ')'");
+            assert_eq!(many(literal("a")).parse_partial("aab"), Ok((vec!["a", "a"], "b")));
+            assert_eq!(many1(literal("a")).parse_partial("b").unwrap_err(), Failure::new("b", "`a`"));
+            assert_eq!(optional(literal("-")).parse_partial("5"), Ok((None, "5")));
+        }
+
+        #[test]
+        fn nested() {
+            use Tree::*;
+            assert_eq!(tree().parse("[[1,2],[[3,4],5]]").unwrap(),
+                       Pair(Box::new(Pair(Box::new(Leaf(1)), Box::new(Leaf(2)))),
+                            Box::new(Pair(Box::new(Pair(Box::new(Leaf(3)), Box::new(Leaf(4)))), Box::new(Leaf(5))))));
+        }
+
+        parameterized_test::create!{ nested_errors, (input, line, column, message), {
+            let err = error(tree().parse(input));
+            assert_eq!((err.line, err.column, err.message.as_str()), (line, column, message));
+        }}
+        nested_errors! {
+            alternatives: ("[1,x]", 1, 4, "expected integer or `[`, found 'x'"),
+            unclosed: ("[[1,2],3", 1, 9, "expected `]`, found end of input"),
+            trailing: ("[1,2]]", 1, 6, "expected end of input, found ']'"),
+        }
+
+        #[test]
+        fn multiline() {
+            let trees = sep_by(tree(), literal("\n"));
+            assert_eq!(trees.parse("1\n[2,3]").unwrap().len(), 2);
+            let err = error(trees.parse("[1,2]\n[3;4]"));
+            assert_eq!((err.line, err.column, err.text.as_str()), (2, 3, "[3;4]"));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -38,4 +540,75 @@ mod tests {
         let err = caps.expect_err("Should not match").to_string();
         assert!(err.contains("did not match"), "Was: {}", err);
     }
+
+    pattern_struct! {
+        #[pattern(r"(?P<name>\w+) is (?P<age>\d+)(?: and likes (?P<hobby>\w+))?")]
+        #[derive(Debug, PartialEq)]
+        struct Person { name: String, age: u32, hobby: String }
+    }
+
+    #[test]
+    fn pattern() {
+        assert_eq!("Alice is 30 and likes chess".parse::<Person>().unwrap(),
+                   Person { name: "Alice".into(), age: 30, hobby: "chess".into() });
+    }
+
+    parameterized_test::create!{ pattern_errors, (input, expected), {
+        let err = input.parse::<Person>().expect_err("Should fail").to_string();
+        assert!(err.contains(expected), "Was: {}", err);
+    }}
+    pattern_errors! {
+        no_match: ("Bob", "did not match"),
+        invalid: ("Bob is 999999999999 and likes golf", "Invalid age `999999999999`"),
+        missing: ("Bob is 40", "No `hobby` group matched in `Bob is 40`"),
+    }
+
+    #[test]
+    fn split_sections() {
+        let input = "a\nb\n\n\nc\r\n\nd\n";
+        let sections: Vec<_> = sections(input).iter().map(|s| (s.text(), s.first_line)).collect();
+        assert_eq!(sections, [("a\nb", 1), ("c", 5), ("d", 7)]);
+    }
+
+    fn position(err: anyhow::Error) -> (usize, usize, String) {
+        let err = err.downcast::<ParseError>().expect("Should be a ParseError");
+        (err.line, err.column, err.text)
+    }
+
+    #[test]
+    fn lines() {
+        assert_eq!(parse_lines::<i32>("1\n-2\n3").unwrap(), [1, -2, 3]);
+        let input = "1\n2\n\n3\nfour\n";
+        assert_eq!(position(sections(input)[1].parse_lines::<i32>().unwrap_err()), (5, 1, "four".into()));
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(parse_csv::<u32>("3,4, 3,1,2\n").unwrap(), [3, 4, 3, 1, 2]);
+        let err = parse_csv::<u32>("1,2,  x,4").unwrap_err();
+        assert_eq!(err.to_string(), "Line 1, column 7: invalid digit found in string\n  1,2,  x,4\n        ^");
+        assert_eq!(position(err), (1, 7, "1,2,  x,4".into()));
+    }
+
+    #[test]
+    fn whitespace() {
+        assert_eq!(parse_whitespace::<u32>(" 1  2\n3 4 ").unwrap(), [1, 2, 3, 4]);
+        assert_eq!(position(parse_whitespace::<u32>("1 2\n 3 -4").unwrap_err()), (2, 4, " 3 -4".into()));
+    }
+
+    #[test]
+    fn grid() {
+        let grid = parse_grid("12\n34", |c| c.to_digit(10).context("not a digit")).unwrap();
+        assert_eq!(grid.len(), 4);
+        assert_eq!(grid[&point(0, 1)], 3);
+        let input = "123\n\n45\n6x\n";
+        let err = sections(input)[1].parse_grid(|c| c.to_digit(10).context("not a digit")).unwrap_err();
+        assert_eq!(position(err), (4, 2, "6x".into()));
+    }
+
+    #[test]
+    fn whole_section() {
+        let err = sections("1\n\nabc\ndef").last().unwrap().parse::<i32>().unwrap_err();
+        assert_eq!(position(err), (3, 1, "abc".into()));
+    }
 }
@@ -0,0 +1,285 @@
+// Locates the files belonging to each day, which live alongside its main.rs in src/bin/NN/:
+//
+//   input.txt            the puzzle input
+//   example.txt          example inputs; numbered (example1.txt, example2.txt, ...) if there are
+//                        several
+//   *.answers            optional expected answers for the .txt file of the same name, one
+//                        "partN: answer" per line, with newlines in an answer escaped as \n
+//   example1-SUFFIX.txt  additional fixtures for an example, such as intermediate states
+//
+// Files are read at runtime relative to the crate root, so this is intended for tests and tools
+// run from a checkout rather than for the day binaries themselves.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use anyhow::{anyhow, bail, ensure, Context, Error, Result};
+
+// The Day for the binary being compiled, e.g. in a day's tests.
+#[macro_export]
+macro_rules! this_day {
+  () => { $crate::puzzle::Day::named(env!("CARGO_BIN_NAME")) }
+}
+pub use this_day;
+
+#[derive(Clone, Debug)]
+pub struct Day {
+    dir: PathBuf,
+}
+
+impl Day {
+    pub fn new(day: u32) -> Day {
+        Day::named(&format!("{:02}", day))
+    }
+
+    pub fn named(name: &str) -> Day {
+        Day { dir: Path::new(env!("CARGO_MANIFEST_DIR")).join("src/bin").join(name) }
+    }
+
+    pub fn dir(&self) -> &Path { &self.dir }
+
+    pub fn input(&self) -> Result<Case> {
+        Case::load(&self.dir, "input")
+    }
+
+    // All examples, in numeric order; it's an error for a day to have none.
+    pub fn examples(&self) -> Result<Vec<Case>> {
+        let mut names = Vec::new();
+        for entry in self.dir.read_dir().with_context(|| format!("Failed to list {}", self.dir.display()))? {
+            let file_name = entry?.file_name();
+            let name = match file_name.to_str().and_then(|f| f.strip_suffix(".txt")) {
+                Some(name) => name,
+                None => continue,
+            };
+            if let Some(number) = name.strip_prefix("example") {
+                if number.is_empty() {
+                    names.push((0, name.to_string()));
+                } else if let Ok(n) = number.parse::<u32>() {
+                    names.push((n, name.to_string()));
+                }
+            }
+        }
+        ensure!(!names.is_empty(), "No examples in {}", self.dir.display());
+        names.sort();
+        names.iter().map(|(_, name)| Case::load(&self.dir, name)).collect()
+    }
+
+    pub fn example(&self, name: &str) -> Result<Case> {
+        Case::load(&self.dir, name)
+    }
+}
+
+// An input file along with any expected answers recorded for it.
+#[derive(Clone, Debug)]
+pub struct Case {
+    name: String,
+    dir: PathBuf,
+    input: String,
+    answers: Answers,
+}
+
+impl Case {
+    fn load(dir: &Path, name: &str) -> Result<Case> {
+        let path = dir.join(format!("{}.txt", name));
+        let input = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let answers_path = dir.join(format!("{}.answers", name));
+        let answers = if answers_path.exists() {
+            std::fs::read_to_string(&answers_path)?.parse()
+                .with_context(|| format!("Invalid answers in {}", answers_path.display()))?
+        } else {
+            Answers::default()
+        };
+        Ok(Case { name: name.to_string(), dir: dir.to_path_buf(), input, answers })
+    }
+
+    pub fn name(&self) -> &str { &self.name }
+
+    pub fn input(&self) -> &str { &self.input }
+
+    pub fn answers(&self) -> &Answers { &self.answers }
+
+    // Where answers for this case are (or would be) recorded
+    pub fn answers_path(&self) -> PathBuf { self.dir.join(format!("{}.answers", self.name)) }
+
+    pub fn answer(&self, part: u32) -> Option<&str> { self.answers.get(part) }
+
+    // Reads a related file, NAME-SUFFIX.txt
+    pub fn fixture(&self, suffix: &str) -> Result<String> {
+        let path = self.dir.join(format!("{}-{}.txt", self.name, suffix));
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    // Panics if an answer is recorded for this part and doesn't match; returns false if there is
+    // no recorded answer.
+    pub fn check(&self, part: u32, actual: impl Display) -> bool {
+        match self.answer(part) {
+            Some(expected) => {
+                assert_eq!(actual.to_string(), expected, "Wrong answer for part {} of {}", part, self.name);
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+// Expected answers, keyed by part, parsed from lines like "part1: 1234". Blank lines and lines
+// starting with # are ignored. Multi-line answers are stored on one line, with \n and \\ escapes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Answers {
+    parts: BTreeMap<u32, String>,
+}
+
+impl Answers {
+    pub fn get(&self, part: u32) -> Option<&str> {
+        self.parts.get(&part).map(|a| a.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool { self.parts.is_empty() }
+
+    pub fn insert(&mut self, part: u32, answer: impl Into<String>) {
+        self.parts.insert(part, answer.into());
+    }
+}
+
+fn unescape(answer: &str) -> Result<String> {
+    let mut ret = String::new();
+    let mut chars = answer.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            ret.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => ret.push('\n'),
+            Some('\\') => ret.push('\\'),
+            other => bail!("Invalid escape `\\{}`", other.map(String::from).unwrap_or_default()),
+        }
+    }
+    Ok(ret)
+}
+
+impl Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (part, answer) in &self.parts {
+            writeln!(f, "part{}: {}", part, answer.replace('\\', "\\\\").replace('\n', "\\n"))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Answers {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = BTreeMap::new();
+        for (i, line) in s.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let (key, answer) = line.split_once(':').ok_or_else(|| anyhow!("Line {}: expected `partN: answer`", i))?;
+            let part = key.trim().strip_prefix("part").and_then(|n| n.parse().ok())
+                .ok_or_else(|| anyhow!("Line {}: invalid part `{}`", i, key))?;
+            let answer = unescape(answer.trim()).with_context(|| format!("Line {}", i))?;
+            if parts.insert(part, answer).is_some() {
+                bail!("Line {}: duplicate answer for part {}", i, part);
+            }
+        }
+        Ok(Answers { parts })
+    }
+}
+
+// Parses a selection of days such as "1..5,15,23", with inclusive ranges.
+pub fn parse_days(spec: &str) -> Result<Vec<u32>> {
+    let mut days = Vec::new();
+    for part in spec.split(',').map(|p| p.trim()) {
+        let parse = |d: &str| -> Result<u32> {
+            let day = d.trim().parse().with_context(|| format!("Invalid day `{}`", d))?;
+            ensure!((1..=25).contains(&day), "Day {} out of range", day);
+            Ok(day)
+        };
+        match part.split_once("..") {
+            Some((start, end)) => {
+                let (start, end) = (parse(start)?, parse(end)?);
+                ensure!(start <= end, "Empty range `{}`", part);
+                days.extend(start..=end);
+            },
+            None => days.push(parse(part)?),
+        }
+    }
+    days.sort_unstable();
+    days.dedup();
+    Ok(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_answers() {
+        let answers: Answers = "# comment\npart1: 42\n\npart2:  abc def \n".parse().unwrap();
+        assert_eq!(answers.get(1), Some("42"));
+        assert_eq!(answers.get(2), Some("abc def"));
+        assert_eq!(answers.get(3), None);
+
+        assert!("42".parse::<Answers>().is_err());
+        assert!("one: 42".parse::<Answers>().is_err());
+        assert!("part1: 1\npart1: 2".parse::<Answers>().is_err());
+        assert!("part1: a\\tb".parse::<Answers>().is_err());
+    }
+
+    #[test]
+    fn escaped_answers() {
+        let mut answers = Answers::default();
+        answers.insert(2, "a\\b\nc");
+        answers.insert(1, "1");
+        let written = answers.to_string();
+        assert_eq!(written, "part1: 1\npart2: a\\\\b\\nc\n");
+        assert_eq!(written.parse::<Answers>().unwrap(), answers);
+    }
+
+    parameterized_test::create!{ days, (spec, expected), {
+        assert_eq!(parse_days(spec).unwrap(), expected);
+    } }
+    days! {
+        single: ("7", vec![7]),
+        list: ("15,3", vec![3, 15]),
+        range: ("1..3", vec![1, 2, 3]),
+        mixed: ("23, 1..2,2", vec![1, 2, 23]),
+    }
+
+    #[test]
+    fn bad_days() {
+        assert!(parse_days("").is_err());
+        assert!(parse_days("0").is_err());
+        assert!(parse_days("26").is_err());
+        assert!(parse_days("5..3").is_err());
+        assert!(parse_days("a..3").is_err());
+    }
+
+    #[test]
+    fn discover() {
+        let examples = Day::new(12).examples().unwrap();
+        let names: Vec<_> = examples.iter().map(|e| e.name()).collect();
+        assert_eq!(names, ["example1", "example2", "example3"]);
+        assert_eq!(examples[0].answer(1), Some("10"));
+        assert!(examples[2].check(2, 3509));
+        assert!(!examples[2].check(3, 0));
+
+        assert!(Day::new(12).input().unwrap().input().starts_with(|c: char| c.is_alphabetic()));
+        assert!(Day::named("nonexistent").examples().is_err());
+    }
+
+    #[test]
+    fn fixtures() {
+        let example = Day::new(11).example("example1").unwrap();
+        assert_eq!(example.answer(1), Some("1656"));
+        assert_eq!(example.fixture("10").unwrap().lines().count(), 10);
+        assert!(example.fixture("missing").is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Wrong answer for part 1 of example1")]
+    fn wrong_answer() {
+        Day::new(12).example("example1").unwrap().check(1, 11);
+    }
+}
@@ -43,7 +43,7 @@ fn append_formatting_off(out: &mut String) {
     out.push_str("\x1B[0m");
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Color {
     BLACK, RED, GREEN, YELLOW, BLUE, MAGENTA, CYAN, GREY,
     WHITE,
@@ -56,6 +56,70 @@ impl Color {
     pub const BROWN: Color = Color::C256(94);
 
     pub fn bg(&self) -> BgColor { BgColor{ color: *self } }
+
+    // Approximates the color as RGB, using xterm's default palette for the named colors.
+    pub fn to_rgb(&self) -> (u8, u8, u8) {
+        static SYSTEM: [(u8, u8, u8); 16] = [
+            (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+            (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+            (127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0),
+            (92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255)];
+        static CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+        match self {
+            Color::BLACK => SYSTEM[0],
+            Color::RED => SYSTEM[1],
+            Color::GREEN => SYSTEM[2],
+            Color::YELLOW => SYSTEM[3],
+            Color::BLUE => SYSTEM[4],
+            Color::MAGENTA => SYSTEM[5],
+            Color::CYAN => SYSTEM[6],
+            Color::GREY => SYSTEM[7],
+            Color::WHITE => SYSTEM[15],
+            Color::C256(code @ 0..=15) => SYSTEM[*code as usize],
+            Color::C256(code @ 16..=231) => {
+                let code = (code - 16) as usize;
+                (CUBE[code / 36], CUBE[code / 6 % 6], CUBE[code % 6])
+            },
+            Color::C256(code) => { let v = 8 + 10 * (code - 232); (v, v, v) },
+            Color::GREYSCALE(f) => { let v = (f * 255.0).round() as u8; (v, v, v) },
+        }
+    }
+
+    // CSS-style "#rrggbb", for exporting to other formats.
+    pub fn to_hex(&self) -> String {
+        let (r, g, b) = self.to_rgb();
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+
+    // Finds the closest color in the 256-color palette. Black and white are the corners of the
+    // color cube, other greys map to GREYSCALE.
+    pub fn from_rgb(r: u8, g: u8, b: u8) -> Color {
+        match (r, g, b) {
+            (0, 0, 0) => return Color::C256(16),
+            (255, 255, 255) => return Color::C256(231),
+            _ if r == g && g == b => return Color::GREYSCALE(r as f32 / 255.0),
+            _ => {},
+        }
+        fn cube_index(v: u8) -> u8 {
+            if v < 48 { 0 } else if v < 115 { 1 } else { (v - 35) / 40 }
+        }
+        Color::C256(16 + 36 * cube_index(r) + 6 * cube_index(g) + cube_index(b))
+    }
+
+    // Perceived brightness, between 0 and 1.
+    pub fn luminance(&self) -> f32 {
+        let (r, g, b) = self.to_rgb();
+        (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32) / 255.0
+    }
+
+    // Wraps text in the escape sequences to print it in this color.
+    pub fn paint(&self, text: impl std::fmt::Display) -> String {
+        let mut out = String::new();
+        self.append_escape(&mut out);
+        out.push_str(&text.to_string());
+        append_formatting_off(&mut out);
+        out
+    }
 }
 
 impl FormattingCode for Color {
@@ -73,7 +137,7 @@ impl FormattingCode for Color {
             Color::C256(code) => out.push_str(&format!("38;5;{}", code)),
             Color::GREYSCALE(f) => {
                 assert!((0.0..=1.0).contains(f), "Greyscale value must be between 0 and 1");
-                out.push_str(&format!("38;5;{}", (f * 24.0).round() as u32 + 232));
+                out.push_str(&format!("38;5;{}", (f * 23.0).round() as u32 + 232));
                 // could instead use 24bit colors:
                 // out.push_str(&format!("38;2;{n};{n};{n}", n=(f * 255.0).round() as u32));
             },
@@ -100,34 +164,187 @@ impl FormattingCode for BgColor {
             Color::C256(code) => out.push_str(&format!("48;5;{}", code)),
             Color::GREYSCALE(f) => {
                 assert!((0.0..=1.0).contains(f), "Greyscale value must be between 0 and 1");
-                out.push_str(&format!("48;5;{}", (f * 24.0).round() as u32 + 232));
+                out.push_str(&format!("48;5;{}", (f * 23.0).round() as u32 + 232));
             },
         }
     }
 }
 
 pub trait TerminalRender {
-    fn render(&self, width_hint: usize, height_hint: usize) -> TerminalImage;
+    // The hints are measured in pixels, not terminal cells: how many pixels fit on screen with the
+    // current Glyphs, e.g. twice as many rows as there are lines with the default HalfBlock glyphs.
+    fn render(&self, pixel_width: usize, pixel_height: usize) -> TerminalImage;
+
+    // Images that may be larger than the terminal can return a Viewport describing how they should
+    // be scaled and which area should be visible. By default images are simply truncated.
+    fn viewport(&self) -> Option<Viewport> { None }
+}
+
+// How a block of pixels is combined into one when downscaling an image.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pooling {
+    // Blend the colors together
+    Average,
+    // Keep the brightest color, which preserves sparse details such as lone pixels or thin paths
+    Max,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Scale {
+    // Shrink the image by a fixed factor in each dimension; 1 leaves the image unchanged
+    Fixed(usize),
+    // Shrink the image just enough to fit in the available space
+    Fit,
+}
+
+// Describes the portion of a TerminalImage to display. If the (scaled) image is still larger than
+// the terminal the visible window is centered on the focus, if any, and position indicators are
+// drawn along the bottom and right edges showing where the window lies within the full image.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Viewport {
+    pub scale: Scale,
+    pub pooling: Pooling,
+    // (x, y) pixel coordinates in the unscaled image
+    pub focus: Option<(usize, usize)>,
+}
+
+impl Viewport {
+    pub fn follow(x: usize, y: usize) -> Viewport {
+        Viewport { scale: Scale::Fixed(1), pooling: Pooling::Average, focus: Some((x, y)) }
+    }
+
+    pub fn fit(pooling: Pooling) -> Viewport {
+        Viewport { scale: Scale::Fit, pooling, focus: None }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct TerminalImage {
     pub pixels: Vec<Color>,
     pub width: usize,
 }
 
 impl TerminalImage {
+    pub fn height(&self) -> usize {
+        if self.width == 0 { return 0; }
+        self.pixels.len().div_ceil(self.width)
+    }
+
+    // Shrinks the image by the given factor, combining each factor x factor block of pixels into
+    // one. Blocks along the right and bottom edges may be smaller.
+    pub fn downscale(&self, factor: usize, pooling: Pooling) -> TerminalImage {
+        assert!(factor > 0, "Scale factor must be positive");
+        if factor == 1 { return self.clone(); }
+        let (width, height) = (self.width, self.height());
+        let scaled_width = width.div_ceil(factor);
+        let mut pixels = Vec::with_capacity(scaled_width * height.div_ceil(factor));
+        let mut block = Vec::with_capacity(factor * factor);
+        for y in (0..height).step_by(factor) {
+            for x in (0..width).step_by(factor) {
+                block.clear();
+                for by in y..(y+factor).min(height) {
+                    let row = by * width;
+                    block.extend(self.pixels.iter().skip(row + x).take(factor.min(width - x)));
+                }
+                pixels.push(Self::pool(&block, pooling));
+            }
+        }
+        TerminalImage{ pixels, width: scaled_width, }
+    }
+
+    fn pool(block: &[Color], pooling: Pooling) -> Color {
+        debug_assert!(!block.is_empty());
+        if block.iter().all(|c| *c == block[0]) {
+            return block[0];
+        }
+        match pooling {
+            Pooling::Max => *block.iter()
+                .max_by(|a, b| a.luminance().total_cmp(&b.luminance()))
+                .expect("Non-empty"),
+            Pooling::Average => {
+                let (r, g, b) = block.iter().map(|c| c.to_rgb())
+                    .fold((0, 0, 0), |(r, g, b), (cr, cg, cb)| (r + cr as usize, g + cg as usize, b + cb as usize));
+                let n = block.len();
+                Color::from_rgb((r / n) as u8, (g / n) as u8, (b / n) as u8)
+            },
+        }
+    }
+
+    // Returns the width x height window whose top-left corner is at (x, y). The window must lie
+    // within the image.
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> TerminalImage {
+        assert!(x + width <= self.width && y + height <= self.height(), "Window out of bounds");
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in y..y+height {
+            pixels.extend(&self.pixels[row*self.width+x..row*self.width+x+width]);
+        }
+        TerminalImage{ pixels, width, }
+    }
+
+    // Scales and crops the image according to the viewport so that it is no more than max_width
+    // by max_height pixels.
+    pub fn view(self, viewport: &Viewport, max_width: usize, max_height: usize) -> TerminalImage {
+        let scale = match viewport.scale {
+            Scale::Fixed(scale) => scale,
+            Scale::Fit => {
+                let scale_width = self.width.div_ceil(max_width.max(1));
+                let scale_height = self.height().div_ceil(max_height.max(1));
+                scale_width.max(scale_height).max(1)
+            },
+        };
+        let image = self.downscale(scale, viewport.pooling);
+        let (width, height) = (image.width, image.height());
+
+        // Determine whether either position indicator is needed; since each takes up a row or
+        // column of space adding one can necessitate the other.
+        let (mut h_bar, mut v_bar) = (false, false);
+        loop {
+            let next = (width > max_width.saturating_sub(v_bar as usize),
+                        height > max_height.saturating_sub(h_bar as usize));
+            if next == (h_bar, v_bar) { break; }
+            (h_bar, v_bar) = next;
+        }
+        if !h_bar && !v_bar { return image; }
+
+        let win_width = width.min(max_width.saturating_sub(v_bar as usize));
+        let win_height = height.min(max_height.saturating_sub(h_bar as usize));
+        let (focus_x, focus_y) = viewport.focus.map(|(x, y)| (x / scale, y / scale)).unwrap_or((0, 0));
+        let origin_x = focus_x.saturating_sub(win_width / 2).min(width - win_width);
+        let origin_y = focus_y.saturating_sub(win_height / 2).min(height - win_height);
+        let window = image.crop(origin_x, origin_y, win_width, win_height);
+
+        // Marks the portion of a track of the given length that corresponds to the window
+        fn indicator(i: usize, track: usize, full: usize, start: usize, len: usize) -> Color {
+            let (lo, hi) = (i * full / track, ((i + 1) * full / track).max(i * full / track + 1));
+            if lo < start + len && hi > start { Color::WHITE } else { Color::C256(238) }
+        }
+
+        let mut pixels = Vec::with_capacity((win_width + 1) * (win_height + 1));
+        for row in 0..win_height {
+            pixels.extend(&window.pixels[row*win_width..(row+1)*win_width]);
+            if v_bar {
+                pixels.push(indicator(row, win_height, height, origin_y, win_height));
+            }
+        }
+        if h_bar {
+            pixels.extend((0..win_width).map(|col| indicator(col, win_width, width, origin_x, win_width)));
+            if v_bar {
+                pixels.push(Color::BLACK);
+            }
+        }
+        TerminalImage{ pixels, width: win_width + v_bar as usize, }
+    }
+
+    // Crops the image so that it is no more than max_width by max_height pixels.
     fn truncate(mut self, max_width: usize, max_height: usize) -> TerminalImage {
-        let max_height = max_height * 2; // Display fits two rows into each line of output
         // If the image is too wide the pixels vec needs to be reflowed.
         if max_width < self.width {
-            let mut trunc = TerminalImage{ pixels: Vec::new(), width: max_width, };
-            let max_len = max_height * max_width;
-            let mut row = 0;
-            while trunc.pixels.len() < max_len {
+            let rows = self.height().min(max_height);
+            let mut trunc = TerminalImage{ pixels: Vec::with_capacity(rows * max_width), width: max_width, };
+            for row in 0..rows {
                 trunc.pixels.extend(self.pixels.iter().skip(row*self.width).take(trunc.width));
-                row += 1;
             }
-            debug_assert_eq!(trunc.pixels.len(), row * trunc.width);
+            debug_assert_eq!(trunc.pixels.len(), rows * trunc.width);
             return trunc;
         }
         // Otherwise we can just truncate the excess rows. This is a no-op if image isn't too tall.
@@ -138,30 +355,664 @@ impl TerminalImage {
 
 impl std::fmt::Display for TerminalImage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        assert_eq!(self.pixels.len() % self.width, 0, "Incomplete image");
+        write!(f, "{}", Glyphs::HalfBlock.render_image(self))
+    }
+}
+
+// The sets of block characters that can be used to draw several "pixels" in a single character
+// cell. Images rendered with denser glyphs show more detail in the same space, but each cell can
+// only show two colors (a foreground and a background).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Glyphs {
+    // ▀ - 1x2 pixels per cell
+    HalfBlock,
+    // ▚ - 2x2 pixels per cell
+    Quadrant,
+    // 🬗 - 2x3 pixels per cell, requires a font supporting Unicode 13's Symbols for Legacy Computing
+    Sextant,
+    // ⡱ - 2x4 pixels per cell
+    Braille,
+}
+
+impl Glyphs {
+    pub const ALL: [Glyphs; 4] = [Glyphs::HalfBlock, Glyphs::Quadrant, Glyphs::Sextant, Glyphs::Braille];
+
+    // The (width, height) of the pixels represented by each character
+    pub fn cell_size(&self) -> (usize, usize) {
+        match self {
+            Glyphs::HalfBlock => (1, 2),
+            Glyphs::Quadrant => (2, 2),
+            Glyphs::Sextant => (2, 3),
+            Glyphs::Braille => (2, 4),
+        }
+    }
+
+    // Returns the character representing the given pixels, where bit (y * cell_width + x) of
+    // pixels is set if the pixel at (x, y) in the cell is present.
+    pub fn glyph(&self, pixels: u8) -> char {
+        match self {
+            Glyphs::HalfBlock => [' ', '▀', '▄', '█'][pixels as usize],
+            Glyphs::Quadrant => [
+                ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█'
+            ][pixels as usize],
+            // https://en.wikipedia.org/wiki/Symbols_for_Legacy_Computing - the sextant block omits
+            // the four patterns that already exist as block elements.
+            Glyphs::Sextant => match pixels {
+                0 => ' ',
+                21 => '▌',
+                42 => '▐',
+                63 => '█',
+                _ => {
+                    let skipped = if pixels > 42 { 3 } else if pixels > 21 { 2 } else { 1 };
+                    char::from_u32(0x1FB00 + pixels as u32 - skipped).expect("Valid sextant char")
+                },
+            },
+            // https://en.wikipedia.org/wiki/Braille_Patterns#Identifying,_naming_and_ordering
+            Glyphs::Braille => {
+                // Braille bit for each pixel in row-major order
+                static DOTS: [u8; 8] = [0, 3, 1, 4, 2, 5, 6, 7];
+                let dots = DOTS.iter().enumerate()
+                    .filter(|(i, _)| pixels & (1 << i) != 0)
+                    .fold(0, |d, (_, dot)| d | 1 << dot);
+                char::from_u32(0x2800 + dots).expect("Valid Braille char")
+            },
+        }
+    }
+
+    // Renders a width x height grid of pixels, which are present if contains returns true. Every
+    // row, including the last, ends with a newline.
+    pub fn render_bits(&self, width: usize, height: usize, contains: impl Fn(usize, usize) -> bool) -> String {
+        let (cell_width, cell_height) = self.cell_size();
         let mut out = String::new();
-        out.reserve(self.pixels.len()*10);
-
-        for r in (0..(self.pixels.len()/self.width)).step_by(2) {
-            for c in 0..self.width {
-                let i = self.width*r+c;
-                let j = i+self.width;
-                let i_color = self.pixels[i];
-                if let Some(j_color) = self.pixels.get(j) {
-                    append_escapes(&[&i_color, &j_color.bg()], &mut out);
-                } else {
-                    i_color.append_escape(&mut out);
+        for y in (0..height).step_by(cell_height) {
+            for x in (0..width).step_by(cell_width) {
+                let mut pixels = 0;
+                for (i, (dx, dy)) in self.cell_offsets().enumerate() {
+                    if contains(x + dx, y + dy) {
+                        pixels |= 1 << i;
+                    }
                 }
-                out.push('▀');
+                out.push(self.glyph(pixels));
             }
-            append_formatting_off(&mut out);
+            out.push('\n');
+        }
+        out
+    }
+
+    // Renders a colored image. Since a cell can only display two colors, cells containing more
+    // than two colors are approximated; the color of the cell's top-left pixel is used as the
+    // foreground and the most common other color as the background.
+    pub fn render_image(&self, image: &TerminalImage) -> String {
+        Cell::format_rows(&self.render_cells(image))
+    }
+
+    // Renders a colored image into rows of Cells, see render_image().
+    fn render_cells(&self, image: &TerminalImage) -> Vec<Vec<Cell>> {
+        assert_eq!(image.pixels.len() % image.width, 0, "Incomplete image");
+        let (cell_width, cell_height) = self.cell_size();
+        let (width, height) = (image.width, image.height());
+
+        let mut cell = Vec::with_capacity(8);
+        (0..height).step_by(cell_height).map(|y| {
+            (0..width).step_by(cell_width).map(|x| {
+                cell.clear();
+                cell.extend(self.cell_offsets()
+                    .map(|(dx, dy)| (x + dx, y + dy))
+                    .map(|(x, y)| if x < width && y < height { Some(image.pixels[y * width + x]) } else { None }));
+                let (pixels, fg, bg) = Self::split_colors(&cell);
+                Cell { glyph: self.glyph(pixels), fg, bg }
+            }).collect()
+        }).collect()
+    }
+
+    fn cell_offsets(&self) -> impl Iterator<Item = (usize, usize)> {
+        let (cell_width, cell_height) = self.cell_size();
+        (0..cell_height).flat_map(move |dy| (0..cell_width).map(move |dx| (dx, dy)))
+    }
+
+    // Picks a foreground and (optional) background color for a cell and determines which pixels
+    // should be drawn in the foreground color.
+    fn split_colors(cell: &[Option<Color>]) -> (u8, Color, Option<Color>) {
+        let fg = cell[0].expect("Cell must contain at least one pixel");
+        let mut others: Vec<(Color, usize)> = Vec::new();
+        for color in cell.iter().flatten().filter(|c| **c != fg) {
+            match others.iter_mut().find(|(c, _)| c == color) {
+                Some((_, count)) => *count += 1,
+                None => others.push((*color, 1)),
+            }
+        }
+        // max_by_key returns the last max element, reverse so the first-seen color wins ties
+        let bg = others.iter().rev().max_by_key(|(_, count)| *count).map(|(c, _)| *c);
+
+        fn distance(a: Color, b: Color) -> i32 {
+            let ((ar, ag, ab), (br, bg, bb)) = (a.to_rgb(), b.to_rgb());
+            (ar as i32 - br as i32).pow(2) + (ag as i32 - bg as i32).pow(2) + (ab as i32 - bb as i32).pow(2)
+        }
+        let mut pixels = 0;
+        for (i, color) in cell.iter().enumerate() {
+            let foreground = match (color, bg) {
+                (None, _) => false,
+                (Some(_), None) => true,
+                (Some(c), Some(bg)) => *c == fg || (*c != bg && distance(*c, fg) <= distance(*c, bg)),
+            };
+            if foreground {
+                pixels |= 1 << i;
+            }
+        }
+        (pixels, fg, bg)
+    }
+}
+
+// A single character of a rendered image
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Cell {
+    glyph: char,
+    fg: Color,
+    bg: Option<Color>,
+}
+
+impl Cell {
+    // Appends a run of cells, only emitting escape sequences where the colors change.
+    fn format_run(cells: &[Cell], out: &mut String) {
+        let mut last: Option<&Cell> = None;
+        for cell in cells {
+            let same_colors = last.map(|l| l.fg == cell.fg && l.bg == cell.bg).unwrap_or(false);
+            if !same_colors {
+                match cell.bg {
+                    Some(bg) => append_escapes(&[&cell.fg, &bg.bg()], out),
+                    None => {
+                        // The only way to unset a background color is to reset all formatting
+                        if last.map(|l| l.bg.is_some()).unwrap_or(false) {
+                            append_formatting_off(out);
+                        }
+                        cell.fg.append_escape(out);
+                    },
+                }
+            }
+            out.push(cell.glyph);
+            last = Some(cell);
+        }
+        append_formatting_off(out);
+    }
+
+    fn format_rows(rows: &[Vec<Cell>]) -> String {
+        let mut out = String::new();
+        out.reserve(rows.iter().map(|r| r.len()).sum::<usize>() * 10);
+        for row in rows {
+            Cell::format_run(row, &mut out);
             out.push('\n');
         }
         out.pop(); // Remove trailing newline
-        write!(f, "{}", out)
+        out
+    }
+
+    // Constructs the output necessary to update the prev rows in-place to match next, by only
+    // reprinting the cells that changed. Returns None if the rows aren't the same size, in which
+    // case the whole image needs to be redrawn. The output expects the cursor to start in the
+    // top-left corner of the image, and also returns the (0-indexed) row the cursor ends on.
+    #[cfg_attr(not(feature = "interactive"), allow(dead_code))]
+    fn diff_rows(prev: &[Vec<Cell>], next: &[Vec<Cell>]) -> Option<(String, usize)> {
+        // Unchanged cells shorter than this are reprinted rather than skipped over, since the
+        // escape sequence to move the cursor would take up more space.
+        const MIN_GAP: usize = 4;
+        if prev.len() != next.len() || prev.iter().zip(next).any(|(p, n)| p.len() != n.len()) {
+            return None;
+        }
+        let mut out = String::new();
+        let mut cursor_row = 0;
+        for (row, (prev_row, next_row)) in prev.iter().zip(next).enumerate() {
+            let changed: Vec<_> = (0..next_row.len()).filter(|&c| prev_row[c] != next_row[c]).collect();
+            let mut runs: Vec<(usize, usize)> = Vec::new();
+            for c in changed {
+                match runs.last_mut() {
+                    Some((_, end)) if c - *end < MIN_GAP => *end = c + 1,
+                    _ => runs.push((c, c + 1)),
+                }
+            }
+            for (start, end) in runs {
+                if row > cursor_row {
+                    out.push_str(&format!("\x1B[{}B", row - cursor_row)); // down
+                    cursor_row = row;
+                }
+                out.push_str(&format!("\x1B[{}G", start + 1)); // to (1-indexed) column
+                Cell::format_run(&next_row[start..end], &mut out);
+            }
+        }
+        Some((out, cursor_row))
     }
 }
 
+impl std::str::FromStr for Glyphs {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s.to_lowercase().as_str() {
+            "halfblock" | "half" => Glyphs::HalfBlock,
+            "quadrant" => Glyphs::Quadrant,
+            "sextant" => Glyphs::Sextant,
+            "braille" => Glyphs::Braille,
+            _ => anyhow::bail!("Unknown glyphs: {}", s),
+        })
+    }
+}
+
+// A run of printable text, or a single terminal escape sequence
+#[derive(Debug, PartialEq, Eq)]
+enum Segment<'a> {
+    Text(&'a str),
+    Escape(&'a str),
+}
+
+// Splits line into text and escape sequences. CSI sequences (\e[...) run until their final byte,
+// other escapes are assumed to be two characters long.
+fn segments(line: &str) -> impl Iterator<Item = Segment<'_>> {
+    let mut rest = line;
+    std::iter::from_fn(move || {
+        if rest.is_empty() { return None; }
+        let (segment, len) = if let Some(escape) = rest.strip_prefix('\x1B') {
+            let len = match escape.strip_prefix('[') {
+                Some(csi) => csi.find(|c| ('\x40'..='\x7E').contains(&c))
+                    .map(|i| i + 3).unwrap_or(rest.len()),
+                None => 1 + escape.chars().next().map(|c| c.len_utf8()).unwrap_or(0),
+            };
+            (Segment::Escape(&rest[..len]), len)
+        } else {
+            let len = rest.find('\x1B').unwrap_or(rest.len());
+            (Segment::Text(&rest[..len]), len)
+        };
+        rest = &rest[len..];
+        Some(segment)
+    })
+}
+
+// The number of columns a single grapheme cluster occupies. Clusters such as emoji ZWJ sequences
+// are drawn as one (wide) glyph, rather than the sum of their parts.
+fn grapheme_width(grapheme: &str) -> usize {
+    use unicode_width::UnicodeWidthStr;
+    if grapheme.contains('\u{FE0F}') { return 2; } // emoji presentation selector
+    grapheme.width().min(2)
+}
+
+// Truncates line to no more than width columns, accounting for wide characters and grapheme
+// clusters. Escape sequences take up no space; any SGR (formatting) sequences that follow the
+// truncation point are retained so that, e.g., a trailing reset still takes effect.
+fn truncate_line(line: &str, width: usize) -> std::borrow::Cow<'_, str> {
+    use unicode_segmentation::UnicodeSegmentation;
+    if display_width(line) <= width { return line.into(); }
+    let mut trunc = String::new();
+    let mut columns = 0;
+    let mut truncated = false;
+    for segment in segments(line) {
+        match segment {
+            Segment::Escape(escape) if !truncated || escape.ends_with('m') => trunc.push_str(escape),
+            Segment::Escape(_) => {},
+            Segment::Text(text) => {
+                for grapheme in text.graphemes(true) {
+                    if truncated { break; }
+                    let grapheme_columns = grapheme_width(grapheme);
+                    if columns + grapheme_columns > width {
+                        truncated = true;
+                        break;
+                    }
+                    columns += grapheme_columns;
+                    trunc.push_str(grapheme);
+                }
+            },
+        }
+    }
+    trunc.into()
+}
+
+// The number of columns line occupies when printed, ignoring any escape sequences.
+fn display_width(line: &str) -> usize {
+    use unicode_segmentation::UnicodeSegmentation;
+    segments(line)
+        .map(|s| match s {
+            Segment::Text(text) => text.graphemes(true).map(grapheme_width).sum(),
+            Segment::Escape(_) => 0,
+        })
+        .sum()
+}
+
+// A composite of text and images, laid out vertically or side-by-side, that can be displayed
+// together. Text is truncated and images cropped (or scaled, per their Viewport) to fit the
+// available space; when stacking, text is laid out first and images fill the remaining lines.
+pub enum Frame {
+    Text(String),
+    Image(TerminalImage, Option<Viewport>),
+    Stack(Vec<Frame>),
+    Beside(Vec<Frame>),
+}
+
+impl Frame {
+    pub fn text(text: impl ToString) -> Frame { Frame::Text(text.to_string()) }
+
+    pub fn image(image: TerminalImage) -> Frame { Frame::Image(image, None) }
+
+    pub fn viewed(image: TerminalImage, viewport: Viewport) -> Frame { Frame::Image(image, Some(viewport)) }
+
+    pub fn stack(frames: impl IntoIterator<Item = Frame>) -> Frame { Frame::Stack(frames.into_iter().collect()) }
+
+    pub fn beside(frames: impl IntoIterator<Item = Frame>) -> Frame { Frame::Beside(frames.into_iter().collect()) }
+
+    fn has_image(&self) -> bool {
+        match self {
+            Frame::Text(_) => false,
+            Frame::Image(..) => true,
+            Frame::Stack(frames) | Frame::Beside(frames) => frames.iter().any(|f| f.has_image()),
+        }
+    }
+
+    // Lays out the frame in no more than width columns and height lines, returning each line
+    // (without a trailing newline) along with the number of columns it occupies.
+    pub fn render_lines(&self, width: usize, height: usize, glyphs: Glyphs) -> Vec<(String, usize)> {
+        if width == 0 || height == 0 { return Vec::new(); }
+        match self {
+            Frame::Text(text) => text.trim_end().lines().take(height)
+                .map(|l| truncate_line(l, width))
+                .map(|l| { let w = display_width(&l); (l.into_owned(), w) })
+                .collect(),
+            Frame::Image(image, viewport) => {
+                let (cell_width, cell_height) = glyphs.cell_size();
+                let (max_width, max_height) = (width * cell_width, height * cell_height);
+                let image = match viewport {
+                    Some(viewport) => image.clone().view(viewport, max_width, max_height),
+                    None => image.clone().truncate(max_width, max_height),
+                };
+                if image.pixels.is_empty() { return Vec::new(); }
+                let columns = image.width.div_ceil(cell_width);
+                glyphs.render_image(&image).lines().map(|l| (l.to_string(), columns)).collect()
+            },
+            Frame::Stack(frames) => {
+                let mut rendered = vec![Vec::new(); frames.len()];
+                let mut remaining = height;
+                // Text gets first claim on the available lines, images split what's left evenly;
+                // any lines an image doesn't need are shared among the images after it
+                for (i, frame) in frames.iter().enumerate().filter(|(_, f)| !f.has_image()) {
+                    rendered[i] = frame.render_lines(width, remaining, glyphs);
+                    remaining -= rendered[i].len();
+                }
+                let images: Vec<_> = frames.iter().enumerate().filter(|(_, f)| f.has_image()).collect();
+                for (n, (i, frame)) in images.iter().enumerate() {
+                    let share = remaining.div_ceil(images.len() - n);
+                    rendered[*i] = frame.render_lines(width, share, glyphs);
+                    remaining -= rendered[*i].len();
+                }
+                rendered.into_iter().flatten().collect()
+            },
+            Frame::Beside(frames) => {
+                let mut columns = Vec::new();
+                let mut remaining = width;
+                for frame in frames {
+                    if remaining == 0 { break; }
+                    let lines = frame.render_lines(remaining, height, glyphs);
+                    let column_width = lines.iter().map(|(_, w)| *w).max().unwrap_or(0);
+                    remaining = remaining.saturating_sub(column_width + 1); // one column gap
+                    columns.push((lines, column_width));
+                }
+                let rows = columns.iter().map(|(lines, _)| lines.len()).max().unwrap_or(0);
+                (0..rows).map(|row| {
+                    let mut line = String::new();
+                    let mut line_width = 0;
+                    for (i, (lines, column_width)) in columns.iter().enumerate() {
+                        if i > 0 {
+                            line.push(' ');
+                            line_width += 1;
+                        }
+                        let (text, text_width) = lines.get(row).map(|(l, w)| (l.as_str(), *w)).unwrap_or(("", 0));
+                        line.push_str(text);
+                        line.push_str(&" ".repeat(column_width - text_width));
+                        line_width += column_width;
+                    }
+                    (line, line_width)
+                }).collect()
+            },
+        }
+    }
+}
+
+mod palette {
+    use std::collections::HashMap;
+    use crate::euclid::{point, Point};
+    use super::*;
+
+    #[derive(Clone, Debug)]
+    enum Mapping {
+        // Linear interpolation between evenly-spaced RGB stops
+        Gradient(Vec<(u8, u8, u8)>),
+        // The range is split into equal bins, one per color
+        Steps(Vec<Color>),
+    }
+
+    // Maps numbers in the range [min, max] to colors; values outside the range are clamped.
+    // Gradients are approximated using the 256-color palette.
+    #[derive(Clone, Debug)]
+    pub struct Palette {
+        min: f64,
+        max: f64,
+        mapping: Mapping,
+    }
+
+    impl Palette {
+        pub fn gradient(min: f64, max: f64, colors: &[Color]) -> Palette {
+            assert!(!colors.is_empty(), "Gradient requires at least one color");
+            Palette::create(min, max, Mapping::Gradient(colors.iter().map(|c| c.to_rgb()).collect()))
+        }
+
+        pub fn steps(min: f64, max: f64, colors: &[Color]) -> Palette {
+            assert!(!colors.is_empty(), "Steps require at least one color");
+            Palette::create(min, max, Mapping::Steps(colors.to_vec()))
+        }
+
+        pub fn greyscale(min: f64, max: f64) -> Palette {
+            Palette::gradient(min, max, &[Color::BLACK, Color::WHITE])
+        }
+
+        // https://bids.github.io/colormap/
+        pub fn viridis(min: f64, max: f64) -> Palette {
+            Palette::create(min, max, Mapping::Gradient(vec!(
+                (0x44, 0x01, 0x54), (0x48, 0x28, 0x78), (0x3E, 0x4A, 0x89), (0x31, 0x68, 0x8E),
+                (0x26, 0x82, 0x8E), (0x1F, 0x9E, 0x89), (0x35, 0xB7, 0x79), (0x6D, 0xCD, 0x59),
+                (0xB4, 0xDE, 0x2C), (0xFD, 0xE7, 0x25))))
+        }
+
+        pub fn magma(min: f64, max: f64) -> Palette {
+            Palette::create(min, max, Mapping::Gradient(vec!(
+                (0x00, 0x00, 0x04), (0x1C, 0x10, 0x44), (0x4F, 0x12, 0x7B), (0x81, 0x25, 0x81),
+                (0xB5, 0x36, 0x7A), (0xE5, 0x50, 0x64), (0xFB, 0x87, 0x61), (0xFE, 0xC2, 0x87),
+                (0xFC, 0xFD, 0xBF))))
+        }
+
+        fn create(min: f64, max: f64, mapping: Mapping) -> Palette {
+            assert!(min < max, "Invalid range: {}..{}", min, max);
+            Palette { min, max, mapping }
+        }
+
+        // Flips the palette, so that min maps to the color max previously did and vice-versa
+        pub fn reversed(mut self) -> Palette {
+            match &mut self.mapping {
+                Mapping::Gradient(stops) => stops.reverse(),
+                Mapping::Steps(colors) => colors.reverse(),
+            }
+            self
+        }
+
+        pub fn range(&self) -> (f64, f64) { (self.min, self.max) }
+
+        pub fn color(&self, value: f64) -> Color {
+            let ratio = ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0);
+            match &self.mapping {
+                Mapping::Steps(colors) => colors[((ratio * colors.len() as f64) as usize).min(colors.len() - 1)],
+                Mapping::Gradient(stops) => {
+                    if stops.len() == 1 {
+                        let (r, g, b) = stops[0];
+                        return Color::from_rgb(r, g, b);
+                    }
+                    let position = ratio * (stops.len() - 1) as f64;
+                    let index = (position as usize).min(stops.len() - 2);
+                    let t = position - index as f64;
+                    let ((r1, g1, b1), (r2, g2, b2)) = (stops[index], stops[index + 1]);
+                    let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+                    Color::from_rgb(lerp(r1, r2), lerp(g1, g2), lerp(b1, b2))
+                },
+            }
+        }
+
+        // A horizontal strip, width pixels wide, showing the palette from min to max. It's as tall
+        // as a cell of the given glyphs, so that it fills one line of output.
+        pub fn legend_image(&self, width: usize, glyphs: Glyphs) -> TerminalImage {
+            assert!(width > 1, "Legend must be at least two pixels wide");
+            let row: Vec<_> = (0..width)
+                .map(|x| self.color(self.min + (self.max - self.min) * x as f64 / (width - 1) as f64))
+                .collect();
+            let (_, rows) = glyphs.cell_size();
+            TerminalImage{ pixels: row.repeat(rows), width, }
+        }
+
+        // A legend strip with the min and max values labeled at either end.
+        pub fn legend(&self, width: usize, glyphs: Glyphs) -> Frame {
+            let (min, max) = (format!("{}", self.min), format!("{}", self.max));
+            let padding = width.saturating_sub(min.len() + max.len()).max(1);
+            Frame::stack([
+                Frame::image(self.legend_image(width, glyphs)),
+                Frame::text(format!("{}{}{}", min, " ".repeat(padding), max)),
+            ])
+        }
+    }
+
+    // Numeric types that can be drawn in a Heatmap.
+    pub trait ToF64: Copy {
+        fn to_f64(self) -> f64;
+    }
+
+    macro_rules! to_f64 {
+        ($($t:ty),*) => { $(impl ToF64 for $t { fn to_f64(self) -> f64 { self as f64 } })* }
+    }
+    to_f64!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+    // Renders numeric values as colors using a Palette. Missing values are drawn in the absent
+    // color, which defaults to black.
+    pub struct Heatmap<'a> {
+        palette: &'a Palette,
+        values: Vec<Option<f64>>,
+        width: usize,
+        absent: Color,
+    }
+
+    impl<'a> Heatmap<'a> {
+        // Renders the bounding box of the map's keys
+        pub fn from_map<N: ToF64>(map: &HashMap<Point, N>, palette: &'a Palette) -> Heatmap<'a> {
+            let (values, width) = match Point::bounding_box(map.keys()) {
+                Some((min, max)) => (
+                    (min.y..=max.y)
+                        .flat_map(|y| (min.x..=max.x).map(move |x| point(x, y)))
+                        .map(|p| map.get(&p).map(|v| v.to_f64()))
+                        .collect(),
+                    (max.x - min.x + 1) as usize),
+                None => (Vec::new(), 0),
+            };
+            Heatmap { palette, values, width, absent: Color::BLACK }
+        }
+
+        // Renders a grid of rows; shorter rows are padded with the absent color
+        pub fn from_grid<N: ToF64>(grid: &[Vec<N>], palette: &'a Palette) -> Heatmap<'a> {
+            let width = grid.iter().map(|r| r.len()).max().unwrap_or(0);
+            let values = grid.iter()
+                .flat_map(|row| (0..width).map(move |x| row.get(x).map(|v| v.to_f64())))
+                .collect();
+            Heatmap { palette, values, width, absent: Color::BLACK }
+        }
+
+        pub fn with_absent(mut self, absent: Color) -> Heatmap<'a> {
+            self.absent = absent;
+            self
+        }
+
+        // The heatmap above its legend, sized for the given glyphs
+        pub fn frame(&self, glyphs: Glyphs) -> Frame {
+            Frame::stack([Frame::image(self.render(0, 0)), self.palette.legend(self.width.max(2), glyphs)])
+        }
+    }
+
+    impl<'a> TerminalRender for Heatmap<'a> {
+        fn render(&self, _w: usize, _h: usize) -> TerminalImage {
+            let pixels = self.values.iter()
+                .map(|v| v.map(|v| self.palette.color(v)).unwrap_or(self.absent))
+                .collect();
+            TerminalImage{ pixels, width: self.width, }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn gradient() {
+            let palette = Palette::greyscale(0.0, 10.0);
+            assert_eq!(palette.color(0.0), Color::C256(16));
+            assert_eq!(palette.color(10.0), Color::C256(231));
+            assert_eq!(palette.color(5.0), Color::from_rgb(128, 128, 128));
+            // clamped
+            assert_eq!(palette.color(-5.0), palette.color(0.0));
+            assert_eq!(palette.color(50.0), palette.color(10.0));
+
+            let palette = Palette::gradient(0.0, 2.0, &[Color::RED, Color::BLACK, Color::BLUE]);
+            assert_eq!(palette.color(0.0), Color::C256(160));
+            assert_eq!(palette.color(1.0), Color::C256(16));
+            assert_eq!(palette.color(2.0), Color::C256(21));
+            assert_eq!(palette.reversed().color(0.0), Color::C256(21));
+        }
+
+        #[test]
+        fn steps() {
+            let palette = Palette::steps(0.0, 3.0, &[Color::RED, Color::GREEN, Color::BLUE]);
+            let colors: Vec<_> = [0.0, 0.9, 1.0, 2.5, 3.0].iter().map(|&v| palette.color(v)).collect();
+            assert_eq!(colors, vec!(Color::RED, Color::RED, Color::GREEN, Color::BLUE, Color::BLUE));
+        }
+
+        #[test]
+        fn viridis() {
+            let palette = Palette::viridis(0.0, 1.0);
+            assert_eq!(palette.color(0.0), Color::from_rgb(0x44, 0x01, 0x54));
+            assert_eq!(palette.color(1.0), Color::from_rgb(0xFD, 0xE7, 0x25));
+        }
+
+        #[test]
+        fn legend() {
+            let palette = Palette::steps(0.0, 9.0, &[Color::RED, Color::GREEN, Color::BLUE]);
+            let legend = palette.legend_image(3, Glyphs::HalfBlock);
+            assert_eq!(legend.pixels, vec!(Color::RED, Color::GREEN, Color::BLUE, Color::RED, Color::GREEN, Color::BLUE));
+            assert_eq!(palette.legend_image(3, Glyphs::Braille).height(), 4);
+            assert_eq!(palette.legend_image(3, Glyphs::Sextant).height(), 3);
+            let lines: Vec<_> = palette.legend(6, Glyphs::HalfBlock).render_lines(6, 2, Glyphs::HalfBlock).into_iter().map(|(l, _)| l).collect();
+            assert_eq!(lines[1], "0    9");
+        }
+
+        #[test]
+        fn heatmap() {
+            let palette = Palette::steps(0.0, 2.0, &[Color::RED, Color::GREEN]);
+            let map: HashMap<_, _> = [(point(1, 1), 0), (point(2, 2), 2)].into_iter().collect();
+            let image = Heatmap::from_map(&map, &palette).with_absent(Color::WHITE).render(0, 0);
+            assert_eq!(image.width, 2);
+            assert_eq!(image.pixels, vec!(Color::RED, Color::WHITE, Color::WHITE, Color::GREEN));
+
+            let grid = vec!(vec!(0.5_f32, 1.5), vec!(1.5));
+            let image = Heatmap::from_grid(&grid, &palette).render(0, 0);
+            assert_eq!(image.pixels, vec!(Color::RED, Color::GREEN, Color::GREEN, Color::BLACK));
+
+            // Integer types that don't implement Into<f64>
+            let map: HashMap<_, _> = [(point(0, 0), 0_usize), (point(1, 0), 2)].into_iter().collect();
+            assert_eq!(Heatmap::from_map(&map, &palette).render(0, 0).pixels, vec!(Color::RED, Color::GREEN));
+            let grid = vec!(vec!(2_u64, 0));
+            assert_eq!(Heatmap::from_grid(&grid, &palette).render(0, 0).pixels, vec!(Color::GREEN, Color::RED));
+            let grid = vec!(vec!(-1_i64, 3));
+            assert_eq!(Heatmap::from_grid(&grid, &palette).render(0, 0).pixels, vec!(Color::RED, Color::GREEN));
+        }
+    }
+}
+pub use self::palette::{Heatmap, Palette, ToF64};
+
 #[cfg(not(feature = "interactive"))]
 pub use self::disabled::*;
 #[cfg(not(feature = "interactive"))]
@@ -170,52 +1021,65 @@ mod disabled {
     pub struct Terminal;
 
     impl Terminal {
-        #[inline] pub fn init() {}
+        #[inline] pub fn init() -> Cleanup { Cleanup }
+        #[inline] pub fn init_full_screen() -> Cleanup { Cleanup }
         #[inline] pub fn active() -> bool { false }
+        #[inline] pub fn set_glyphs(_glyphs: Glyphs) {}
+        #[inline] pub fn glyphs() -> Glyphs { Glyphs::HalfBlock }
         #[inline] pub fn interactive_display(_lazy: impl ToString, _delay: std::time::Duration) {}
         #[inline] pub fn interactive_render(_lazy: &impl TerminalRender, _delay: std::time::Duration) {}
+        #[inline] pub fn interactive_frame(_lazy: impl FnOnce() -> Frame, _delay: std::time::Duration) {}
         #[inline] pub fn end_interactive() {}
         #[inline] pub fn clear_interactive() {}
-
-        // Throwaway method that calls TerminalImage.truncate() so that method is not considered unused
-        #[allow(dead_code)]
-        fn disregard(img: TerminalImage) {
-            img.truncate(0,0);
-            unreachable!();
-        }
     }
+
+    pub struct Cleanup;
 }
 
 #[cfg(feature = "interactive")]
 pub use self::real::*;
 #[cfg(feature = "interactive")]
 mod real {
-    use std::sync::atomic::{AtomicUsize, Ordering};
-    use crate::terminal::TerminalRender;
+    use std::io::Write;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+    use crate::terminal::{display_width, truncate_line, Cell, Frame, Glyphs, TerminalRender};
 
     static CURSOR_SHIFT: AtomicUsize = AtomicUsize::new(0);
+    static GLYPHS: AtomicU8 = AtomicU8::new(0); // index into Glyphs::ALL
     static CLEAR_END_OF_LINE: bool = true;
+    static FULL_SCREEN: AtomicBool = AtomicBool::new(false);
+    static RESIZED: AtomicBool = AtomicBool::new(false);
+    // The most recent output of interactive_render(), if nothing has been printed since
+    static LAST_IMAGE: Mutex<Option<LastImage>> = Mutex::new(None);
+
+    struct LastImage {
+        cells: Vec<Vec<Cell>>,
+        area: (usize, usize),
+    }
+    // How often to check for a resize while pausing between frames in full-screen mode
+    static RESIZE_POLL: Duration = Duration::from_millis(20);
 
     pub struct Terminal;
 
     // Ensures str is no more than height lines long, and no line is more than width columns wide.
-    // The width requirement is difficult to enforce properly, so this is not guaranteed to work for
-    // all characters. Normal-width Unicode characters without modifiers should work, but other
-    // sequences such as emojis or double-width glyphs may fail to render correctly. Notably, this
-    // function must be called before any terminal escape sequences are inserted.
+    // Widths are measured in grapheme clusters, accounting for double-width glyphs, and escape
+    // sequences (such as those inserted by Color) are ignored, so colored text can be truncated
+    // safely. Width is ultimately determined by the terminal's font, so unusual sequences may still
+    // render differently.
     // str will _not_ end with a newline character after this returns.
     fn truncate_string(str: &mut String, width: usize, height: usize) {
-        let (_w, _h) = (width, height);
-        str.truncate(str.trim().len());
+        str.truncate(str.trim_end().len());
         if !CLEAR_END_OF_LINE {
             // Short-circuit if we're not adding escape sequences to each line and the string fits
-            if str.lines().count() <= height && str.lines().all(|l| l.len() <= width) {
+            if str.lines().count() <= height && str.lines().all(|l| display_width(l) <= width) {
                 return;
             }
         }
         let mut trunc = String::new();
         for line in str.lines().take(height) {
-            trunc.extend(line.chars().take(width));
+            trunc.push_str(&truncate_line(line, width));
             if CLEAR_END_OF_LINE {
                 trunc.push_str("\x1B[K");
             }
@@ -226,19 +1090,102 @@ mod real {
         *str = trunc;
     }
 
+    // Returns the (width, height) available for interactive output
+    fn print_area() -> (usize, usize) {
+        let (term_width, term_height) = term_size::dimensions().expect("Interactive mode unsupported");
+        if FULL_SCREEN.load(Ordering::SeqCst) {
+            return (term_width, term_height);
+        }
+        (term_width, term_height-1) // Leave one line for the cursor
+    }
+
+    // Sleeps for delay, but in full-screen mode calls redraw if the window is resized in the
+    // meantime so the output doesn't sit garbled until the next frame.
+    fn pause(delay: Duration, redraw: impl Fn()) {
+        if !FULL_SCREEN.load(Ordering::SeqCst) {
+            std::thread::sleep(delay);
+            return;
+        }
+        let end = Instant::now() + delay;
+        while let Some(remaining) = end.checked_duration_since(Instant::now()).filter(|r| !r.is_zero()) {
+            std::thread::sleep(remaining.min(RESIZE_POLL));
+            if RESIZED.load(Ordering::SeqCst) {
+                redraw();
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    fn watch_resize() {
+        extern "C" fn on_resize(_signal: libc::c_int) {
+            RESIZED.store(true, Ordering::SeqCst);
+        }
+        // Safe because the handler only touches an atomic, which is async-signal-safe
+        unsafe { libc::signal(libc::SIGWINCH, on_resize as extern "C" fn(libc::c_int) as libc::sighandler_t); }
+    }
+
+    #[cfg(not(unix))]
+    fn watch_resize() {}
+
+    fn exit_full_screen() {
+        if FULL_SCREEN.swap(false, Ordering::SeqCst) {
+            print!("\x1B[?1049l"); // restore the original screen
+            std::io::stdout().flush().expect("Failed to flush stdout");
+        }
+    }
+
     impl Terminal {
         pub fn init() -> Cleanup {
             print!("\x1B[?25l"); // hide cursor
             Cleanup
         }
 
+        // Like init(), but also switches to the terminal's alternate screen buffer. Interactive
+        // output is drawn from the top of the screen, and is redrawn from scratch if the window is
+        // resized. The original screen is restored when the Cleanup is dropped or if the program
+        // panics (before the panic message is printed). Note that anything else printed while in
+        // full-screen mode is discarded along with the alternate screen.
+        pub fn init_full_screen() -> Cleanup {
+            FULL_SCREEN.store(true, Ordering::SeqCst);
+            watch_resize();
+            let default_hook = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                exit_full_screen();
+                print!("\x1B[?25h"); // restore cursor
+                default_hook(info);
+            }));
+            print!("\x1B[?1049h\x1B[H\x1B[2J"); // switch to the alternate screen and clear it
+            Terminal::init()
+        }
+
         #[inline] pub fn active() -> bool { true }
 
+        // Selects the characters used by subsequent calls to interactive_render().
+        pub fn set_glyphs(glyphs: Glyphs) {
+            let index = Glyphs::ALL.iter().position(|g| *g == glyphs).expect("Known glyphs");
+            GLYPHS.store(index as u8, Ordering::SeqCst);
+        }
+
+        // The glyphs selected by set_glyphs(), e.g. to size images to match.
+        pub fn glyphs() -> Glyphs {
+            Glyphs::ALL[GLYPHS.load(Ordering::SeqCst) as usize]
+        }
+
         fn interactive_print(str: String, print_height: usize) {
             debug_assert!(!str.ends_with('\n'), "String should not have trailing newlines");
 
             let lines = str.lines().count();
             debug_assert!(lines <= print_height, "String cannot be printed safely");
+            *LAST_IMAGE.lock().expect("Not poisoned") = None;
+
+            if FULL_SCREEN.load(Ordering::SeqCst) {
+                // After a resize the terminal may have reflowed the old output, so start fresh
+                let clear = if RESIZED.swap(false, Ordering::SeqCst) { "\x1B[2J" } else { "" };
+                // Move the cursor to the top-left, print the str, and clear anything below it
+                print!("{}\x1B[H{}\x1B[J", clear, str);
+                std::io::stdout().flush().expect("Failed to flush stdout");
+                return;
+            }
 
             // Never shift by more than the available height - this can happen when the window is resized
             CURSOR_SHIFT.fetch_min(print_height, Ordering::SeqCst);
@@ -266,37 +1213,111 @@ mod real {
         // Prints the given input to the console, ensuring that it fits within the terminal window
         // and recording its height so subsequent calls to Terminal functions will overwrite it.
         // The cursor is left on the last line of the terminal at the first column, which is blank.
-        pub fn interactive_display(lazy: impl ToString, delay: std::time::Duration) {
-            let (term_width, term_height) = term_size::dimensions().expect("Interactive mode unsupported");
-            let print_height = term_height-1; // Leave one line for the cursor
-            let mut str = lazy.to_string();
-            truncate_string(&mut str, term_width, print_height);
-            Terminal::interactive_print(str, print_height);
-            std::thread::sleep(delay);
+        pub fn interactive_display(lazy: impl ToString, delay: Duration) {
+            let draw = || {
+                let (term_width, print_height) = print_area();
+                let mut str = lazy.to_string();
+                truncate_string(&mut str, term_width, print_height);
+                Terminal::interactive_print(str, print_height);
+            };
+            draw();
+            pause(delay, draw);
         }
 
         // Prints the given input to the console as an image, ensuring that it fits within the
         // terminal window, and recording its height so subsequent calls to Terminal functions will
         // overwrite it. The cursor is left on the last line of the terminal at the first column,
         // which is blank.
-        pub fn interactive_render(lazy: &impl TerminalRender, delay: std::time::Duration) {
-            let (term_width, term_height) = term_size::dimensions().expect("Interactive mode unsupported");
-            let print_height = term_height-1; // Leave one line for the cursor
-            let image = lazy.render(term_width, print_height).truncate(term_width, print_height);
-            Terminal::interactive_print(image.to_string(), print_height);
-            std::thread::sleep(delay);
+        pub fn interactive_render(lazy: &impl TerminalRender, delay: Duration) {
+            let draw = || {
+                let (term_width, print_height) = print_area();
+                let glyphs = Terminal::glyphs();
+                let (cell_width, cell_height) = glyphs.cell_size();
+                let (max_width, max_height) = (term_width * cell_width, print_height * cell_height);
+                let image = lazy.render(max_width, max_height);
+                let image = match lazy.viewport() {
+                    Some(viewport) => image.view(&viewport, max_width, max_height),
+                    None => image.truncate(max_width, max_height),
+                };
+                let cells = glyphs.render_cells(&image);
+                let area = (term_width, print_height);
+                if !Terminal::interactive_update(&cells, area) {
+                    Terminal::interactive_print(Cell::format_rows(&cells), print_height);
+                }
+                *LAST_IMAGE.lock().expect("Not poisoned") = Some(LastImage{ cells, area });
+            };
+            draw();
+            pause(delay, draw);
+        }
+
+        // Attempts to redraw the previously-rendered image in-place by only printing the cells that
+        // changed. Returns false if a full redraw is needed instead, e.g. because the terminal
+        // was resized or something else has been printed since.
+        fn interactive_update(cells: &[Vec<Cell>], area: (usize, usize)) -> bool {
+            let last = LAST_IMAGE.lock().expect("Not poisoned");
+            let last = match &*last {
+                Some(last) if last.area == area && !RESIZED.load(Ordering::SeqCst) => last,
+                _ => return false,
+            };
+            let full_screen = FULL_SCREEN.load(Ordering::SeqCst);
+            let lines = cells.len();
+            // Inline, the cursor should still be on the line just below the image
+            if lines == 0 || (!full_screen && CURSOR_SHIFT.load(Ordering::SeqCst) != lines) {
+                return false;
+            }
+            let (diff, row) = match Cell::diff_rows(&last.cells, cells) {
+                Some(diff) => diff,
+                None => return false,
+            };
+            if full_screen {
+                print!("\x1B[H{}", diff);
+            } else {
+                // Move up to the top of the image, and afterwards back down below it
+                print!("\x1B[{}A{}\x1B[{}B\r", lines, diff, lines - row);
+            }
+            std::io::stdout().flush().expect("Failed to flush stdout");
+            true
+        }
+
+        // Prints the Frame constructed by lazy to the console, laid out to fit within the terminal
+        // window, and records its height so subsequent calls to Terminal functions will overwrite
+        // it. The cursor is left on the last line of the terminal at the first column, which is
+        // blank. If the terminal is resized the same Frame is laid out again.
+        pub fn interactive_frame(lazy: impl FnOnce() -> Frame, delay: Duration) {
+            let frame = lazy();
+            let draw = || {
+                let (term_width, print_height) = print_area();
+                let glyphs = Terminal::glyphs();
+                let lines: Vec<_> = frame.render_lines(term_width, print_height, glyphs).into_iter()
+                    .map(|(line, _)| line)
+                    .collect();
+                let separator = if CLEAR_END_OF_LINE { "\x1B[K\n" } else { "\n" };
+                let mut str = lines.join(separator);
+                if CLEAR_END_OF_LINE && !lines.is_empty() {
+                    str.push_str("\x1B[K");
+                }
+                Terminal::interactive_print(str, print_height);
+            };
+            draw();
+            pause(delay, draw);
         }
 
         // Resets the interactive cursor's position, so that subsequent interactive calls will not
         // overwrite earlier output. Use this to separate blocks of interactive output (e.g. part 1
         // followed by part 2).
         pub fn end_interactive() {
+            *LAST_IMAGE.lock().expect("Not poisoned") = None;
             CURSOR_SHIFT.store(0, Ordering::SeqCst);
         }
 
         // Clears any previously printed interactive content, leaving the cursor in position to
         // overwrite the area.
         pub fn clear_interactive() {
+            *LAST_IMAGE.lock().expect("Not poisoned") = None;
+            if FULL_SCREEN.load(Ordering::SeqCst) {
+                print!("\x1B[H\x1B[J");
+                return;
+            }
             // Reset the cursor shift to zero
             let cursor_shift = CURSOR_SHIFT.swap(0, Ordering::SeqCst);
             // Position the cursor at the shift point and clear all below
@@ -304,13 +1325,223 @@ mod real {
         }
     }
 
-    // Take advantage of Drop to (attempt to) unconditionally restore the cursor. See
+    // Take advantage of Drop to (attempt to) unconditionally restore the cursor and screen. See
     // https://stackoverflow.com/a/57860708/113632 for more, or
     // https://doc.rust-lang.org/std/panic/fn.catch_unwind.html for another potential approach.
     pub struct Cleanup;
     impl Drop for Cleanup {
         fn drop(&mut self) {
+            exit_full_screen();
             print!("\x1B[?25h"); // restore cursor
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(rows: &[&str]) -> TerminalImage {
+        let pixels = rows.iter().flat_map(|r| r.chars()).map(|c| match c {
+            '#' => Color::WHITE,
+            '.' => Color::BLACK,
+            'R' => Color::RED,
+            _ => panic!("Unexpected: {}", c),
+        }).collect();
+        TerminalImage{ pixels, width: rows[0].len(), }
+    }
+
+    #[test]
+    fn paint() {
+        assert_eq!(Color::RED.paint("abc"), "\x1B[31mabc\x1B[0m");
+        assert_eq!(Color::C256(214).paint(5), "\x1B[38;5;214m5\x1B[0m");
+        assert_eq!(display_width(&Color::GREEN.paint("[1,2]")), 5);
+    }
+
+    #[test]
+    fn greyscale_codes() {
+        assert_eq!(Color::GREYSCALE(0.0).paint(""), "\x1B[38;5;232m\x1B[0m");
+        assert_eq!(Color::GREYSCALE(1.0).paint(""), "\x1B[38;5;255m\x1B[0m");
+        let palette = palette::Palette::greyscale(0.0, 255.0);
+        for v in 0..=255 {
+            for color in [Color::GREYSCALE(v as f32 / 255.0), Color::from_rgb(v, v, v), palette.color(v as f64)] {
+                let mut out = String::new();
+                append_escapes(&[&color, &color.bg()], &mut out);
+                let codes: Vec<u32> = out.trim_start_matches("\x1B[").trim_end_matches('m')
+                    .split(';').map(|c| c.parse().unwrap()).collect();
+                assert!(codes.iter().all(|&c| c <= 255), "{:?} emitted {:?}", color, out);
+            }
+        }
+    }
+
+    #[test]
+    fn rgb_round_trip() {
+        for code in 16..=255 {
+            let color = Color::C256(code);
+            let (r, g, b) = color.to_rgb();
+            assert_eq!(Color::from_rgb(r, g, b).to_rgb(), (r, g, b), "{:?}", color);
+        }
+    }
+
+    #[test]
+    fn downscale() {
+        let img = image(&["#...", "....", "..##", "..##", "R..."]);
+        let max = img.downscale(2, Pooling::Max);
+        assert_eq!(max, image(&["#.", ".#", "R."]));
+
+        let avg = img.downscale(2, Pooling::Average);
+        assert_eq!(avg.width, 2);
+        assert_eq!(avg.pixels[0], Color::from_rgb(63, 63, 63));
+        assert_eq!(&avg.pixels[1..4], &[Color::BLACK, Color::BLACK, Color::WHITE]);
+        assert_eq!(avg.pixels[4], Color::from_rgb(102, 0, 0));
+    }
+
+    #[test]
+    fn render_glyphs() {
+        let img = TerminalImage{ pixels: vec!(Color::RED, Color::BLUE), width: 1 };
+        assert_eq!(img.to_string(), "\x1B[31;44m▀\x1B[0m");
+
+        let img = image(&["#.", ".#", "##"]);
+        assert_eq!(Glyphs::Quadrant.render_image(&img), "\x1B[97;40m▚\x1B[0m\n\x1B[97m▀\x1B[0m");
+        assert_eq!(Glyphs::Braille.render_image(&img), "\x1B[97;40m⠵\x1B[0m");
+    }
+
+    #[test]
+    fn format_cells() {
+        let img = image(&["##R.", "##R.", "....", "...."]);
+        // Colors are only set when they change
+        assert_eq!(Glyphs::HalfBlock.render_image(&img),
+                   "\x1B[97m██\x1B[31m█\x1B[30m█\x1B[0m\n\x1B[30m████\x1B[0m");
+    }
+
+    #[test]
+    fn diff_cells() {
+        let before = Glyphs::HalfBlock.render_cells(&image(&["........", "........", "........", "........"]));
+        let after = Glyphs::HalfBlock.render_cells(&image(&["#.......", "#.......", "........", "...#..#R"]));
+        let (diff, row) = Cell::diff_rows(&before, &after).unwrap();
+        // The first row changes in one cell, the second in a run which skips a short gap
+        assert_eq!(diff, "\x1B[1G\x1B[97m█\x1B[0m\x1B[1B\x1B[4G\x1B[30;107m▀\x1B[0m\x1B[30m██\x1B[30;107m▀\x1B[30;41m▀\x1B[0m");
+        assert_eq!(row, 1);
+
+        assert_eq!(Cell::diff_rows(&after, &after), Some((String::new(), 0)));
+        assert_eq!(Cell::diff_rows(&before, &after[..1]), None);
+    }
+
+    #[test]
+    fn frames() {
+        let img = image(&["#.", ".#", "##", "#."]);
+        let frame = Frame::stack([
+            Frame::text("Title that is long"),
+            Frame::beside([Frame::image(img), Frame::text("a\nbc")]),
+            Frame::text("Status"),
+        ]);
+        let lines: Vec<_> = frame.render_lines(10, 3, Glyphs::HalfBlock).into_iter().map(|(l, _)| l).collect();
+        // The image only gets one line, after the text is laid out
+        assert_eq!(lines, vec!("Title that", "\x1B[97;40m▀\x1B[30;107m▀\x1B[0m a", "Status"));
+
+        let (lines, widths): (Vec<_>, Vec<_>) = frame.render_lines(4, 10, Glyphs::Quadrant).into_iter().unzip();
+        assert_eq!(widths, vec!(4, 4, 4, 4));
+        assert_eq!(&lines[1..3], &["\x1B[97;40m▚\x1B[0m a ", "\x1B[97;40m▛\x1B[0m bc"]);
+    }
+
+    parameterized_test::create!{ layout, (frame, width, height, glyphs, expected), {
+        let expected: Vec<_> = expected.iter().map(|&(l, w): &(&str, usize)| (l.to_string(), w)).collect();
+        assert_eq!(frame.render_lines(width, height, glyphs), expected);
+    }}
+    layout! {
+        text: (Frame::text("ab\ncde\n\n"), 5, 5, Glyphs::HalfBlock, &[("ab", 2), ("cde", 3)]),
+        text_truncated: (Frame::text("abcdef\nghi\njkl"), 4, 2, Glyphs::HalfBlock, &[("abcd", 4), ("ghi", 3)]),
+        no_space: (Frame::text("abc"), 0, 5, Glyphs::HalfBlock, &[]),
+        image_cropped: (Frame::image(image(&["#.#", ".#.", "##."])), 2, 1, Glyphs::HalfBlock,
+            &[("\x1B[97;40m▀\x1B[30;107m▀\x1B[0m", 2)]),
+        image_viewed: (Frame::viewed(image(&["#...", ".#..", "..#.", "...#"]), Viewport::fit(Pooling::Max)), 2, 1,
+            Glyphs::HalfBlock, &[("\x1B[97;40m▀\x1B[30;107m▀\x1B[0m", 2)]),
+        image_glyphs: (Frame::image(image(&["#.", ".#", "##"])), 5, 5, Glyphs::Braille, &[("\x1B[97;40m⠵\x1B[0m", 1)]),
+        stack: (Frame::stack([Frame::text("a"), Frame::text("bc")]), 5, 5, Glyphs::HalfBlock, &[("a", 1), ("bc", 2)]),
+        stack_truncated: (Frame::stack([Frame::text("a\nb"), Frame::text("c")]), 5, 2, Glyphs::HalfBlock,
+            &[("a", 1), ("b", 1)]),
+        stack_text_first: (Frame::stack([Frame::image(image(&["#", "#", "#", "#"])), Frame::text("status")]), 6, 2,
+            Glyphs::HalfBlock, &[("\x1B[97m█\x1B[0m", 1), ("status", 6)]),
+        stack_images_split: (Frame::stack([Frame::image(image(&["#", "#", "#", "#"])), Frame::image(image(&["#", "#", "#", "#"]))]),
+            1, 2, Glyphs::HalfBlock, &[("\x1B[97m█\x1B[0m", 1), ("\x1B[97m█\x1B[0m", 1)]),
+        stack_images_unused: (Frame::stack([Frame::image(image(&["#", "#"])), Frame::image(image(&["#"; 8]))]),
+            1, 4, Glyphs::HalfBlock, &[("\x1B[97m█\x1B[0m", 1); 4]),
+        beside: (Frame::beside([Frame::text("a\nbcd"), Frame::text("ef")]), 10, 5, Glyphs::HalfBlock,
+            &[("a   ef", 6), ("bcd   ", 6)]),
+        beside_truncated: (Frame::beside([Frame::text("abc"), Frame::text("def")]), 5, 1, Glyphs::HalfBlock,
+            &[("abc d", 5)]),
+        beside_no_space: (Frame::beside([Frame::text("abc"), Frame::text("def")]), 3, 1, Glyphs::HalfBlock,
+            &[("abc", 3)]),
+    }
+
+    #[test]
+    fn segmenting() {
+        let segments: Vec<_> = segments("a\x1B[31;1mbc\x1B[0m\x1B[Kd\x1B7").collect();
+        assert_eq!(segments, vec!(
+            Segment::Text("a"), Segment::Escape("\x1B[31;1m"), Segment::Text("bc"), Segment::Escape("\x1B[0m"),
+            Segment::Escape("\x1B[K"), Segment::Text("d"), Segment::Escape("\x1B7")));
+    }
+
+    parameterized_test::create!{ widths, (line, width), {
+        assert_eq!(display_width(line), width);
+    }}
+    widths! {
+        ascii: ("hello", 5),
+        wide: ("日本語", 6),
+        combining: ("e\u{301}e\u{301}", 2),
+        emoji: ("🎄🎅", 4),
+        zwj: ("👨‍👩‍👧", 2),
+        flag: ("🇺🇸", 2),
+        escapes: ("\x1B[31mred\x1B[0m", 3),
+    }
+
+    parameterized_test::create!{ truncation, (line, width, expected), {
+        assert_eq!(truncate_line(line, width), expected);
+    }}
+    truncation! {
+        fits: ("hello", 5, "hello"),
+        ascii: ("hello", 3, "hel"),
+        wide: ("日本語", 5, "日本"),
+        combining: ("e\u{301}e\u{301}e\u{301}", 2, "e\u{301}e\u{301}"),
+        zwj: ("a👨‍👩‍👧b", 3, "a👨‍👩‍👧"),
+        colored: ("\x1B[31mred\x1B[0m", 2, "\x1B[31mre\x1B[0m"),
+        drops_cursor_escapes: ("ab\x1B[Kcd", 1, "a"),
+    }
+
+    #[test]
+    fn truncate_colored() {
+        let mut line = String::new();
+        Color::GREEN.append_escape(&mut line);
+        line.push_str("green");
+        append_formatting_off(&mut line);
+        assert_eq!(truncate_line(&line, 1), "\x1B[32mg\x1B[0m");
+    }
+
+    #[test]
+    fn crop() {
+        let img = image(&["#...", ".#..", "..#.", "...#"]);
+        assert_eq!(img.crop(1, 1, 2, 3), image(&["#.", ".#", ".."]));
+    }
+
+    #[test]
+    fn view_fits() {
+        let img = image(&["#...", ".#..", "..#.", "...#"]);
+        assert_eq!(img.clone().view(&Viewport::follow(3, 3), 4, 4), img);
+        assert_eq!(img.clone().view(&Viewport::fit(Pooling::Max), 2, 2), image(&["#.", ".#"]));
+    }
+
+    #[test]
+    fn view_follows_focus() {
+        let img = image(&["#.......", ".#......", "..#.....", "...#....", "....#...", ".....#.."]);
+        let view = img.view(&Viewport::follow(5, 5), 4, 4);
+        let (on, off) = (Color::WHITE, Color::C256(238));
+        // 3x3 window starting at (4, 3), plus indicators
+        assert_eq!(view.width, 4);
+        assert_eq!(view.pixels, vec!(
+            Color::BLACK, Color::BLACK, Color::BLACK, off,
+            Color::WHITE, Color::BLACK, Color::BLACK, on,
+            Color::BLACK, Color::WHITE, Color::BLACK, on,
+            off, on, on, Color::BLACK,
+        ));
+    }
+}